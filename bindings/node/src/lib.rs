@@ -0,0 +1,193 @@
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::JsFunction;
+use napi_derive::napi;
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use swc_common::SourceMap;
+
+use dx::config::{Config, SourceSyntax, CONFIG_FILE};
+use dx::extract::{collect_from_file, extract_from_source, Options};
+use dx::Scanner;
+
+#[napi(object)]
+pub struct ScanResult {
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+#[napi(object)]
+pub struct TransformOptions {
+    pub groups: Option<HashMap<String, String>>,
+    pub syntax: Option<String>,
+    pub attributes: Option<Vec<String>>,
+    pub helpers: Option<Vec<String>>,
+    pub group_transform: Option<bool>,
+    pub id_generation: Option<bool>,
+    pub obfuscate: Option<bool>,
+}
+
+#[napi(object)]
+pub struct TransformResult {
+    pub code: String,
+    pub modified: bool,
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+}
+
+#[napi(object)]
+pub struct CssUpdate {
+    pub path: String,
+    pub css: String,
+    pub classnames: u32,
+    pub ids: u32,
+}
+
+fn project_options() -> Options {
+    Options::from_config(&Config::load(Path::new(CONFIG_FILE)))
+}
+
+fn resolve_options(overrides: Option<TransformOptions>) -> Options {
+    let mut options = project_options();
+    let Some(overrides) = overrides else {
+        return options;
+    };
+
+    if let Some(groups) = overrides.groups {
+        options.groups = groups;
+    }
+    if let Some(syntax) = overrides.syntax {
+        options.syntax = match syntax.as_str() {
+            "jsx" => SourceSyntax::Jsx,
+            _ => SourceSyntax::Tsx,
+        };
+    }
+    if let Some(attributes) = overrides.attributes {
+        options.attributes = attributes;
+    }
+    if let Some(helpers) = overrides.helpers {
+        options.helpers = helpers;
+    }
+    if let Some(group_transform) = overrides.group_transform {
+        options.transform_groups = group_transform;
+    }
+    if let Some(id_generation) = overrides.id_generation {
+        options.generate_ids = id_generation;
+    }
+    if let Some(obfuscate) = overrides.obfuscate {
+        options.obfuscate = obfuscate;
+    }
+    options
+}
+
+/// Extracts classes and ids from the given files without rewriting them.
+#[napi]
+pub fn scan(paths: Vec<String>) -> ScanResult {
+    let options = project_options();
+    let cm: Arc<SourceMap> = Default::default();
+
+    let results: Vec<_> = paths
+        .par_iter()
+        .map(|path| (path, collect_from_file(&PathBuf::from(path), &cm, &options)))
+        .collect();
+
+    let mut classnames = BTreeSet::new();
+    let mut ids = BTreeSet::new();
+    let mut failed = Vec::new();
+    for (path, result) in results {
+        match result {
+            Some((file_classnames, file_ids)) => {
+                classnames.extend(file_classnames);
+                ids.extend(file_ids);
+            }
+            None => failed.push(path.clone()),
+        }
+    }
+
+    ScanResult {
+        classnames: classnames.into_iter().collect(),
+        ids: ids.into_iter().collect(),
+        failed,
+    }
+}
+
+/// Runs the full rewrite pipeline over an in-memory source.
+#[napi]
+pub fn transform(source: String, options: Option<TransformOptions>) -> Result<TransformResult> {
+    let options = resolve_options(options);
+    let extraction = extract_from_source(&source, &options)
+        .ok_or_else(|| Error::new(Status::InvalidArg, "Failed to parse source".to_string()))?;
+
+    let mut classnames: Vec<_> = extraction.classnames.iter().cloned().collect();
+    classnames.sort();
+    let mut ids: Vec<_> = extraction.ids.iter().cloned().collect();
+    ids.sort();
+
+    Ok(TransformResult {
+        modified: extraction.is_modified(),
+        code: extraction.code,
+        classnames,
+        ids,
+    })
+}
+
+/// Indexes the project in the current directory (without rewriting any file)
+/// and returns the stylesheet the CLI would generate.
+#[napi]
+pub fn css() -> String {
+    let mut scanner = Scanner::new(Config::load(Path::new(CONFIG_FILE)));
+    scanner.index();
+    scanner.css()
+}
+
+#[napi]
+pub struct Subscription {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[napi]
+impl Subscription {
+    /// Stops watching and waits for the watcher thread to exit.
+    #[napi]
+    pub fn close(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Scans the project in the current directory, then watches ./src and calls
+/// `callback` with the new stylesheet whenever it changes.
+#[napi(ts_args_type = "callback: (update: CssUpdate) => void")]
+pub fn subscribe(callback: JsFunction) -> Result<Subscription> {
+    let tsfn: ThreadsafeFunction<CssUpdate, ErrorStrategy::Fatal> =
+        callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let handle = thread::spawn(move || {
+        let mut scanner = Scanner::new(Config::load(Path::new(CONFIG_FILE)));
+        scanner.initial_scan();
+        dx::watch::watch(&mut scanner, &thread_stop, |scanner, summary| {
+            let update = CssUpdate {
+                path: summary.path.to_string_lossy().to_string(),
+                css: scanner.css(),
+                classnames: scanner.classnames.len() as u32,
+                ids: scanner.ids.len() as u32,
+            };
+            tsfn.call(update, ThreadsafeFunctionCallMode::NonBlocking);
+        });
+    });
+
+    Ok(Subscription {
+        stop,
+        handle: Some(handle),
+    })
+}
@@ -0,0 +1,44 @@
+#ifndef DX_H
+#define DX_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DX_OK 0
+#define DX_PARSE_ERROR 1
+#define DX_INVALID_ARGUMENT 2
+
+typedef struct DxStringArray {
+    char **items;
+    size_t len;
+} DxStringArray;
+
+typedef struct DxExtraction {
+    int32_t status;
+    bool modified;
+    char *code;
+    DxStringArray classnames;
+    DxStringArray ids;
+    char *error;
+} DxExtraction;
+
+/* config_toml may be NULL. Free the result with dx_extraction_free. */
+DxExtraction *dx_extract(const char *source, const char *config_toml);
+
+/* Free the result with dx_string_free. */
+char *dx_generate_css(const DxExtraction *extraction);
+
+void dx_extraction_free(DxExtraction *extraction);
+void dx_string_free(char *value);
+const char *dx_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
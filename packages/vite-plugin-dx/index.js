@@ -0,0 +1,48 @@
+import { css, subscribe } from "@dx/styles-node";
+
+const VIRTUAL_MODULE_ID = "virtual:dx-styles.css";
+const RESOLVED_VIRTUAL_MODULE_ID = "\0virtual:dx-styles.css";
+
+// Usage: import "virtual:dx-styles.css";
+export default function dxStyles() {
+  let current = null;
+  let subscription = null;
+
+  return {
+    name: "dx-styles",
+    enforce: "pre",
+
+    resolveId(id) {
+      if (id === VIRTUAL_MODULE_ID) {
+        return RESOLVED_VIRTUAL_MODULE_ID;
+      }
+    },
+
+    load(id) {
+      if (id === RESOLVED_VIRTUAL_MODULE_ID) {
+        if (current === null) {
+          current = css();
+        }
+        return current;
+      }
+    },
+
+    configureServer(server) {
+      subscription = subscribe((update) => {
+        current = update.css;
+        const mod = server.moduleGraph.getModuleById(RESOLVED_VIRTUAL_MODULE_ID);
+        if (mod) {
+          server.reloadModule(mod);
+        }
+      });
+      server.httpServer?.once("close", () => subscription?.close());
+    },
+
+    buildEnd() {
+      if (subscription && !this.meta.watchMode) {
+        subscription.close();
+        subscription = null;
+      }
+    },
+  };
+}
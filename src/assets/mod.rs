@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cache::content_hash;
+
+pub const ASSET_MANIFEST_FILE: &str = "dx-assets.json";
+
+/// Logical stylesheet name (e.g. `styles.css`) to its content-addressed file
+/// (`styles.3f2a9c1e.css`), both relative to the project root with `/` separators.
+pub type AssetManifest = BTreeMap<String, String>;
+
+/// `path` with the hash of `content` before its extension: `styles.css` becomes
+/// `styles.3f2a9c1e.css`.
+pub fn hashed_path(path: &Path, content: &str) -> PathBuf {
+    let hash = format!("{:08x}", content_hash(content.as_bytes()) as u32);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, hash, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, hash),
+    };
+    path.with_file_name(name)
+}
+
+pub fn asset_name(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+pub fn read_asset_manifest(path: &Path) -> AssetManifest {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_asset_manifest(path: &Path, manifest: &AssetManifest) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(manifest)?;
+    std::fs::write(path, json)
+}
+
+/// A precompressed copy written next to each stylesheet for static hosts to serve as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Brotli,
+    Gzip,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 2] = [Compression::Brotli, Compression::Gzip];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Brotli => "br",
+            Compression::Gzip => "gz",
+        }
+    }
+
+    /// `path` with this format's extension appended: `styles.css` becomes `styles.css.br`.
+    pub fn path(self, path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(self.extension());
+        path.with_file_name(name)
+    }
+
+    /// `content` compressed at the highest level, since it is compressed once and served often.
+    pub fn compress(self, content: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Brotli => {
+                let mut compressed = Vec::new();
+                let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                writer.write_all(content)?;
+                writer.flush()?;
+                drop(writer);
+                Ok(compressed)
+            }
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(content)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_path_inserts_the_content_hash() {
+        let path = hashed_path(Path::new("/project/styles.css"), ".a { color: red; }");
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(path.parent(), Some(Path::new("/project")));
+        assert!(name.starts_with("styles.") && name.ends_with(".css") && name.len() == "styles.12345678.css".len());
+        assert_eq!(path, hashed_path(Path::new("/project/styles.css"), ".a { color: red; }"));
+        assert_ne!(path, hashed_path(Path::new("/project/styles.css"), ".a { color: blue; }"));
+    }
+
+    #[test]
+    fn asset_names_are_relative() {
+        assert_eq!(asset_name(Path::new("/project/dist/app.css"), Path::new("/project")), "dist/app.css");
+    }
+
+    #[test]
+    fn compressed_copies() {
+        assert_eq!(Compression::Brotli.path(Path::new("styles.css")), Path::new("styles.css.br"));
+        assert_eq!(Compression::Gzip.path(Path::new("styles.css")), Path::new("styles.css.gz"));
+        let css = ".a { color: red; }\n".repeat(100);
+        let gzip = Compression::Gzip.compress(css.as_bytes()).unwrap();
+        assert!(gzip.starts_with(&[0x1f, 0x8b]));
+        assert!(gzip.len() < css.len());
+        assert!(Compression::Brotli.compress(css.as_bytes()).unwrap().len() < css.len());
+    }
+}
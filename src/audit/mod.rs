@@ -0,0 +1,45 @@
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use crate::stylesheet::{selector_classes, selector_ids, Rule};
+
+/// `:not(...)` matches when its argument is absent, so names inside it never make a selector dead.
+static NEGATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r":not\([^)]*\)").unwrap());
+
+/// A selector referencing classes or ids that never appear in scanned sources.
+#[derive(Debug, Clone)]
+pub struct UnusedSelector {
+    pub selector: String,
+    /// Zero-based line of the rule in the stylesheet.
+    pub line: usize,
+    pub missing_classes: Vec<String>,
+    pub missing_ids: Vec<String>,
+}
+
+/// Selectors from `rules` that cannot match any element, given the classes and ids used in sources.
+///
+/// Selectors without any class or id (element, attribute, `:root`, ...) are never reported.
+pub fn unused_selectors(rules: &[Rule], classnames: &HashSet<String>, ids: &HashSet<String>) -> Vec<UnusedSelector> {
+    let mut unused = Vec::new();
+    for rule in rules {
+        for selector in rule.selectors() {
+            let matched = NEGATION.replace_all(selector, "");
+            let missing_classes: Vec<_> = selector_classes(&matched)
+                .into_iter()
+                .filter(|c| !classnames.contains(c))
+                .collect();
+            let missing_ids: Vec<_> = selector_ids(&matched).into_iter().filter(|id| !ids.contains(id)).collect();
+            if missing_classes.is_empty() && missing_ids.is_empty() {
+                continue;
+            }
+            unused.push(UnusedSelector {
+                selector: selector.to_string(),
+                line: rule.line,
+                missing_classes,
+                missing_ids,
+            });
+        }
+    }
+    unused
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::report::Finding;
+
+pub const BASELINE_FILE: &str = ".dx/baseline.json";
+
+/// Findings with the same code, file and message, accepted as they were when the baseline was
+/// written. Lines are left out so edits elsewhere in the file do not resurface them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub code: String,
+    /// Relative to the project root.
+    pub path: PathBuf,
+    pub message: String,
+    pub count: usize,
+}
+
+/// Lint findings that existed before a project adopted the lint, kept in `.dx/baseline.json`
+/// so only new ones fail `dx lint` and `dx duplicates`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub entries: Vec<BaselineEntry>,
+}
+
+fn key<'a>(finding: &'a Finding, root: &Path) -> (&'a str, PathBuf, &'a str) {
+    let path = finding.path.strip_prefix(root).unwrap_or(&finding.path).to_path_buf();
+    (finding.code, path, finding.message.as_str())
+}
+
+impl Baseline {
+    pub fn load(root: &Path) -> Self {
+        std::fs::read_to_string(root.join(BASELINE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let path = root.join(BASELINE_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Replaces the entries for `code` with `findings`, leaving other lints' entries alone.
+    pub fn update(&mut self, code: &str, findings: &[Finding], root: &Path) {
+        self.entries.retain(|entry| entry.code != code);
+        let mut counts: HashMap<(&str, PathBuf, &str), usize> = HashMap::new();
+        for finding in findings.iter().filter(|finding| finding.code == code) {
+            *counts.entry(key(finding, root)).or_default() += 1;
+        }
+        self.entries.extend(counts.into_iter().map(|((code, path, message), count)| BaselineEntry {
+            code: code.to_string(),
+            path,
+            message: message.to_string(),
+            count,
+        }));
+        self.entries.sort_by(|a, b| (&a.code, &a.path, &a.message).cmp(&(&b.code, &b.path, &b.message)));
+    }
+
+    /// Whether each of `findings` is new. A finding matching an entry uses up one of its count,
+    /// so a file with one more occurrence than the baseline reports the extra one.
+    pub fn is_new(&self, findings: &[Finding], root: &Path) -> Vec<bool> {
+        let mut remaining: HashMap<(&str, PathBuf, &str), usize> = self
+            .entries
+            .iter()
+            .map(|entry| ((entry.code.as_str(), entry.path.clone(), entry.message.as_str()), entry.count))
+            .collect();
+        findings
+            .iter()
+            .map(|finding| match remaining.get_mut(&key(finding, root)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Level;
+
+    fn finding(code: &'static str, path: &str, line: usize) -> Finding {
+        Finding {
+            code,
+            level: Level::Warning,
+            path: PathBuf::from(path),
+            line,
+            column: 1,
+            message: "duplicate".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_findings_beyond_the_baseline_are_new() {
+        let root = Path::new("/project");
+        let mut baseline = Baseline::default();
+        baseline.update("DX0205", &[finding("DX0205", "/project/src/a.tsx", 3)], root);
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.entries[0].path, PathBuf::from("src/a.tsx"));
+
+        let findings = [
+            finding("DX0205", "/project/src/a.tsx", 10),
+            finding("DX0205", "/project/src/a.tsx", 12),
+            finding("DX0201", "/project/src/a.tsx", 3),
+        ];
+        assert_eq!(baseline.is_new(&findings, root), [false, true, true]);
+    }
+
+    #[test]
+    fn update_keeps_other_codes() {
+        let root = Path::new("/project");
+        let mut baseline = Baseline::default();
+        baseline.update("DX0201", &[finding("DX0201", "/project/src/a.tsx", 1)], root);
+        baseline.update("DX0205", &[], root);
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.entries[0].code, "DX0201");
+    }
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+pub const CACHE_FILE: &str = ".dx/cache.json";
+pub const SNAPSHOT_FILE: &str = ".dx/snapshot.json";
+pub const APPROVALS_FILE: &str = ".dx/approved.json";
+const CACHE_VERSION: u32 = 1;
+
+/// 64-bit FNV-1a, stable across runs and platforms.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Hashes every config field that affects extraction, so a config edit invalidates the cache.
+pub fn config_fingerprint(config: &Config) -> u64 {
+    let mut groups: Vec<_> = config.groups.iter().collect();
+    groups.sort();
+    let mut renames: Vec<_> = config.rename.iter().collect();
+    renames.sort();
+    let mut patterns: Vec<_> = config.plugins.patterns.iter().collect();
+    patterns.sort();
+    let canonical = format!(
+        "{}|{:?}|{}|{:?}|{:?}|{}|{:?}|{}|{:?}|{:?}|{}|{:?}",
+        env!("CARGO_PKG_VERSION"),
+        groups,
+        config.syntax.extension(),
+        config.attributes,
+        config.helpers,
+        config.features.groups,
+        config.plugins.extractors,
+        config.plugins.id_strategy,
+        patterns,
+        config.id_references,
+        config.features.inline_styles,
+        renames,
+    );
+    content_hash(canonical.as_bytes())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub hash: u64,
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+}
+
+impl CacheEntry {
+    pub fn new(hash: u64, classnames: &HashSet<String>, ids: &HashSet<String>) -> Self {
+        let mut classnames: Vec<_> = classnames.iter().cloned().collect();
+        classnames.sort();
+        let mut ids: Vec<_> = ids.iter().cloned().collect();
+        ids.sort();
+        CacheEntry { hash, classnames, ids }
+    }
+
+    pub fn entities(&self) -> (HashSet<String>, HashSet<String>) {
+        (self.classnames.iter().cloned().collect(), self.ids.iter().cloned().collect())
+    }
+}
+
+/// A shared store of extraction results keyed by config fingerprint and content hash, e.g. for CI runners.
+pub trait RemoteCache: Send + Sync {
+    fn name(&self) -> &str;
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+    fn put(&self, key: &str, entry: &CacheEntry);
+}
+
+impl std::fmt::Debug for dyn RemoteCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RemoteCache({})", self.name())
+    }
+}
+
+/// The remote key for a file's content under a given config.
+pub fn remote_key(fingerprint: u64, hash: u64) -> String {
+    format!("{:016x}{:016x}", fingerprint, hash)
+}
+
+/// Per-file extraction results keyed by content hash, persisted between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cache {
+    version: u32,
+    fingerprint: u64,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl Cache {
+    pub fn new(fingerprint: u64) -> Self {
+        Cache {
+            version: CACHE_VERSION,
+            fingerprint,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the cache at `path`, starting empty if it is missing, unreadable or was built with another config.
+    pub fn load(path: &Path, fingerprint: u64) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Cache>(&json).ok())
+            .filter(|cache| cache.version == CACHE_VERSION && cache.fingerprint == fingerprint)
+            .unwrap_or_else(|| Cache::new(fingerprint))
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// The cached classes and ids for `path`, if its content still hashes to `hash`.
+    pub fn get(&self, path: &Path, hash: u64) -> Option<(HashSet<String>, HashSet<String>)> {
+        let entry = self.entries.get(path).filter(|entry| entry.hash == hash)?;
+        Some(entry.entities())
+    }
+
+    /// The content hash `path`'s entry was stored under.
+    pub fn hash(&self, path: &Path) -> Option<u64> {
+        Some(self.entries.get(path)?.hash)
+    }
+
+    /// The cached classes and ids for `path` without checking its content.
+    pub fn get_unchecked(&self, path: &Path) -> Option<(HashSet<String>, HashSet<String>)> {
+        Some(self.entries.get(path)?.entities())
+    }
+
+    pub fn insert(&mut self, path: PathBuf, hash: u64, classnames: &HashSet<String>, ids: &HashSet<String>) {
+        self.entries.insert(path, CacheEntry::new(hash, classnames, ids));
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// Drops entries for files that are no longer scanned.
+    pub fn retain(&mut self, keep: impl Fn(&Path) -> bool) {
+        self.entries.retain(|path, _| keep(path));
+    }
+}
+
+/// The scanner's state when `dx watch` last exited, so the next start can skip extraction and
+/// globals when no source (nor the stylesheet) changed since.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    fingerprint: u64,
+    /// Content hash of the stylesheet the global sets were rendered to.
+    pub stylesheet_hash: u64,
+    pub files: HashMap<PathBuf, CacheEntry>,
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+}
+
+impl Snapshot {
+    pub fn new(
+        fingerprint: u64,
+        stylesheet_hash: u64,
+        files: HashMap<PathBuf, CacheEntry>,
+        classnames: &HashSet<String>,
+        ids: &HashSet<String>,
+    ) -> Self {
+        let globals = CacheEntry::new(stylesheet_hash, classnames, ids);
+        Snapshot {
+            version: CACHE_VERSION,
+            fingerprint,
+            stylesheet_hash,
+            files,
+            classnames: globals.classnames,
+            ids: globals.ids,
+        }
+    }
+
+    /// The snapshot at `path`, unless it is missing, unreadable or was taken with another config.
+    pub fn load(path: &Path, fingerprint: u64) -> Option<Self> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Snapshot>(&json).ok())
+            .filter(|snapshot| snapshot.version == CACHE_VERSION && snapshot.fingerprint == fingerprint)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+}
+
+/// Sources, relative to the project root, whose rewrites the user approved under `--interactive`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Approvals {
+    pub files: BTreeSet<PathBuf>,
+}
+
+impl Approvals {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,115 @@
+use glob::{glob, Pattern};
+use std::path::{Path, PathBuf};
+
+use crate::config::{ConfigOverride, CONFIG_FILE};
+use crate::paths;
+
+/// The `dx.config.toml` files nested below a project's `src/`, resolved per file like ESLint's
+/// cascading configs: the deepest config containing a file wins for each setting it sets.
+#[derive(Debug, Clone, Default)]
+pub struct Cascade {
+    /// Canonical config directories with their overrides, shallowest first.
+    configs: Vec<(PathBuf, ConfigOverride)>,
+}
+
+impl Cascade {
+    pub fn discover(root: &Path) -> Cascade {
+        let src = Pattern::escape(&root.join("src").to_string_lossy());
+        let mut configs: Vec<(PathBuf, ConfigOverride)> = glob(&format!("{}/**/{}", src, CONFIG_FILE))
+            .map(|paths| paths.filter_map(Result::ok).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|path| {
+                let dir = paths::canonicalize(path.parent()?).ok()?;
+                Some((dir, ConfigOverride::load(&path)?))
+            })
+            .collect();
+        configs.sort_by_key(|(dir, _)| (dir.components().count(), dir.clone()));
+        Cascade { configs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.configs.is_empty()
+    }
+
+    /// The configs that apply to `path`, shallowest first.
+    fn applicable<'a, 'p>(
+        &'a self,
+        path: &'p Path,
+    ) -> impl DoubleEndedIterator<Item = &'a (PathBuf, ConfigOverride)> + use<'a, 'p> {
+        self.configs.iter().filter(move |(dir, _)| path.starts_with(dir))
+    }
+
+    /// The id trigger class for `path`, if a config above it overrides the root one.
+    pub fn id_trigger(&self, path: &Path) -> Option<&str> {
+        self.applicable(path).rev().find_map(|(_, config)| config.id_trigger.as_deref())
+    }
+
+    /// Whether `path` passes the include globs of its nearest config setting them and the exclude
+    /// globs of every config above it. Globs are relative to the directory of their config.
+    pub fn includes(&self, path: &Path) -> bool {
+        let matches = |dir: &Path, globs: &[String]| {
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            globs.iter().any(|glob| {
+                Pattern::new(glob.trim_start_matches("./")).is_ok_and(|pattern| pattern.matches_path(relative))
+            })
+        };
+        let included = self
+            .applicable(path)
+            .rev()
+            .find_map(|(dir, config)| config.include.as_ref().map(|globs| matches(dir, globs)))
+            .unwrap_or(true);
+        included
+            && !self
+                .applicable(path)
+                .any(|(dir, config)| config.exclude.as_ref().is_some_and(|globs| matches(dir, globs)))
+    }
+
+    /// Whether no file below `dir` can pass [`Cascade::includes`], so it need not be watched:
+    /// an exclude glob covers the whole directory, or no include glob can reach into it.
+    pub fn excludes_dir(&self, dir: &Path) -> bool {
+        let relative = |base: &Path| dir.strip_prefix(base).unwrap_or(dir).to_path_buf();
+        let excluded = self.applicable(dir).any(|(base, config)| {
+            config.exclude.as_ref().is_some_and(|globs| {
+                globs.iter().any(|glob| {
+                    let glob = glob.trim_start_matches("./");
+                    let subtree = glob.strip_suffix("/**/*").or_else(|| glob.strip_suffix("/**")).unwrap_or(glob);
+                    Pattern::new(subtree).is_ok_and(|pattern| pattern.matches_path(&relative(base)))
+                })
+            })
+        });
+        let reachable = self
+            .applicable(dir)
+            .rev()
+            .find_map(|(base, config)| {
+                let relative = relative(base);
+                config.include.as_ref().map(|globs| {
+                    globs.iter().any(|glob| {
+                        // The directories before the first wildcard.
+                        let literal: PathBuf = Path::new(glob.trim_start_matches("./"))
+                            .components()
+                            .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+                            .collect();
+                        literal.starts_with(&relative) || relative.starts_with(&literal)
+                    })
+                })
+            })
+            .unwrap_or(true);
+        excluded || !reachable
+    }
+
+    /// The stylesheet `path`'s classes go to instead of the root output, if a config sets one.
+    pub fn output(&self, path: &Path) -> Option<PathBuf> {
+        self.applicable(path)
+            .rev()
+            .find_map(|(dir, config)| config.output.as_ref().map(|output| dir.join(output)))
+    }
+
+    /// Every stylesheet set by a nested config.
+    pub fn outputs(&self) -> Vec<PathBuf> {
+        self.configs
+            .iter()
+            .filter_map(|(dir, config)| config.output.as_ref().map(|output| dir.join(output)))
+            .collect()
+    }
+}
@@ -0,0 +1,145 @@
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{FileName, SourceMap};
+use swc_ecma_visit::VisitMutWith;
+
+use crate::codes::EMIT_FAILURE;
+use crate::extract::{emit_module, parse_module, Diagnostic, Options, Pragma};
+use crate::obfuscate::ClassRenamer;
+use crate::utility::parse_class;
+
+/// What a mapping file entry does with its class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassEdit {
+    /// `"btn-old": "btn"`
+    Rename(String),
+    /// `"card": "rounded shadow p-4"`
+    Split(Vec<String>),
+    /// `"legacy": null` or `"legacy": ""`
+    Remove,
+}
+
+/// Class edits read from a mapping file such as `map.json` for `dx codemod`.
+#[derive(Debug, Clone, Default)]
+pub struct Codemod {
+    pub edits: HashMap<String, ClassEdit>,
+}
+
+/// How many class occurrences a codemod changed in one file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodemodStats {
+    pub renamed: usize,
+    pub split: usize,
+    pub removed: usize,
+}
+
+impl CodemodStats {
+    pub fn total(&self) -> usize {
+        self.renamed + self.split + self.removed
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileCodemod {
+    pub code: String,
+    pub stats: CodemodStats,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Classes(String),
+    Remove(()),
+}
+
+impl Codemod {
+    pub fn parse(json: &str) -> Result<Codemod, String> {
+        let entries: HashMap<String, Entry> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let edits = entries
+            .into_iter()
+            .map(|(class, entry)| {
+                let classes: Vec<String> = match entry {
+                    Entry::Classes(classes) => classes.split_whitespace().map(str::to_string).collect(),
+                    Entry::Remove(()) => Vec::new(),
+                };
+                let edit = match classes.len() {
+                    0 => ClassEdit::Remove,
+                    1 => ClassEdit::Rename(classes.into_iter().next().unwrap_or_default()),
+                    _ => ClassEdit::Split(classes),
+                };
+                (class, edit)
+            })
+            .collect();
+        Ok(Codemod { edits })
+    }
+
+    pub fn read(path: &Path) -> Result<Codemod, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Codemod::parse(&json)
+    }
+
+    /// The edit for `classname` and the variants (`hover:`) in front of it. An edit of `card` also
+    /// applies under variants, so `md:card` splits into `md:rounded md:shadow md:p-4`.
+    fn edit<'a>(&'a self, classname: &'a str) -> Option<(&'a str, &'a ClassEdit)> {
+        if let Some(edit) = self.edits.get(classname) {
+            return Some(("", edit));
+        }
+        let utility = parse_class(classname).utility;
+        let edit = self.edits.get(utility)?;
+        Some((&classname[..classname.len() - utility.len()], edit))
+    }
+
+    /// Applies the edits to the class attributes and helper calls of `source`, returning the
+    /// rewritten code, or `None` when nothing changed or the file opts out with `/* dx-disable */`.
+    pub fn apply(&self, path: &Path, source: &str, options: &Options) -> Result<Option<FileCodemod>, Diagnostic> {
+        let Some(options) = Pragma::parse(source).apply(options) else {
+            return Ok(None);
+        };
+        let cm: Arc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source.to_string());
+        let comments = SingleThreadedComments::default();
+        let (mut module, _) = parse_module(&fm, options.syntax, Some(&comments))?;
+
+        let (renamed, split, removed) = (Cell::new(0), Cell::new(0), Cell::new(0));
+        let rename = |classname: &str| {
+            let Some((variants, edit)) = self.edit(classname) else {
+                return classname.to_string();
+            };
+            match edit {
+                ClassEdit::Rename(class) => {
+                    renamed.set(renamed.get() + 1);
+                    format!("{}{}", variants, class)
+                }
+                ClassEdit::Split(classes) => {
+                    split.set(split.get() + 1);
+                    classes.iter().map(|class| format!("{}{}", variants, class)).collect::<Vec<_>>().join(" ")
+                }
+                ClassEdit::Remove => {
+                    removed.set(removed.get() + 1);
+                    String::new()
+                }
+            }
+        };
+        module.visit_mut_with(&mut ClassRenamer {
+            attributes: &options.attributes,
+            helpers: &options.helpers,
+            rename: &rename,
+        });
+
+        let stats = CodemodStats { renamed: renamed.get(), split: split.get(), removed: removed.get() };
+        if stats.total() == 0 {
+            return Ok(None);
+        }
+        let code = emit_module(&cm, &module, &comments).ok_or_else(|| Diagnostic {
+            code: EMIT_FAILURE,
+            message: "Failed to emit the rewritten module".to_string(),
+            start: 0,
+            end: 0,
+        })?;
+        Ok(Some(FileCodemod { code, stats }))
+    }
+}
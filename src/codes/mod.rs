@@ -0,0 +1,184 @@
+/// A stable diagnostic code with the cause and fix printed by `dx explain`.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub explanation: &'static str,
+}
+
+pub const PARSE_FAILURE: &str = "DX0001";
+pub const UNREADABLE_FILE: &str = "DX0002";
+pub const EMIT_FAILURE: &str = "DX0003";
+pub const WRITE_FAILURE: &str = "DX0004";
+pub const OVERSIZED_FILE: &str = "DX0005";
+pub const BINARY_FILE: &str = "DX0006";
+pub const STALE_SOURCE: &str = "DX0007";
+pub const UNSTABLE_REWRITE: &str = "DX0008";
+pub const UNKNOWN_GROUP: &str = "DX0101";
+pub const ID_CONFLICT: &str = "DX0102";
+pub const ID_REFERENCED: &str = "DX0103";
+pub const CONFLICTING_UTILITIES: &str = "DX0201";
+pub const UNRESOLVED_CLASS: &str = "DX0202";
+pub const UNUSED_SELECTOR: &str = "DX0203";
+pub const STALE_STYLESHEET: &str = "DX0204";
+pub const DUPLICATE_CLASS_SET: &str = "DX0205";
+pub const INVALID_CONFIG: &str = "DX0301";
+pub const UNKNOWN_PLUGIN: &str = "DX0302";
+pub const INVALID_PATTERN: &str = "DX0303";
+pub const CSS_TRANSFORM_FAILED: &str = "DX0304";
+
+pub const CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: PARSE_FAILURE,
+        title: "Source file failed to parse",
+        explanation: "The file is not valid TSX/JSX for the configured `syntax`, so its classes and ids are left out of \
+styles.css until it parses again.\n\nFix the reported syntax error, or set `syntax = \"jsx\"` in dx.config.toml if the \
+project is plain JavaScript.",
+    },
+    ErrorCode {
+        code: UNREADABLE_FILE,
+        title: "Source file could not be read",
+        explanation: "The file disappeared between being listed and being read, or the current user lacks permission \
+to read it.\n\nCheck the file's permissions; it is picked up again on its next change.",
+    },
+    ErrorCode {
+        code: EMIT_FAILURE,
+        title: "Rewritten source could not be generated",
+        explanation: "The file parsed, but printing it back after expanding groups or assigning ids failed, so it was \
+not rewritten.\n\nPlease report the file contents as a bug.",
+    },
+    ErrorCode {
+        code: WRITE_FAILURE,
+        title: "File could not be written",
+        explanation: "A rewritten source or a stylesheet could not be saved, usually because the file is read-only \
+or the current user lacks permission to write it. Other files keep being processed.\n\nFix the file's permissions; \
+the write is retried on the next change.",
+    },
+    ErrorCode {
+        code: OVERSIZED_FILE,
+        title: "Source file skipped for its size",
+        explanation: "The file is larger than `max_file_size` (2 MiB by default), which usually means it is generated, \
+so it is neither parsed nor rewritten and its classes are left out of styles.css.\n\nExclude generated files from \
+`src/`, or raise `max_file_size` (in bytes) in dx.config.toml if the file really is handwritten.",
+    },
+    ErrorCode {
+        code: BINARY_FILE,
+        title: "Source file skipped as binary",
+        explanation: "The file has a source extension but contains NUL bytes, so it is most likely a misnamed binary \
+and is skipped.\n\nRename or move the file out of `src/`.",
+    },
+    ErrorCode {
+        code: STALE_SOURCE,
+        title: "Source would be rewritten",
+        explanation: "`dx check` found a source that still has classes carrying the `id` trigger or unexpanded \
+groups, so the next build would rewrite it.\n\nRun `dx build` and commit the rewritten file.",
+    },
+    ErrorCode {
+        code: UNSTABLE_REWRITE,
+        title: "Rewrite is not idempotent",
+        explanation: "`dx fmt-check` rewrote a source twice and the second pass changed it again, so every build \
+would keep touching the file.\n\nPlease report the file contents as a bug.",
+    },
+    ErrorCode {
+        code: UNKNOWN_GROUP,
+        title: "Unknown config group",
+        explanation: "A class list references `name()` with empty parentheses, which expands a group from the \
+`[groups]` table, but no group with that name is defined.\n\nAdd `name = \"classes ...\"` under `[groups]` in \
+dx.config.toml, or write the group inline as `name(class+class)`.",
+    },
+    ErrorCode {
+        code: ID_CONFLICT,
+        title: "Id used by more than one element",
+        explanation: "Two elements in the same file end up with the same id, so `#id` rules and fragment links match \
+ambiguously.\n\nRename one of the hand-written ids, or let dx manage both by adding the `id` class to each element.",
+    },
+    ErrorCode {
+        code: ID_REFERENCED,
+        title: "Referenced id kept",
+        explanation: "dx would rename a generated id, but `htmlFor`, `aria-labelledby`, `aria-describedby` or \
+`href=\"#...\"` still point at the old one, so renaming it would break labels and links. The element keeps its old \
+id.\n\nReferences in another file are only rewritten with `id_references = \"project\"`: otherwise point them at \
+the new id, or give the element a fixed id without the `id` class. References in the same file are updated unless \
+`id_references = \"refuse\"`.",
+    },
+    ErrorCode {
+        code: CONFLICTING_UTILITIES,
+        title: "Conflicting utilities on one element",
+        explanation: "An element carries several utilities that set the same property under the same variants, such \
+as `p-2 p-4` or `flex grid`; which one wins depends on stylesheet order, not the class list.\n\nKeep one of them, or \
+move the other behind a variant like `md:`. Configure with `[lint] conflicts = \"off\" | \"warn\" | \"error\"`.",
+    },
+    ErrorCode {
+        code: UNRESOLVED_CLASS,
+        title: "Class does not resolve to any declarations",
+        explanation: "The class is not defined by any configured stylesheet and generates an empty rule in \
+styles.css.\n\nCheck for a typo, or add the class to a stylesheet matched by `stylesheets` in dx.config.toml.",
+    },
+    ErrorCode {
+        code: UNUSED_SELECTOR,
+        title: "Stylesheet selector is never used",
+        explanation: "`dx audit` found a selector whose classes or ids never appear in any scanned source, so it \
+cannot match.\n\nDelete the rule, or check whether the class is built dynamically in a way dx cannot see.",
+    },
+    ErrorCode {
+        code: STALE_STYLESHEET,
+        title: "Stylesheet is out of date",
+        explanation: "`dx check` rendered the stylesheet from the current sources and it has selectors the file on \
+disk lacks, or lacks selectors the file has.\n\nRun `dx build` and commit styles.css.",
+    },
+    ErrorCode {
+        code: DUPLICATE_CLASS_SET,
+        title: "Class set repeated across elements",
+        explanation: "`dx duplicates` found elements that share most of their classes, which usually means the \
+same component is styled by hand in several places.\n\nAdd the shared classes as a group under `[groups]` in \
+dx.config.toml and use `name()` on each element.",
+    },
+    ErrorCode {
+        code: INVALID_CONFIG,
+        title: "dx.config.toml could not be parsed",
+        explanation: "The config file is not valid TOML, has a key dx does not know (often a typo) or has a field \
+of the wrong type. dx falls back to the default config.\n\nFix the reported line and column. `dx config schema` prints \
+a JSON Schema editors can use to flag these while typing.",
+    },
+    ErrorCode {
+        code: UNKNOWN_PLUGIN,
+        title: "Unknown plugin",
+        explanation: "`[plugins]` names an extractor, emitter or id strategy that is neither built in nor \
+registered by the embedding program, so it is ignored.\n\nCheck the spelling, or register the plugin on the \
+PluginRegistry before creating the Scanner.",
+    },
+    ErrorCode {
+        code: INVALID_PATTERN,
+        title: "Invalid extractor pattern",
+        explanation: "A regular expression under `[plugins.patterns]` failed to compile, so that extractor is \
+skipped.\n\nFix the pattern; its first capture group is used as the class name.",
+    },
+    ErrorCode {
+        code: CSS_TRANSFORM_FAILED,
+        title: "CSS transform pass failed",
+        explanation: "`css.targets` or `css.minify` asks for a final pass over the generated stylesheet, but it \
+could not run: a browserslist query in `targets` is invalid, a plugin emitted CSS the pass cannot parse, or dx was \
+built without the `lightningcss` feature. The stylesheet is written untransformed.\n\nFix the query or the \
+emitter, or build dx with `--features lightningcss`.",
+    },
+];
+
+/// Looks up a code such as `DX0102`, ignoring case.
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    CODES.iter().find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_explained() {
+        for (i, entry) in CODES.iter().enumerate() {
+            assert!(CODES[i + 1..].iter().all(|other| other.code != entry.code), "{} is listed twice", entry.code);
+            assert!(!entry.title.is_empty() && !entry.explanation.is_empty());
+        }
+        assert_eq!(explain("dx0001").map(|entry| entry.code), Some(PARSE_FAILURE));
+        assert!(explain("DX9999").is_none());
+    }
+}
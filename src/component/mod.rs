@@ -0,0 +1,320 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use swc_common::{FileName, SourceMap, Span};
+use swc_ecma_ast::{
+    BindingIdent, ClassDecl, Expr, FnDecl, Ident, ImportDecl, ImportSpecifier, JSXAttr, JSXAttrName, JSXAttrOrSpread,
+    JSXAttrValue, JSXElement, JSXElementName, Lit, ModuleDecl, ModuleExportName, ModuleItem, Stmt,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::extract::{parse_module, Options};
+
+/// Which element `dx extract-component` moves out.
+#[derive(Debug, Clone)]
+pub enum ElementSelector {
+    /// The outermost element whose opening tag starts on this 1-based line.
+    Line(usize),
+    /// The element whose `id` attribute is this literal.
+    Id(String),
+}
+
+/// The result of moving a JSX subtree into its own component file.
+#[derive(Debug, Clone)]
+pub struct ComponentExtraction {
+    /// The new component file, next to the original.
+    pub path: PathBuf,
+    pub component: String,
+    /// The original file with the subtree replaced by the component usage.
+    pub source: String,
+    /// Bindings of the original file the subtree uses, passed in as props.
+    pub props: Vec<String>,
+}
+
+struct ElementFinder<'a> {
+    cm: &'a SourceMap,
+    selector: &'a ElementSelector,
+    found: Option<Span>,
+}
+
+fn id_attribute(element: &JSXElement) -> Option<String> {
+    element.opening.attrs.iter().find_map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(JSXAttr {
+            name: JSXAttrName::Ident(ident),
+            value: Some(JSXAttrValue::Lit(Lit::Str(s))),
+            ..
+        }) if ident.sym == "id" => Some(s.value.to_string()),
+        _ => None,
+    })
+}
+
+impl Visit for ElementFinder<'_> {
+    fn visit_jsx_element(&mut self, element: &JSXElement) {
+        if self.found.is_some() {
+            return;
+        }
+        let matches = match self.selector {
+            ElementSelector::Line(line) => self.cm.lookup_char_pos(element.span.lo).line == *line,
+            ElementSelector::Id(id) => id_attribute(element).as_deref() == Some(id.as_str()),
+        };
+        if matches {
+            self.found = Some(element.span);
+            return;
+        }
+        element.visit_children_with(self);
+    }
+}
+
+/// Identifiers a subtree reads, and the ones it binds itself (callback parameters and the like).
+#[derive(Default)]
+struct References {
+    used: HashSet<String>,
+    bound: HashSet<String>,
+}
+
+impl Visit for References {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.used.insert(ident.sym.to_string());
+    }
+
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.bound.insert(ident.id.sym.to_string());
+    }
+
+    fn visit_jsx_element_name(&mut self, name: &JSXElementName) {
+        // Intrinsic elements such as `div` are not references.
+        if let JSXElementName::Ident(ident) = name {
+            if ident.sym.starts_with(|c: char| c.is_ascii_lowercase()) {
+                return;
+            }
+        }
+        name.visit_children_with(self);
+    }
+}
+
+/// Every name the module declares outside its imports.
+#[derive(Default)]
+struct Declarations {
+    names: HashSet<String>,
+}
+
+impl Visit for Declarations {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.names.insert(ident.id.sym.to_string());
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.names.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, decl: &ClassDecl) {
+        self.names.insert(decl.ident.sym.to_string());
+        decl.visit_children_with(self);
+    }
+
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+}
+
+fn import_locals(decl: &ImportDecl) -> impl Iterator<Item = String> + '_ {
+    decl.specifiers.iter().map(|specifier| match specifier {
+        ImportSpecifier::Named(named) => named.local.sym.to_string(),
+        ImportSpecifier::Default(default) => default.local.sym.to_string(),
+        ImportSpecifier::Namespace(namespace) => namespace.local.sym.to_string(),
+    })
+}
+
+/// `decl` narrowed to the specifiers in `names`, or `None` if it binds none of them.
+fn import_line(decl: &ImportDecl, names: &HashSet<String>) -> Option<String> {
+    let keyword = if decl.type_only { "import type" } else { "import" };
+    let mut default = None;
+    let mut namespace = None;
+    let mut named = Vec::new();
+    for specifier in &decl.specifiers {
+        match specifier {
+            ImportSpecifier::Default(s) if names.contains(&*s.local.sym) => default = Some(s.local.sym.to_string()),
+            ImportSpecifier::Namespace(s) if names.contains(&*s.local.sym) => namespace = Some(s.local.sym.to_string()),
+            ImportSpecifier::Named(s) if names.contains(&*s.local.sym) => {
+                let local = &s.local.sym;
+                let mut specifier = match &s.imported {
+                    Some(ModuleExportName::Ident(imported)) if imported.sym != *local => {
+                        format!("{} as {}", imported.sym, local)
+                    }
+                    Some(ModuleExportName::Str(imported)) => format!("\"{}\" as {}", imported.value, local),
+                    _ => local.to_string(),
+                };
+                if s.is_type_only {
+                    specifier.insert_str(0, "type ");
+                }
+                named.push(specifier);
+            }
+            _ => {}
+        }
+    }
+
+    let mut clauses: Vec<String> = default.into_iter().collect();
+    if let Some(namespace) = namespace {
+        clauses.push(format!("* as {}", namespace));
+    }
+    if !named.is_empty() {
+        clauses.push(format!("{{ {} }}", named.join(", ")));
+    }
+    if clauses.is_empty() {
+        return None;
+    }
+    Some(format!("{} {} from \"{}\";", keyword, clauses.join(", "), decl.src.value))
+}
+
+/// Where an import of the new component goes: after the last import, or after leading directives
+/// such as `"use client"`.
+fn import_offset(items: &[ModuleItem], start: u32) -> usize {
+    items
+        .iter()
+        .take_while(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(_)) => true,
+            ModuleItem::Stmt(Stmt::Expr(stmt)) => matches!(&*stmt.expr, Expr::Lit(Lit::Str(_))),
+            _ => false,
+        })
+        .last()
+        .map_or(0, |item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => (import.span.hi.0 - start) as usize,
+            ModuleItem::Stmt(Stmt::Expr(stmt)) => (stmt.span.hi.0 - start) as usize,
+            _ => 0,
+        })
+}
+
+/// `text` with the indentation of its first line's source line removed from the lines after it,
+/// then every line indented by `indent`.
+fn reindent(text: &str, base: &str, indent: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 0 { line } else { line.strip_prefix(base).unwrap_or(line.trim_start()) };
+            if line.trim().is_empty() { String::new() } else { format!("{}{}", indent, line) }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_component_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Moves the JSX subtree selected by `element` in `path` into a new `name` component next to it.
+///
+/// Imports the subtree uses are copied to the new file and names it takes from the original
+/// file's scope become props. Classes and ids travel with the subtree verbatim; rescanning both
+/// files afterwards keeps the stylesheet in sync.
+pub fn extract_component(
+    path: &Path,
+    source: &str,
+    options: &Options,
+    element: &ElementSelector,
+    name: &str,
+) -> Result<ComponentExtraction, String> {
+    if !is_component_name(name) {
+        return Err(format!("`{}` is not a PascalCase component name", name));
+    }
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("tsx");
+    let component_path = path.with_file_name(format!("{}.{}", name, extension));
+
+    let cm: Arc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source.to_string());
+    let (module, _) = parse_module(&fm, options.syntax, None).map_err(|d| d.message)?;
+
+    let mut finder = ElementFinder { cm: &cm, selector: element, found: None };
+    module.visit_with(&mut finder);
+    let span = finder.found.ok_or_else(|| match element {
+        ElementSelector::Line(line) => format!("no element starts on line {}", line),
+        ElementSelector::Id(id) => format!("no element has id=\"{}\"", id),
+    })?;
+    let start = fm.start_pos.0;
+    let (lo, hi) = ((span.lo.0 - start) as usize, (span.hi.0 - start) as usize);
+
+    let mut declarations = Declarations::default();
+    module.visit_with(&mut declarations);
+    let mut references = References::default();
+    module.visit_with(&mut SubtreeVisitor { span, references: &mut references });
+    let free: HashSet<String> = references.used.difference(&references.bound).cloned().collect();
+
+    let imports: Vec<&ImportDecl> = module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(import),
+            _ => None,
+        })
+        .collect();
+    let imported: HashMap<String, &ImportDecl> =
+        imports.iter().flat_map(|decl| import_locals(decl).map(move |local| (local, *decl))).collect();
+    if declarations.names.contains(name) || imported.contains_key(name) {
+        return Err(format!("`{}` is already declared in {}", name, path.display()));
+    }
+    // The classic JSX runtime needs `React` in scope wherever JSX is.
+    let mut needed = free.clone();
+    if imported.contains_key("React") {
+        needed.insert("React".to_string());
+    }
+    let import_lines: Vec<String> = imports.iter().filter_map(|decl| import_line(decl, &needed)).collect();
+    let props: Vec<String> = free
+        .iter()
+        .filter(|ident| !imported.contains_key(*ident) && declarations.names.contains(*ident))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let line_start = source[..lo].rfind('\n').map_or(0, |i| i + 1);
+    let base: String = source[line_start..lo].chars().take_while(|c| c.is_whitespace()).collect();
+    let body = reindent(&source[lo..hi], &base, "        ");
+    let parameters = match (props.is_empty(), extension) {
+        (true, _) => String::new(),
+        (false, "tsx" | "ts") => format!(
+            "{{ {} }}: {{ {} }}",
+            props.join(", "),
+            props.iter().map(|prop| format!("{}: any", prop)).collect::<Vec<_>>().join("; ")
+        ),
+        (false, _) => format!("{{ {} }}", props.join(", ")),
+    };
+    let mut component = import_lines.join("\n");
+    if !component.is_empty() {
+        component.push_str("\n\n");
+    }
+    component.push_str(&format!(
+        "export default function {}({}) {{\n    return (\n{}\n    );\n}}\n",
+        name, parameters, body
+    ));
+
+    let usage = if props.is_empty() {
+        format!("<{} />", name)
+    } else {
+        let attributes: Vec<_> = props.iter().map(|prop| format!("{}={{{}}}", prop, prop)).collect();
+        format!("<{} {} />", name, attributes.join(" "))
+    };
+    let offset = import_offset(&module.body, start);
+    let import = format!("import {} from \"./{}\";", name, name);
+    let mut rewritten = String::with_capacity(source.len());
+    rewritten.push_str(&source[..offset]);
+    rewritten.push_str(&if offset == 0 { format!("{}\n", import) } else { format!("\n{}", import) });
+    rewritten.push_str(&source[offset..lo]);
+    rewritten.push_str(&usage);
+    rewritten.push_str(&source[hi..]);
+
+    Ok(ComponentExtraction { path: component_path, component, source: rewritten, props })
+}
+
+/// Collects [`References`] inside the element at `span`.
+struct SubtreeVisitor<'a> {
+    span: Span,
+    references: &'a mut References,
+}
+
+impl Visit for SubtreeVisitor<'_> {
+    fn visit_jsx_element(&mut self, element: &JSXElement) {
+        if element.span == self.span {
+            element.visit_children_with(self.references);
+        } else {
+            element.visit_children_with(self);
+        }
+    }
+}
@@ -0,0 +1,584 @@
+use colored::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::codes::INVALID_CONFIG;
+
+pub const CONFIG_FILE: &str = "dx.config.toml";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceSyntax {
+    #[default]
+    Tsx,
+    Jsx,
+}
+
+impl SourceSyntax {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SourceSyntax::Tsx => "tsx",
+            SourceSyntax::Jsx => "jsx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// Hoist and expand `name(a+b)` groups.
+    pub groups: bool,
+    /// Generate ids for elements carrying the id trigger class.
+    pub ids: bool,
+    /// Generate test ids for elements carrying the test id trigger class.
+    pub test_ids: bool,
+    /// Tag every element of a component with its `data-dx-scope` and nest the component's
+    /// classes under it, like Vue's scoped styles.
+    pub scoped: bool,
+    /// Replace literal `style={{ ... }}` objects with arbitrary-property classes such as `[padding:16px]`.
+    pub inline_styles: bool,
+    /// Sort the classes of literal class attributes into a canonical order, like prettier-plugin-tailwindcss.
+    pub sort_classes: bool,
+    /// Collapse repeated whitespace and drop duplicate classes in literal class attributes; implied by `sort_classes`.
+    pub normalize_classes: bool,
+    /// Desktop notifications for parse errors and crashes in watch mode.
+    pub notifications: bool,
+    /// Rename classes to short hashed tokens in transformed code and the stylesheet (production builds).
+    pub obfuscate: bool,
+    /// Merge rules with identical declarations; disable to keep one rule per class while debugging.
+    pub merge_rules: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            groups: true,
+            ids: true,
+            test_ids: false,
+            scoped: false,
+            inline_styles: false,
+            sort_classes: false,
+            normalize_classes: false,
+            notifications: false,
+            obfuscate: false,
+            merge_rules: true,
+        }
+    }
+}
+
+/// How symlinks below `src/` (e.g. linked monorepo packages) are scanned and watched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Scan through links, keyed by the path under `src/`; a target reached by two links counts twice.
+    Follow,
+    /// Skip everything reached through a link.
+    Ignore,
+    /// Scan through links, keyed by the link target, so each file counts once.
+    #[default]
+    Dedupe,
+}
+
+/// What happens to `htmlFor`, `aria-labelledby`, `aria-describedby`, `href="#..."` and
+/// `getElementById("...")` references when a generated id changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdReferences {
+    /// Rewrite references in the same file along with the id; ids referenced from other files
+    /// keep their old value.
+    #[default]
+    Update,
+    /// Keep the old id and report the element instead.
+    Refuse,
+    /// Rewrite references in every source along with the id, writing all affected files as one batch.
+    Project,
+}
+
+/// How the watcher covers `src/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchStrategy {
+    /// One recursive watch per root; the OS still spends a watch on every directory below it.
+    #[default]
+    Recursive,
+    /// One watch per directory that can hold included sources, skipping `node_modules`, hidden
+    /// directories and subtrees nested configs exclude, for trees near the OS watch limit.
+    Directories,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Off,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// Contradictory utilities on one element, e.g. `p-2 p-4` or `flex grid`.
+    pub conflicts: Severity,
+}
+
+/// How rules are ordered in the generated stylesheet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CssOrder {
+    #[default]
+    Alphabetical,
+    /// Grouped by utility category (layout, spacing, typography, ...), variants after base rules.
+    Category,
+    /// Tailwind-compatible: base utilities, then state variants in Tailwind's order.
+    Layers,
+}
+
+/// How rules for generated ids are selected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdRules {
+    /// `#BF1 {}`
+    #[default]
+    Id,
+    /// `[id="BF1"] {}`, as specific as a class so utilities are not overridden.
+    Attribute,
+    /// `:where(#BF1) {}`, with no specificity.
+    Where,
+    /// No id rules; ids are still generated and managed in sources.
+    None,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CssConfig {
+    pub order: CssOrder,
+    /// Marks a utility as `!important` when placed before (`!p-4`) or after (`p-4!`) it; empty disables.
+    pub important_modifier: String,
+    /// Breakpoints for responsive variants: `md:` applies from the width up, `max-md:` below it.
+    pub screens: BTreeMap<String, String>,
+    /// Ancestor selector every rule is nested under, e.g. `.my-app` for `.my-app .p-4 { ... }`.
+    pub selector_prefix: String,
+    /// Prefix utilities carry in sources, e.g. `tw-` for `tw-p-4`; other classes get no rule.
+    pub class_prefix: String,
+    /// Wrap selectors in `:where()` so generated rules never out-specify component styles.
+    pub zero_specificity: bool,
+    /// Annotate each generated rule with up to this many usage sites, e.g.
+    /// `/* used in src/Nav.tsx:42 */`; 0 disables. Rules are not merged while enabled.
+    pub usage_comments: usize,
+    /// Start the stylesheet with a Tailwind-preflight-style reset; see `split.base` to write it
+    /// to its own file.
+    pub preflight: bool,
+    pub id_rules: IdRules,
+    pub split: CssSplit,
+    /// Also write each stylesheet under a content-addressed name, e.g. `styles.3f2a9c1e.css`,
+    /// listed in `dx-assets.json`, so it can be served with immutable caching headers.
+    pub hashed_filenames: bool,
+    /// Browserslist queries, e.g. `["> 0.5%", "not dead"]`, the stylesheet is lowered and prefixed
+    /// for in a final lightningcss pass; empty leaves it as generated.
+    pub targets: Vec<String>,
+    /// Minify the stylesheet in the same pass. The pass drops comments, usage comments included.
+    pub minify: bool,
+}
+
+/// Files, relative to the project root, that categories of rules are written to instead of the
+/// main stylesheet, e.g. `utilities = "styles.utilities.css"`, so each can be cached separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CssSplit {
+    /// Resets and other base styles.
+    pub base: Option<String>,
+    /// Utility rules, including the output of CSS emitters.
+    pub utilities: Option<String>,
+    /// Rules for generated ids.
+    pub ids: Option<String>,
+}
+
+impl Default for CssConfig {
+    fn default() -> Self {
+        CssConfig {
+            order: CssOrder::default(),
+            important_modifier: "!".to_string(),
+            screens: [("sm", "640px"), ("md", "768px"), ("lg", "1024px"), ("xl", "1280px"), ("2xl", "1536px")]
+                .into_iter()
+                .map(|(name, width)| (name.to_string(), width.to_string()))
+                .collect(),
+            selector_prefix: String::new(),
+            class_prefix: String::new(),
+            zero_specificity: false,
+            usage_comments: 0,
+            preflight: false,
+            id_rules: IdRules::default(),
+            split: CssSplit::default(),
+            hashed_filenames: false,
+            targets: Vec::new(),
+            minify: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginConfig {
+    pub extractors: Vec<String>,
+    pub emitters: Vec<String>,
+    pub id_strategy: String,
+    pub patterns: HashMap<String, String>,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        PluginConfig {
+            extractors: Vec::new(),
+            emitters: Vec::new(),
+            id_strategy: "initials".to_string(),
+            patterns: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// `https://...` for a plain HTTP store, or `s3://bucket/prefix` for S3 and compatible services.
+    pub remote: Option<String>,
+    /// S3 endpoint override, e.g. for MinIO or R2.
+    pub endpoint: Option<String>,
+    pub region: String,
+    /// Environment variable holding a bearer token for the HTTP store.
+    pub token_env: String,
+    /// Upload results after extraction; disable for untrusted (e.g. fork PR) builds.
+    pub upload: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            remote: None,
+            endpoint: None,
+            region: "us-east-1".to_string(),
+            token_env: "DX_CACHE_TOKEN".to_string(),
+            upload: true,
+        }
+    }
+}
+
+/// A `dx.config.toml` nested below `src/`; its settings apply to files in its directory and
+/// below, overriding the root config and any config further up.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigOverride {
+    /// Globs, relative to the config's directory, a file must match to be scanned.
+    pub include: Option<Vec<String>>,
+    /// Globs, relative to the config's directory, of files to skip.
+    pub exclude: Option<Vec<String>>,
+    pub id_trigger: Option<String>,
+    /// Stylesheet, relative to the config's directory, for this subtree's classes and ids.
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub groups: HashMap<String, String>,
+    /// Deprecated classes and their replacements (`"btn-old" = "btn"`); sources are rewritten to
+    /// the new names and only those get rules.
+    pub rename: HashMap<String, String>,
+    pub syntax: SourceSyntax,
+    pub attributes: Vec<String>,
+    pub helpers: Vec<String>,
+    pub stylesheets: Vec<String>,
+    /// Entry point globs (e.g. `src/main.tsx`); when set, only files they reach through
+    /// imports contribute to the stylesheet.
+    pub entries: Vec<String>,
+    /// The class that asks dx to generate an id for its element.
+    pub id_trigger: String,
+    pub id_references: IdReferences,
+    /// The class that asks dx to generate a test id for its element.
+    pub test_id_trigger: String,
+    /// The attribute generated test ids are written to.
+    pub test_id_attribute: String,
+    pub symlinks: SymlinkPolicy,
+    pub watch: WatchStrategy,
+    /// Sources larger than this many bytes (usually generated) are skipped with a warning.
+    pub max_file_size: u64,
+    /// Whether sources are rewritten with generated ids and expanded groups; when off, dx only
+    /// generates CSS and never touches a source file.
+    pub rewrite: bool,
+    pub features: Features,
+    pub plugins: PluginConfig,
+    pub lint: LintConfig,
+    pub cache: CacheConfig,
+    pub css: CssConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            groups: HashMap::new(),
+            rename: HashMap::new(),
+            syntax: SourceSyntax::default(),
+            attributes: vec!["className".to_string()],
+            helpers: Vec::new(),
+            stylesheets: vec!["./styles/**/*.css".to_string()],
+            entries: Vec::new(),
+            id_trigger: "id".to_string(),
+            id_references: IdReferences::default(),
+            test_id_trigger: "testid".to_string(),
+            test_id_attribute: "data-testid".to_string(),
+            symlinks: SymlinkPolicy::default(),
+            watch: WatchStrategy::default(),
+            max_file_size: 2 * 1024 * 1024,
+            rewrite: true,
+            features: Features::default(),
+            plugins: PluginConfig::default(),
+            lint: LintConfig::default(),
+            cache: CacheConfig::default(),
+            css: CssConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(source: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(source)
+    }
+
+    pub fn load(path: &Path) -> Config {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(_) => return Config::default(),
+        };
+
+        match Config::parse(&source) {
+            Ok(config) => config,
+            Err(e) => {
+                report_invalid(path, &source, &e);
+                Config::default()
+            }
+        }
+    }
+}
+
+impl ConfigOverride {
+    pub fn load(path: &Path) -> Option<ConfigOverride> {
+        let source = std::fs::read_to_string(path).ok()?;
+        match toml::from_str(&source) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                report_invalid(path, &source, &e);
+                None
+            }
+        }
+    }
+}
+
+/// The 1-based line and column of a parse error, for `path:line:column` locations editors can jump to.
+pub fn error_position(source: &str, error: &toml::de::Error) -> Option<(usize, usize)> {
+    let offset = error.span()?.start.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    Some((line, column))
+}
+
+fn report_invalid(path: &Path, source: &str, error: &toml::de::Error) {
+    let location = match error_position(source, error) {
+        Some((line, column)) => format!("{}:{}:{}", path.display(), line, column),
+        None => path.display().to_string(),
+    };
+    eprintln!(
+        "{} [{}] {}: {}",
+        "✗".bright_red(),
+        INVALID_CONFIG,
+        location,
+        error.message().trim_end()
+    );
+}
+
+fn enum_schema(description: &str, values: &[&str], default: &str) -> Value {
+    json!({ "description": description, "enum": values, "default": default })
+}
+
+fn string_list_schema(description: &str, default: &[String]) -> Value {
+    json!({ "description": description, "type": "array", "items": { "type": "string" }, "default": default })
+}
+
+/// An object with exactly `properties`, for the config's tables.
+fn table_schema(properties: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": false, "properties": properties })
+}
+
+/// A JSON Schema (draft 2020-12) for the root `dx.config.toml`, for editor validation
+/// and completion, e.g. via a `#:schema` directive or the `json.schemas` / `evenBetterToml` settings.
+/// Each table is built by its own helper so no single `json!` grows past the macro recursion limit.
+pub fn schema() -> Value {
+    let defaults = Config::default();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "dx config",
+        "description": "Settings for dx, read from dx.config.toml in the project root.",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "groups": {
+                "description": "Named class groups, e.g. `card = \"p-4 rounded+shadow\"`.",
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "default": {}
+            },
+            "rename": {
+                "description": "Deprecated classes and their replacements, e.g. `\"btn-old\" = \"btn\"`.",
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "default": {}
+            },
+            "syntax": enum_schema("Source syntax to parse and scan.", &["tsx", "jsx"], "tsx"),
+            "attributes": string_list_schema("JSX attributes holding class names.", &defaults.attributes),
+            "helpers": string_list_schema("Functions whose string arguments are class names, e.g. `cn`.", &defaults.helpers),
+            "stylesheets": string_list_schema("Globs of hand-written stylesheets checked by `dx audit`.", &defaults.stylesheets),
+            "entries": string_list_schema(
+                "Entry point globs; when set, only files they reach through imports contribute to the stylesheet.",
+                &defaults.entries
+            ),
+            "id_trigger": {
+                "description": "The class that asks dx to generate an id for its element.",
+                "type": "string",
+                "default": defaults.id_trigger
+            },
+            "test_id_trigger": {
+                "description": "The class that asks dx to generate a test id for its element.",
+                "type": "string",
+                "default": defaults.test_id_trigger
+            },
+            "test_id_attribute": {
+                "description": "The attribute generated test ids are written to.",
+                "type": "string",
+                "default": defaults.test_id_attribute
+            },
+            "id_references": enum_schema("Whether references to a renamed id are updated, in the same file or across the project, or block the rename.", &["update", "refuse", "project"], "update"),
+            "symlinks": enum_schema("How symlinks below src/ are scanned and watched.", &["follow", "ignore", "dedupe"], "dedupe"),
+            "watch": enum_schema(
+                "How the watcher covers src/: recursively, or one watch per directory that can hold sources.",
+                &["recursive", "directories"],
+                "recursive"
+            ),
+            "max_file_size": {
+                "description": "Sources larger than this many bytes are skipped with a warning.",
+                "type": "integer",
+                "minimum": 0,
+                "default": defaults.max_file_size
+            },
+            "rewrite": {
+                "description": "Rewrite sources with generated ids and expanded groups; when off, only CSS is generated.",
+                "type": "boolean",
+                "default": defaults.rewrite
+            },
+            "features": features_schema(&defaults.features),
+            "plugins": plugins_schema(&defaults.plugins),
+            "lint": lint_schema(),
+            "cache": cache_schema(&defaults.cache),
+            "css": css_schema(&defaults.css)
+        }
+    })
+}
+
+fn features_schema(features: &Features) -> Value {
+    table_schema(json!({
+        "groups": { "description": "Hoist and expand `name(a+b)` groups.", "type": "boolean", "default": features.groups },
+        "ids": { "description": "Generate ids for elements carrying the id trigger class.", "type": "boolean", "default": features.ids },
+        "test_ids": { "description": "Generate test ids for elements carrying the test id trigger class.", "type": "boolean", "default": features.test_ids },
+        "scoped": { "description": "Scope each component's classes to its elements with `data-dx-scope`.", "type": "boolean", "default": features.scoped },
+        "inline_styles": { "description": "Replace literal `style={{ ... }}` objects with utility classes.", "type": "boolean", "default": features.inline_styles },
+        "sort_classes": { "description": "Sort the classes of literal class attributes into a canonical order.", "type": "boolean", "default": features.sort_classes },
+        "normalize_classes": { "description": "Collapse whitespace and drop duplicate classes in literal class attributes.", "type": "boolean", "default": features.normalize_classes },
+        "notifications": { "description": "Desktop notifications in watch mode.", "type": "boolean", "default": features.notifications },
+        "obfuscate": { "description": "Rename classes to short hashed tokens.", "type": "boolean", "default": features.obfuscate },
+        "merge_rules": { "description": "Merge rules with identical declarations.", "type": "boolean", "default": features.merge_rules }
+    }))
+}
+
+fn plugins_schema(plugins: &PluginConfig) -> Value {
+    table_schema(json!({
+        "extractors": { "type": "array", "items": { "type": "string" }, "default": [] },
+        "emitters": { "type": "array", "items": { "type": "string" }, "default": [] },
+        "id_strategy": { "type": "string", "default": plugins.id_strategy },
+        "patterns": { "type": "object", "additionalProperties": { "type": "string" }, "default": {} }
+    }))
+}
+
+fn lint_schema() -> Value {
+    table_schema(json!({
+        "conflicts": enum_schema("Contradictory utilities on one element, e.g. `p-2 p-4`.", &["off", "warn", "error"], "off")
+    }))
+}
+
+fn cache_schema(cache: &CacheConfig) -> Value {
+    table_schema(json!({
+        "remote": { "description": "`https://...` or `s3://bucket/prefix`.", "type": "string" },
+        "endpoint": { "description": "S3 endpoint override, e.g. for MinIO or R2.", "type": "string" },
+        "region": { "type": "string", "default": cache.region },
+        "token_env": { "description": "Environment variable holding a bearer token.", "type": "string", "default": cache.token_env },
+        "upload": { "description": "Upload results after extraction.", "type": "boolean", "default": cache.upload }
+    }))
+}
+
+fn css_schema(css: &CssConfig) -> Value {
+    table_schema(json!({
+        "order": enum_schema("How rules are ordered in the generated stylesheet.", &["alphabetical", "category", "layers"], "alphabetical"),
+        "important_modifier": { "description": "Marks a utility as `!important`; empty disables.", "type": "string", "default": css.important_modifier },
+        "screens": {
+            "description": "Breakpoints for responsive variants.",
+            "type": "object",
+            "additionalProperties": { "type": "string" },
+            "default": css.screens
+        },
+        "selector_prefix": { "description": "Ancestor selector every rule is nested under.", "type": "string", "default": css.selector_prefix },
+        "class_prefix": { "description": "Prefix utilities carry in sources, e.g. `tw-`.", "type": "string", "default": css.class_prefix },
+        "zero_specificity": { "description": "Wrap selectors in `:where()`.", "type": "boolean", "default": css.zero_specificity },
+        "usage_comments": {
+            "description": "Annotate each generated rule with up to this many usage sites; 0 disables.",
+            "type": "integer",
+            "minimum": 0,
+            "default": css.usage_comments
+        },
+        "preflight": { "description": "Start the stylesheet with a Tailwind-preflight-style reset.", "type": "boolean", "default": css.preflight },
+        "id_rules": enum_schema("How rules for generated ids are selected, if at all.", &["id", "attribute", "where", "none"], "id"),
+        "split": css_split_schema(),
+        "hashed_filenames": {
+            "description": "Also write each stylesheet as `<name>.<hash>.css`, listed in dx-assets.json.",
+            "type": "boolean",
+            "default": css.hashed_filenames
+        },
+        "targets": string_list_schema(
+            "Browserslist queries the stylesheet is lowered and prefixed for in a final lightningcss pass.",
+            &css.targets
+        ),
+        "minify": {
+            "description": "Minify the stylesheet in the final lightningcss pass; comments are dropped.",
+            "type": "boolean",
+            "default": css.minify
+        }
+    }))
+}
+
+fn css_split_schema() -> Value {
+    let mut split = table_schema(json!({
+        "base": { "description": "Resets and other base styles, e.g. `styles.base.css`.", "type": "string" },
+        "utilities": { "description": "Utility rules, e.g. `styles.utilities.css`.", "type": "string" },
+        "ids": { "description": "Rules for generated ids.", "type": "string" }
+    }));
+    split["description"] =
+        "Files, relative to the project root, that categories of rules are written to instead of the main stylesheet.".into();
+    split
+}
+
+pub fn split_group_classes(definition: &str) -> Vec<String> {
+    definition
+        .split(|c: char| c.is_whitespace() || c == '+')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
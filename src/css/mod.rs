@@ -0,0 +1,438 @@
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::cache::content_hash;
+use crate::codes::CSS_TRANSFORM_FAILED;
+use crate::config::{CssConfig, CssOrder, IdRules};
+use crate::extract::Extraction;
+use crate::obfuscate::obfuscated_name;
+use crate::plugin::CssEmitter;
+use crate::utility::{
+    category, class_declarations, class_selector, escape_class, format_body, is_marker, media_query, parse_class,
+    sorted_screens, strip_class_prefix, strip_important, variant_rank, MediaOrder,
+};
+
+/// Settings that influence how CSS is rendered from extracted entities.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CssOptions {
+    /// Emitters run over the rendered stylesheet, in order.
+    #[serde(skip)]
+    pub emitters: Vec<Arc<dyn CssEmitter>>,
+    /// Write classes under their production names; see [`crate::obfuscate`].
+    pub obfuscate: bool,
+    /// Merge rules with identical declarations into one rule with a selector list.
+    pub merge_rules: bool,
+    pub order: CssOrder,
+    pub important_modifier: String,
+    /// Breakpoints for responsive variants, narrowest first.
+    pub screens: Vec<(String, String)>,
+    /// Ancestor selector every rule is nested under, e.g. `.my-app`.
+    pub selector_prefix: String,
+    /// Prefix utilities carry in sources, e.g. `tw-`.
+    pub class_prefix: String,
+    /// Wrap selectors in `:where()`.
+    pub zero_specificity: bool,
+    /// How many usage sites to list in a comment after each rule; 0 disables.
+    pub usage_comments: usize,
+    /// Start the stylesheet with [`PREFLIGHT`].
+    pub preflight: bool,
+    pub id_rules: IdRules,
+    /// Browserslist queries for the final lightningcss pass; see [`transform_css`].
+    pub targets: Vec<String>,
+    /// Minify in the final lightningcss pass.
+    pub minify: bool,
+}
+
+impl Default for CssOptions {
+    fn default() -> Self {
+        CssOptions {
+            emitters: Vec::new(),
+            obfuscate: false,
+            merge_rules: true,
+            order: CssOrder::default(),
+            important_modifier: "!".to_string(),
+            screens: sorted_screens(&CssConfig::default().screens),
+            selector_prefix: String::new(),
+            class_prefix: String::new(),
+            zero_specificity: false,
+            usage_comments: 0,
+            preflight: false,
+            id_rules: IdRules::default(),
+            targets: Vec::new(),
+            minify: false,
+        }
+    }
+}
+
+impl CssOptions {
+    /// The selector name `classname` is rendered under.
+    pub fn class_name<'a>(&self, classname: &'a str) -> Cow<'a, str> {
+        if self.obfuscate {
+            obfuscated_name(classname)
+        } else {
+            Cow::Borrowed(classname)
+        }
+    }
+
+    /// `classname` without the class prefix, for looking up its declarations; `None` for markers
+    /// and, with a class prefix, for classes without it, which get no rule.
+    pub fn utility_class(&self, classname: &str) -> Option<String> {
+        if is_marker(classname) {
+            return None;
+        }
+        strip_class_prefix(classname, &self.class_prefix, &self.important_modifier)
+    }
+
+    /// `selector` nested under the selector prefix and, for zero specificity, wrapped in
+    /// `:where()`. A trailing pseudo-element stays outside, as `:where()` cannot match one.
+    pub fn scoped(&self, selector: String) -> String {
+        let selector = if self.selector_prefix.is_empty() {
+            selector
+        } else {
+            format!("{} {}", self.selector_prefix, selector)
+        };
+        if !self.zero_specificity {
+            return selector;
+        }
+        let (base, pseudo_element) = selector.split_at(selector.find("::").unwrap_or(selector.len()));
+        format!(":where({}){}", base, pseudo_element)
+    }
+
+    /// The selector of the rule for `id`, or `None` when id rules are off.
+    pub fn id_selector(&self, id: &str) -> Option<String> {
+        let selector = match self.id_rules {
+            IdRules::Id => format!("#{}", escape_class(id)),
+            IdRules::Attribute => format!("[id=\"{}\"]", id.replace('\\', "\\\\").replace('"', "\\\"")),
+            IdRules::Where => format!(":where(#{})", escape_class(id)),
+            IdRules::None => return None,
+        };
+        Some(self.scoped(selector))
+    }
+}
+
+/// The attribute tagging every element of a component with its scope under `features.scoped`.
+pub const SCOPE_ATTRIBUTE: &str = "data-dx-scope";
+
+/// The scope of the component at `path`, relative to the project root with `/` separators.
+pub fn scope_hash(path: &str) -> String {
+    format!("{:08x}", content_hash(path.as_bytes()) as u32)
+}
+
+/// Like [`render_css_parts`], with each scope's classes nested under
+/// `[data-dx-scope="<scope>"]` (inside the selector prefix, if any).
+pub fn render_scoped_css_parts(
+    scopes: &[(String, &HashSet<String>)],
+    ids: &HashSet<String>,
+    options: &CssOptions,
+) -> CssParts {
+    let mut parts = render_css_parts(&HashSet::new(), ids, options, &UsageSites::new());
+    for (scope, classnames) in scopes {
+        let mut scoped = options.clone();
+        let attribute = format!("[{}=\"{}\"]", SCOPE_ATTRIBUTE, scope);
+        scoped.selector_prefix = format!("{} {}", options.selector_prefix, attribute).trim_start().to_string();
+        parts.utilities.push_str(&render_css_parts(classnames, &HashSet::new(), &scoped, &UsageSites::new()).utilities);
+    }
+    parts
+}
+
+/// A Tailwind-preflight-style reset: box sizing, zeroed margins, inherited form fonts and so on.
+pub const PREFLIGHT: &str = include_str!("preflight.css");
+
+/// Renders the stylesheet for a single extraction.
+pub fn generate_css(extraction: &Extraction, options: &CssOptions) -> String {
+    render_css(&extraction.classnames, &extraction.ids, options)
+}
+
+pub fn render_css(classnames: &HashSet<String>, ids: &HashSet<String>, options: &CssOptions) -> String {
+    render_css_with_usages(classnames, ids, options, &UsageSites::new())
+}
+
+/// Usage sites per class, e.g. `src/Nav.tsx:42`, in source order.
+pub type UsageSites = HashMap<String, Vec<String>>;
+
+/// `/* used in a, b (+2 more) */` for the first `limit` of `sites`.
+fn usage_comment(sites: &[String], limit: usize) -> String {
+    let shown = sites[..sites.len().min(limit)].join(", ").replace("*/", "* /");
+    match sites.len().saturating_sub(limit) {
+        0 => format!("/* used in {} */", shown),
+        more => format!("/* used in {} (+{} more) */", shown, more),
+    }
+}
+
+/// Like [`render_css`], with a comment after each class rule listing where `usages` says it is
+/// used, up to `options.usage_comments` sites. Annotated rules are never merged.
+pub fn render_css_with_usages(
+    classnames: &HashSet<String>,
+    ids: &HashSet<String>,
+    options: &CssOptions,
+    usages: &UsageSites,
+) -> String {
+    let parts = render_css_parts(classnames, ids, options, usages);
+    let css = [parts.base, parts.utilities, parts.ids].concat();
+    finish_css(css, classnames, ids, options, !usages.is_empty())
+}
+
+/// A rendered stylesheet split by rule category, before emitters and merging.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CssParts {
+    /// Resets and other base styles that do not depend on the classes in use.
+    pub base: String,
+    /// Utility rules, responsive ones last.
+    pub utilities: String,
+    /// Empty rules for generated ids.
+    pub ids: String,
+}
+
+pub fn render_css_parts(
+    classnames: &HashSet<String>,
+    ids: &HashSet<String>,
+    options: &CssOptions,
+    usages: &UsageSites,
+) -> CssParts {
+    let mut css = String::new();
+    let annotate = options.usage_comments > 0 && !usages.is_empty();
+
+    let mut responsive: BTreeMap<(MediaOrder, String), Vec<String>> = BTreeMap::new();
+    for classname in sorted_classnames(classnames, options) {
+        let Some(utility) = options.utility_class(classname) else {
+            continue;
+        };
+        let selector = options.scoped(class_selector(classname, |name| options.class_name(name).into_owned()));
+        let (declarations, important) = class_declarations(&utility, &options.important_modifier);
+        let mut rule = format!("{} {}", selector, format_body(&declarations, important));
+        if let Some(sites) = usages.get(classname).filter(|_| annotate) {
+            write!(rule, " {}", usage_comment(sites, options.usage_comments)).unwrap();
+        }
+        match media_query(classname, &options.screens) {
+            Some((query, order)) => responsive.entry((order, query)).or_default().push(rule),
+            None => writeln!(css, "{}", rule).unwrap(),
+        }
+    }
+
+    for ((_, query), rules) in responsive {
+        writeln!(css, "@media {} {{", query).unwrap();
+        for rule in rules {
+            writeln!(css, "  {}", rule).unwrap();
+        }
+        writeln!(css, "}}").unwrap();
+    }
+
+    let mut id_rules = String::new();
+    let mut sorted_ids: Vec<_> = ids.iter().collect();
+    sorted_ids.sort();
+    for selector in sorted_ids.into_iter().filter_map(|id| options.id_selector(id)) {
+        writeln!(id_rules, "{} {{}}", selector).unwrap();
+    }
+
+    let base = if options.preflight { PREFLIGHT.to_string() } else { String::new() };
+    CssParts { base, utilities: css, ids: id_rules }
+}
+
+/// Runs the emitters over `css` and merges duplicate rules unless it carries usage comments.
+pub fn finish_css(
+    mut css: String,
+    classnames: &HashSet<String>,
+    ids: &HashSet<String>,
+    options: &CssOptions,
+    annotated: bool,
+) -> String {
+    for emitter in &options.emitters {
+        emitter.emit(classnames, ids, &mut css);
+    }
+
+    if options.merge_rules && !(annotated && options.usage_comments > 0) {
+        css = merge_duplicate_rules(&css);
+    }
+    transform_stylesheet(css, options)
+}
+
+/// Runs the final pass when `options` asks for one, keeping `css` as generated if it fails.
+pub fn transform_stylesheet(css: String, options: &CssOptions) -> String {
+    if options.targets.is_empty() && !options.minify {
+        return css;
+    }
+    match transform_css(&css, options) {
+        Ok(transformed) => transformed,
+        Err(e) => {
+            eprintln!("[{}] Stylesheet written untransformed: {}", CSS_TRANSFORM_FAILED, e);
+            css
+        }
+    }
+}
+
+/// Lowers nesting and newer syntax and adds prefixes for the browsers matching `options.targets`,
+/// and minifies under `options.minify`. Comments, usage comments included, do not survive.
+#[cfg(feature = "lightningcss")]
+pub fn transform_css(css: &str, options: &CssOptions) -> Result<String, String> {
+    use lightningcss::printer::PrinterOptions;
+    use lightningcss::stylesheet::{MinifyOptions, ParserOptions, StyleSheet};
+    use lightningcss::targets::{Browsers, Targets};
+
+    let browsers = match options.targets.is_empty() {
+        true => None,
+        false => Browsers::from_browserslist(options.targets.iter().map(String::as_str)).map_err(|e| e.to_string())?,
+    };
+    let targets = Targets { browsers, ..Default::default() };
+    let mut stylesheet = StyleSheet::parse(css, ParserOptions::default()).map_err(|e| e.to_string())?;
+    stylesheet.minify(MinifyOptions { targets, ..Default::default() }).map_err(|e| e.to_string())?;
+    let printed = stylesheet
+        .to_css(PrinterOptions { minify: options.minify, targets, ..Default::default() })
+        .map_err(|e| e.to_string())?;
+    Ok(printed.code)
+}
+
+/// Without the `lightningcss` feature the stylesheet is left as generated; the scanner warns
+/// when the config asks for the pass.
+#[cfg(not(feature = "lightningcss"))]
+pub fn transform_css(css: &str, _options: &CssOptions) -> Result<String, String> {
+    Ok(css.to_string())
+}
+
+fn sorted_classnames<'a>(classnames: &'a HashSet<String>, options: &CssOptions) -> Vec<&'a String> {
+    let mut sorted: Vec<_> = classnames.iter().collect();
+    let utility = |classname: &'a str| {
+        let utility = strip_important(parse_class(classname).utility, &options.important_modifier).1;
+        utility.strip_prefix(options.class_prefix.as_str()).unwrap_or(utility)
+    };
+    match options.order {
+        CssOrder::Alphabetical => sorted.sort(),
+        CssOrder::Category => sorted.sort_by_cached_key(|classname| {
+            let has_variants = !parse_class(classname).variants.is_empty();
+            (has_variants, category(utility(classname)), *classname)
+        }),
+        CssOrder::Layers => sorted.sort_by_cached_key(|classname| {
+            let variants = parse_class(classname).variants;
+            let rank = variants.iter().map(|v| variant_rank(v)).max().unwrap_or(0);
+            (rank, variants.len(), category(utility(classname)), utility(classname), *classname)
+        }),
+    }
+    sorted
+}
+
+struct TopLevelItem<'a> {
+    /// Whitespace (and anything else) between the previous item and the prelude.
+    leading: &'a str,
+    prelude: &'a str,
+    body: &'a str,
+    raw: &'a str,
+}
+
+impl TopLevelItem<'_> {
+    fn is_rule(&self) -> bool {
+        !self.prelude.starts_with('@')
+    }
+
+    fn declarations(&self) -> Vec<&str> {
+        self.body.split(';').map(str::trim).filter(|d| !d.is_empty()).collect()
+    }
+
+    fn properties(&self) -> Vec<&str> {
+        self.declarations()
+            .iter()
+            .filter_map(|d| d.split_once(':').map(|(property, _)| property.trim()))
+            .collect()
+    }
+}
+
+fn top_level_items(css: &str) -> (Vec<TopLevelItem<'_>>, &str) {
+    let bytes = css.as_bytes();
+    let mut items = Vec::new();
+    let mut item_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' {
+            let mut depth = 1;
+            let mut j = i + 1;
+            while j < bytes.len() && depth > 0 {
+                match bytes[j] {
+                    b'{' => depth += 1,
+                    b'}' => depth -= 1,
+                    _ => {}
+                }
+                j += 1;
+            }
+            let before = &css[item_start..i];
+            let prelude = before.trim();
+            let leading = &before[..before.len() - before.trim_start().len()];
+            let body_end = if depth == 0 { j - 1 } else { j };
+            items.push(TopLevelItem {
+                leading,
+                prelude,
+                body: css[i + 1..body_end].trim(),
+                raw: &css[item_start + leading.len()..j],
+            });
+            item_start = j;
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    (items, &css[item_start..])
+}
+
+/// Folds each rule into the first earlier rule with the same declarations, as long as no rule
+/// in between sets any of the same properties (which would change the cascade). Empty rules
+/// and at-rules are left alone.
+pub fn merge_duplicate_rules(css: &str) -> String {
+    let (items, trailing) = top_level_items(css);
+    let mut selectors: Vec<Vec<&str>> = items.iter().map(|item| vec![item.prelude]).collect();
+    let mut removed = vec![false; items.len()];
+    let mut anchors: HashMap<Vec<&str>, usize> = HashMap::new();
+
+    for (i, item) in items.iter().enumerate() {
+        if !item.is_rule() || item.body.is_empty() {
+            continue;
+        }
+        let declarations = item.declarations();
+        if let Some(&anchor) = anchors.get(&declarations) {
+            let properties = item.properties();
+            let blocked = (anchor + 1..i).filter(|&k| !removed[k]).any(|k| {
+                !items[k].is_rule() || items[k].properties().iter().any(|p| properties.contains(p))
+            });
+            if !blocked {
+                selectors[anchor].push(item.prelude);
+                removed[i] = true;
+                continue;
+            }
+        }
+        anchors.insert(declarations, i);
+    }
+
+    let mut out = String::with_capacity(css.len());
+    for (i, item) in items.iter().enumerate() {
+        if removed[i] {
+            continue;
+        }
+        out.push_str(item.leading);
+        if selectors[i].len() > 1 {
+            write!(out, "{} {{ {} }}", selectors[i].join(", "), item.body).unwrap();
+        } else {
+            out.push_str(item.raw);
+        }
+    }
+    out.push_str(trailing);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_rules_with_the_same_declarations() {
+        let css = ".a { color: red; }\n.c { margin: 0; }\n.b { color: red; }\n";
+        assert_eq!(merge_duplicate_rules(css), ".a, .b { color: red; }\n.c { margin: 0; }\n");
+    }
+
+    #[test]
+    fn keeps_rules_apart_when_the_cascade_would_change() {
+        let overridden = ".a { color: red; }\n.c { color: blue; }\n.b { color: red; }\n";
+        assert_eq!(merge_duplicate_rules(overridden), overridden);
+        let at_rule = ".a { color: red; }\n@media (min-width: 640px) { .x { color: red; } }\n.b { color: red; }\n";
+        assert_eq!(merge_duplicate_rules(at_rule), at_rule);
+    }
+}
@@ -0,0 +1,241 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use swc_common::SourceMap;
+
+use crate::extract::extract_from_file;
+use crate::scanner::Scanner;
+use crate::watch::{rebuild, watch_changes, MAX_BATCH};
+
+/// The id bundlers import the generated stylesheet as.
+pub const VIRTUAL_MODULE_ID: &str = "virtual:dx-styles.css";
+/// The resolved id; the `\0` prefix keeps other plugins from touching it.
+pub const RESOLVED_VIRTUAL_MODULE_ID: &str = "\0virtual:dx-styles.css";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// A warm scanner shared by every connected client.
+///
+/// Requests are newline-delimited JSON-RPC 2.0 messages:
+/// `extract { path }`, `buildAll`, `css`, `resolveId { id }`, `load { id }`,
+/// `subscribe { event: "cssChanged" }` and `shutdown`.
+#[derive(Clone)]
+pub struct Daemon {
+    scanner: Arc<Mutex<Scanner>>,
+    subscribers: Arc<Mutex<Vec<SharedWriter>>>,
+    stop: Arc<AtomicBool>,
+}
+
+fn send(writer: &SharedWriter, message: &Value) -> bool {
+    let mut writer = writer.lock().unwrap();
+    writeln!(writer, "{}", message).is_ok() && writer.flush().is_ok()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+impl Daemon {
+    pub fn new(mut scanner: Scanner) -> Self {
+        scanner.initial_scan();
+        Daemon {
+            scanner: Arc::new(Mutex::new(scanner)),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Starts the background watcher that keeps the warm state current.
+    pub fn spawn_watcher(&self) {
+        let daemon = self.clone();
+        thread::spawn(move || {
+            let extension = daemon.scanner.lock().unwrap().options.syntax.extension();
+            watch_changes(extension, &daemon.stop, |mut paths| {
+                for stale in daemon.scanner.lock().unwrap().stale_quarantine() {
+                    if !paths.contains(&stale) {
+                        paths.push(stale);
+                    }
+                }
+                if paths.len() > MAX_BATCH {
+                    let css = {
+                        let mut scanner = daemon.scanner.lock().unwrap();
+                        rebuild(&mut scanner, &paths, &mut |_, _| {});
+                        scanner.css()
+                    };
+                    daemon.broadcast_css(None, css);
+                    return;
+                }
+                for path in paths {
+                    let change = {
+                        let mut scanner = daemon.scanner.lock().unwrap();
+                        scanner.process_change(&path).map(|summary| (summary, scanner.css()))
+                    };
+                    if let Some((summary, css)) = change {
+                        daemon.broadcast_css(Some(summary.path), css);
+                    }
+                }
+            });
+        });
+    }
+
+    fn broadcast_css(&self, path: Option<PathBuf>, css: String) {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "cssChanged",
+            "params": { "path": path, "css": css, "invalidate": [RESOLVED_VIRTUAL_MODULE_ID] },
+        });
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|writer| send(writer, &notification));
+    }
+
+    /// Serves one client until its input closes or it requests a shutdown.
+    pub fn serve(&self, reader: impl Read, writer: Box<dyn Write + Send>) {
+        let writer: SharedWriter = Arc::new(Mutex::new(writer));
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let request: Value = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    send(&writer, &error_response(Value::Null, PARSE_ERROR, &e.to_string()));
+                    continue;
+                }
+            };
+
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            let Some(method) = request.get("method").and_then(Value::as_str) else {
+                send(&writer, &error_response(id, INVALID_REQUEST, "Missing method"));
+                continue;
+            };
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+            if method == "shutdown" {
+                send(&writer, &json!({ "jsonrpc": "2.0", "id": id, "result": null }));
+                std::process::exit(0);
+            }
+
+            let response = match self.handle(method, &params, &writer) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => error_response(id, code, &message),
+            };
+
+            if request.get("id").is_some() {
+                send(&writer, &response);
+            }
+        }
+    }
+
+    fn handle(&self, method: &str, params: &Value, writer: &SharedWriter) -> Result<Value, (i64, String)> {
+        match method {
+            "extract" => {
+                let path = params
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .ok_or((INVALID_PARAMS, "Expected { path }".to_string()))?;
+                let cm: Arc<SourceMap> = Default::default();
+                let scanner = self.scanner.lock().unwrap();
+                let source_path = PathBuf::from(path);
+                let extraction = extract_from_file(&source_path, &cm, &scanner.options_for(&source_path))
+                    .ok_or((INVALID_PARAMS, format!("Failed to parse {}", path)))?;
+
+                let mut classnames: Vec<_> = extraction.classnames.iter().collect();
+                classnames.sort();
+                let mut ids: Vec<_> = extraction.ids.iter().collect();
+                ids.sort();
+                Ok(json!({
+                    "path": path,
+                    "classnames": classnames,
+                    "ids": ids,
+                    "modified": extraction.is_modified(),
+                    "code": extraction.code,
+                }))
+            }
+            "buildAll" => {
+                let (summary, css) = {
+                    let mut scanner = self.scanner.lock().unwrap();
+                    let summary = scanner.initial_scan();
+                    (summary, scanner.css())
+                };
+                self.broadcast_css(None, css);
+                Ok(json!({
+                    "files": summary.files,
+                    "classnames": summary.classnames,
+                    "ids": summary.ids,
+                    "parseFailures": summary.parse_failures,
+                    "rewritten": summary.rewritten,
+                    "cacheHits": summary.cache_hits,
+                    "remoteHits": summary.remote_hits,
+                    "upToDate": summary.up_to_date,
+                    "durationMs": summary.duration.as_secs_f64() * 1000.0,
+                }))
+            }
+            "css" => Ok(json!(self.scanner.lock().unwrap().css())),
+            "resolveId" => {
+                let id = params.get("id").and_then(Value::as_str).unwrap_or_default();
+                if id == VIRTUAL_MODULE_ID || id == RESOLVED_VIRTUAL_MODULE_ID {
+                    Ok(json!(RESOLVED_VIRTUAL_MODULE_ID))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            "load" => {
+                let id = params.get("id").and_then(Value::as_str).unwrap_or_default();
+                if id == RESOLVED_VIRTUAL_MODULE_ID {
+                    Ok(json!({ "code": self.scanner.lock().unwrap().css() }))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            "subscribe" => {
+                let event = params.get("event").and_then(Value::as_str).unwrap_or("cssChanged");
+                if event != "cssChanged" {
+                    return Err((INVALID_PARAMS, format!("Unknown event {}", event)));
+                }
+                self.subscribers.lock().unwrap().push(writer.clone());
+                Ok(json!(true))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+        }
+    }
+}
+
+/// Serves a single client over stdin/stdout.
+pub fn serve_stdio(scanner: Scanner) {
+    let daemon = Daemon::new(scanner);
+    daemon.spawn_watcher();
+    daemon.serve(std::io::stdin(), Box::new(std::io::stdout()));
+}
+
+/// Serves any number of clients over a unix domain socket.
+#[cfg(unix)]
+pub fn serve_socket(scanner: Scanner, path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    let daemon = Daemon::new(scanner);
+    daemon.spawn_watcher();
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let writer = stream.try_clone()?;
+        let daemon = daemon.clone();
+        thread::spawn(move || daemon.serve(stream, Box::new(writer)));
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+/// Unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// The shortest line edit script from `a` to `b` (Myers' algorithm).
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Op> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize;
+    let mut v = vec![0isize; 2 * max + 2];
+    let mut trace = Vec::new();
+    'search: for d in 0..=max as isize {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) { v[i + 1] } else { v[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::with_capacity(max);
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let i = (k + offset) as usize;
+        let previous_k = if k == -d || (k != d && v[i - 1] < v[i + 1]) { k + 1 } else { k - 1 };
+        let previous_x = v[(previous_k + offset) as usize];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == previous_x { Op::Insert } else { Op::Delete });
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    ops.reverse();
+    ops
+}
+
+/// A unified diff from `old` to `new` with `label` as both file names, or `None` if they have
+/// the same lines. Lines start with ` `, `-` or `+` so callers can color them.
+pub fn unified_diff(label: &str, old: &str, new: &str) -> Option<String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = edit_script(&a, &b);
+    let changes: Vec<usize> = ops.iter().enumerate().filter(|(_, op)| **op != Op::Equal).map(|(i, _)| i).collect();
+    if changes.is_empty() {
+        return None;
+    }
+
+    // Line numbers in `a` and `b` before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut x, mut y) = (0, 0);
+    for op in &ops {
+        positions.push((x, y));
+        match op {
+            Op::Equal => (x, y) = (x + 1, y + 1),
+            Op::Delete => x += 1,
+            Op::Insert => y += 1,
+        }
+    }
+    positions.push((x, y));
+
+    let mut output = format!("--- a/{}\n+++ b/{}\n", label, label);
+    let mut group_start = 0;
+    while group_start < changes.len() {
+        let mut group_end = group_start;
+        while group_end + 1 < changes.len() && changes[group_end + 1] - changes[group_end] <= 2 * CONTEXT + 1 {
+            group_end += 1;
+        }
+        let start = changes[group_start].saturating_sub(CONTEXT);
+        let end = (changes[group_end] + CONTEXT + 1).min(ops.len());
+        let ((old_start, new_start), (old_end, new_end)) = (positions[start], positions[end]);
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{},0", start),
+            1 => format!("{}", start + 1),
+            _ => format!("{},{}", start + 1, len),
+        };
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_end - old_start),
+            range(new_start, new_end - new_start)
+        ));
+        for (op, (x, y)) in ops[start..end].iter().zip(&positions[start..end]) {
+            match op {
+                Op::Equal => output.push_str(&format!(" {}\n", a[*x])),
+                Op::Delete => output.push_str(&format!("-{}\n", a[*x])),
+                Op::Insert => output.push_str(&format!("+{}\n", b[*y])),
+            }
+        }
+        group_start = group_end + 1;
+    }
+    Some(output)
+}
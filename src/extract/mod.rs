@@ -0,0 +1,849 @@
+#[cfg(not(target_arch = "wasm32"))]
+use memmap2::Mmap;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use swc_common::comments::{Comments, SingleThreadedComments};
+use swc_common::{FileName, SourceFile, SourceMap, Span, Spanned};
+use swc_ecma_ast::Module;
+use swc_ecma_codegen::{text_writer::JsWriter, Emitter};
+use swc_ecma_parser::{lexer::Lexer, EsSyntax, Parser, StringInput, Syntax, TsSyntax};
+use swc_ecma_visit::{Visit, VisitMutWith};
+
+use crate::codes::{
+    BINARY_FILE, EMIT_FAILURE, ID_CONFLICT, ID_REFERENCED, OVERSIZED_FILE, PARSE_FAILURE, UNKNOWN_GROUP, UNREADABLE_FILE,
+};
+use crate::config::{Config, IdReferences, SourceSyntax};
+use crate::group::{GroupTransformer, GroupUsage};
+use crate::id::{
+    determine_css_entities_and_updates, determine_test_id_updates, IdApplier, ScopeApplier, IdReferenceCollector, IdReferenceUpdater, InfoCollector,
+};
+use crate::inline::InlineStyleExtractor;
+use crate::obfuscate::{obfuscated_name, renamed_class, ClassRenamer};
+use crate::plugin::{Extractor, IdStrategy, PluginRegistry, ResolvedPlugins};
+use crate::sort::ClassSorter;
+
+/// Settings that influence how a single source file is extracted and rewritten.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Named groups (`card = "rounded-lg shadow p-4"`) referenced as `card()`.
+    pub groups: HashMap<String, String>,
+    /// Whether sources are parsed as TSX or plain JSX.
+    pub syntax: SourceSyntax,
+    /// JSX attributes whose values hold class names.
+    pub attributes: Vec<String>,
+    /// Helper functions (`cn`, `clsx`, ...) whose string arguments hold class names.
+    pub helpers: Vec<String>,
+    /// Whether `name(a+b)` groups are hoisted and expanded.
+    pub transform_groups: bool,
+    /// Whether elements carrying the id trigger class get generated ids.
+    pub generate_ids: bool,
+    /// Additional extractors whose class names are merged into the result.
+    pub extractors: Vec<Arc<dyn Extractor>>,
+    /// How base ids are derived for elements carrying the `id` trigger class.
+    pub id_strategy: Arc<dyn IdStrategy>,
+    /// Whether class names in the emitted code are renamed; see [`crate::obfuscate`].
+    pub obfuscate: bool,
+    /// The class that asks for a generated id.
+    pub id_trigger: String,
+    /// Whether elements carrying the test id trigger class get generated test ids.
+    pub generate_test_ids: bool,
+    /// The class that asks for a generated test id.
+    pub test_id_trigger: String,
+    /// The attribute test ids are written to, e.g. `data-testid`.
+    pub test_id_attribute: String,
+    /// Files larger than this many bytes are not read.
+    pub max_file_size: u64,
+    /// Whether the emitted code carries generated ids and expanded groups. When off, `code` is
+    /// the untouched source and only ids already in it are reported.
+    pub rewrite: bool,
+    /// Whether references to a renamed id in the same file are updated or keep the old id.
+    pub id_references: IdReferences,
+    /// Existing ids that are never renamed, e.g. because other files reference them.
+    pub pinned_ids: Arc<HashSet<String>>,
+    /// The component scope every intrinsic element is tagged with under `features.scoped`.
+    pub scope: Option<String>,
+    /// Whether literal `style={{ ... }}` objects are replaced with classes; see [`crate::inline`].
+    pub extract_inline_styles: bool,
+    /// Deprecated classes and their replacements, from the config's `rename` table.
+    pub class_renames: HashMap<String, String>,
+    /// Whether literal class attributes are sorted; see [`crate::sort`].
+    pub sort_classes: bool,
+    /// Whether literal class attributes lose repeated whitespace and duplicate classes.
+    pub normalize_classes: bool,
+    /// `css.class_prefix` and `css.important_modifier`, which the sort order needs to recognize utilities.
+    pub class_prefix: String,
+    pub important_modifier: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::from_config(&Config::default())
+    }
+}
+
+impl Options {
+    pub fn from_config(config: &Config) -> Self {
+        let plugins = PluginRegistry::with_builtins().resolve(&config.plugins);
+        Options::with_plugins(config, &plugins)
+    }
+
+    pub fn with_plugins(config: &Config, plugins: &ResolvedPlugins) -> Self {
+        Options {
+            groups: config.groups.clone(),
+            syntax: config.syntax,
+            attributes: config.attributes.clone(),
+            helpers: config.helpers.clone(),
+            transform_groups: config.features.groups,
+            generate_ids: config.features.ids,
+            extractors: plugins.extractors.clone(),
+            id_strategy: plugins.id_strategy.clone(),
+            obfuscate: config.features.obfuscate,
+            id_trigger: config.id_trigger.clone(),
+            generate_test_ids: config.features.test_ids,
+            test_id_trigger: config.test_id_trigger.clone(),
+            test_id_attribute: config.test_id_attribute.clone(),
+            max_file_size: config.max_file_size,
+            rewrite: config.rewrite,
+            id_references: config.id_references,
+            pinned_ids: Arc::default(),
+            scope: None,
+            extract_inline_styles: config.features.inline_styles,
+            class_renames: config.rename.clone(),
+            sort_classes: config.features.sort_classes,
+            normalize_classes: config.features.normalize_classes,
+            class_prefix: config.css.class_prefix.clone(),
+            important_modifier: config.css.important_modifier.clone(),
+        }
+    }
+
+    /// `classnames` with deprecated classes replaced, so the stylesheet only carries the new names
+    /// even for sources that are not rewritten.
+    fn rename_classes(&self, classnames: &mut HashSet<String>) {
+        if self.class_renames.is_empty() {
+            return;
+        }
+        *classnames = classnames
+            .drain()
+            .map(|classname| renamed_class(&classname, &self.class_renames).into_owned())
+            .collect();
+    }
+
+    fn run_extractors(&self, path: Option<&Path>, source: &str, classnames: &mut HashSet<String>) {
+        for extractor in &self.extractors {
+            classnames.extend(extractor.extract(path, source));
+        }
+    }
+}
+
+/// The result of running the extraction pipeline over one source file.
+#[derive(Debug, Clone, Default)]
+pub struct Extraction {
+    /// Every class name used by the file, with groups expanded.
+    pub classnames: HashSet<String>,
+    /// Every id the file carries once generated ids are applied.
+    pub ids: HashSet<String>,
+    /// The source after group hoisting and id rewriting.
+    pub code: String,
+    /// The source as it was read.
+    pub source: String,
+    /// Ids that were generated or replaced while rewriting.
+    pub id_updates: Vec<IdUpdate>,
+    /// Every element carrying classes or an id, in source order.
+    pub elements: Vec<ElementExtraction>,
+    /// Recoverable syntax errors reported by the parser.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// An id written to an element, located by the element's opening tag in the original source.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdUpdate {
+    pub previous: Option<String>,
+    pub id: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// An element carrying classes or an id, located by its opening tag in the original source.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementExtraction {
+    pub start: u32,
+    pub end: u32,
+    /// One-based line of the opening tag.
+    pub line: usize,
+    /// Zero-based column (in characters) of the opening tag.
+    pub column: usize,
+    pub classnames: Vec<String>,
+    /// Where the classes written literally on the element are, in source order.
+    pub class_spans: Vec<ClassSpan>,
+    /// The id after rewriting.
+    pub id: Option<String>,
+    /// Byte offsets of the id in the original source, when it is a string literal the rewrite keeps.
+    pub id_span: Option<(u32, u32)>,
+    /// Whether the id is managed by dx (the element carries the `id` trigger class).
+    pub generated_id: bool,
+    /// The test id after rewriting.
+    pub test_id: Option<String>,
+    /// Whether the test id is managed by dx (the element carries the test id trigger class).
+    pub generated_test_id: bool,
+}
+
+/// A class token as written in the original source.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassSpan {
+    pub name: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Class,
+    Id,
+}
+
+/// A class or id as written in the original source, for highlighting, codemods and source
+/// maps. Lines are one-based and columns zero-based, in characters, as for elements.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitySpan {
+    pub kind: EntityKind,
+    pub name: String,
+    pub start: u32,
+    pub end: u32,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// A parser message with byte offsets into the original source.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// A stable code such as `DX0001`; see [`crate::codes`].
+    pub code: &'static str,
+    pub message: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Diagnostic {
+    pub(crate) fn unreadable() -> Self {
+        skipped(UNREADABLE_FILE, "could not read file".to_string())
+    }
+
+    /// Whether the file was deliberately left alone (too large, or binary) rather than broken.
+    pub fn is_skip(&self) -> bool {
+        self.code == OVERSIZED_FILE || self.code == BINARY_FILE
+    }
+
+    fn from_parse_error(error: &swc_ecma_parser::error::Error, fm: &SourceFile) -> Self {
+        let (start, end) = relative_offsets(error.span(), fm);
+        Diagnostic {
+            code: PARSE_FAILURE,
+            message: error.kind().msg().to_string(),
+            start,
+            end,
+        }
+    }
+}
+
+fn relative_offsets(span: Span, fm: &SourceFile) -> (u32, u32) {
+    (
+        span.lo.0.saturating_sub(fm.start_pos.0),
+        span.hi.0.saturating_sub(fm.start_pos.0),
+    )
+}
+
+impl Extraction {
+    /// Whether `code` differs from the original `source`.
+    pub fn is_modified(&self) -> bool {
+        self.code != self.source
+    }
+
+    /// Every class and id written literally in the original source, in source order. Classes
+    /// that only come from groups or expressions, and ids dx generates, have no span.
+    pub fn spans(&self) -> Vec<EntitySpan> {
+        let line_starts: Vec<usize> =
+            std::iter::once(0).chain(self.source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        let position = |offset: u32| {
+            let offset = (offset as usize).min(self.source.len());
+            let line = line_starts.partition_point(|&start| start <= offset) - 1;
+            let column = self.source.get(line_starts[line]..offset).map_or(0, |text| text.chars().count());
+            (line + 1, column)
+        };
+        let span = |kind, name: &str, start: u32, end: u32| {
+            let ((line, column), (end_line, end_column)) = (position(start), position(end));
+            EntitySpan { kind, name: name.to_string(), start, end, line, column, end_line, end_column }
+        };
+        let mut spans = Vec::new();
+        for el in &self.elements {
+            for class in &el.class_spans {
+                spans.push(span(EntityKind::Class, &class.name, class.start, class.end));
+            }
+            if let (Some(id), Some((start, end))) = (&el.id, el.id_span) {
+                spans.push(span(EntityKind::Id, id, start, end));
+            }
+        }
+        spans.sort_by_key(|span| span.start);
+        spans
+    }
+
+    /// Ids this extraction renames, from the previous id to the new one.
+    pub fn id_renames(&self) -> HashMap<String, String> {
+        self.id_updates
+            .iter()
+            .filter_map(|update| Some((update.previous.clone()?, update.id.clone())))
+            .filter(|(previous, id)| previous != id)
+            .collect()
+    }
+}
+
+/// Parses `source` as TSX and returns its classes, ids and rewritten code.
+///
+/// Returns `None` if the source does not parse.
+pub fn extract_from_source(source: &str, options: &Options) -> Option<Extraction> {
+    try_extract_from_source(source, options).ok()
+}
+
+/// Like [`extract_from_source`], but reports why the source failed to parse.
+pub fn try_extract_from_source(source: &str, options: &Options) -> Result<Extraction, Diagnostic> {
+    let cm: Arc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(Arc::new(FileName::Anon), source.to_string());
+    extract(&cm, &fm, source.to_string(), options)
+}
+
+/// Extracts many in-memory files (`(name, source)` pairs) sharing one source map.
+pub fn extract_sources<'a>(
+    files: impl IntoIterator<Item = (&'a str, &'a str)>,
+    options: &Options,
+) -> Vec<(String, Result<Extraction, Diagnostic>)> {
+    let cm: Arc<SourceMap> = Default::default();
+    files
+        .into_iter()
+        .map(|(name, source)| {
+            let fm = cm.new_source_file(Arc::new(FileName::Custom(name.to_string())), source.to_string());
+            (name.to_string(), extract(&cm, &fm, source.to_string(), options))
+        })
+        .collect()
+}
+
+pub fn extract_from_file(path: &Path, cm: &Arc<SourceMap>, options: &Options) -> Option<Extraction> {
+    let source = read_source(path, options.max_file_size).ok()?;
+    extract_file_source(path, source, cm, options).ok()
+}
+
+/// Extracts `source`, already read from `path`.
+pub(crate) fn extract_file_source(
+    path: &Path,
+    source: String,
+    cm: &Arc<SourceMap>,
+    options: &Options,
+) -> Result<Extraction, Diagnostic> {
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source.clone());
+    extract(cm, &fm, source, options)
+}
+
+/// Points the `htmlFor`, `aria-*`, `href` and `getElementById` references in `source` at renamed
+/// ids, returning the rewritten code, or `None` if it references none of them.
+pub(crate) fn rewrite_id_references(
+    path: &Path,
+    source: String,
+    cm: &Arc<SourceMap>,
+    options: &Options,
+    renames: &HashMap<String, String>,
+) -> Result<Option<String>, Diagnostic> {
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source);
+    let comments = SingleThreadedComments::default();
+    let (mut module, _) = parse_module(&fm, options.syntax, Some(&comments))?;
+    let mut updater = IdReferenceUpdater { renames, updated: 0 };
+    module.visit_mut_with(&mut updater);
+    if updater.updated == 0 {
+        return Ok(None);
+    }
+    emit_module(cm, &module, &comments).map(Some).ok_or_else(|| Diagnostic {
+        code: EMIT_FAILURE,
+        message: "Failed to emit the rewritten module".to_string(),
+        start: 0,
+        end: 0,
+    })
+}
+
+/// What a file opts out of with a leading `/* dx-disable */`, `/* dx-disable ids */` or
+/// `/* dx-disable groups */` comment (several may be combined, e.g. `dx-disable ids groups`).
+/// `/* dx-disable sort */` keeps the file's class attributes as written under `features.sort_classes`
+/// and `features.normalize_classes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pragma {
+    /// Neither extracted nor rewritten.
+    pub all: bool,
+    pub ids: bool,
+    pub groups: bool,
+    pub sort: bool,
+}
+
+impl Pragma {
+    /// Reads the comments before the first statement of `source`.
+    pub fn parse(source: &str) -> Pragma {
+        let mut pragma = Pragma::default();
+        let mut rest = source.trim_start_matches('\u{feff}');
+        loop {
+            rest = rest.trim_start();
+            let (comment, after) = if let Some(block) = rest.strip_prefix("/*") {
+                let end = block.find("*/").unwrap_or(block.len());
+                (&block[..end], block.get(end + 2..).unwrap_or_default())
+            } else if let Some(line) = rest.strip_prefix("//") {
+                let end = line.find('\n').unwrap_or(line.len());
+                (&line[..end], &line[end..])
+            } else {
+                return pragma;
+            };
+            let mut words = comment.trim().trim_start_matches('*').split(|c: char| c.is_whitespace() || c == ',');
+            if words.next() == Some("dx-disable") {
+                let mut scoped = false;
+                for word in words.filter(|word| !word.is_empty()) {
+                    match word {
+                        "ids" => pragma.ids = true,
+                        "groups" => pragma.groups = true,
+                        "sort" => pragma.sort = true,
+                        _ => continue,
+                    }
+                    scoped = true;
+                }
+                pragma.all |= !scoped;
+            }
+            rest = after;
+        }
+    }
+
+    /// `options` with the opted-out transforms turned off, or `None` when the whole file is.
+    pub fn apply<'a>(&self, options: &'a Options) -> Option<Cow<'a, Options>> {
+        if self.all {
+            return None;
+        }
+        if !self.ids && !self.groups && !self.sort {
+            return Some(Cow::Borrowed(options));
+        }
+        let mut options = options.clone();
+        options.generate_ids &= !self.ids;
+        options.generate_test_ids &= !self.ids;
+        options.transform_groups &= !self.groups;
+        options.sort_classes &= !self.sort;
+        options.normalize_classes &= !self.sort;
+        Some(Cow::Owned(options))
+    }
+}
+
+/// The classes and ids a build of `path` would produce, without emitting the rewritten code.
+pub fn collect_from_file(
+    path: &Path,
+    cm: &Arc<SourceMap>,
+    options: &Options,
+) -> Option<(HashSet<String>, HashSet<String>)> {
+    let source = read_source(path, options.max_file_size).ok()?;
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source.clone());
+    let extraction = analyze(cm, &fm, source, options, false).ok()?;
+    Some((extraction.classnames, extraction.ids))
+}
+
+pub fn group_usages_from_file(
+    path: &Path,
+    cm: &Arc<SourceMap>,
+    options: &Options,
+) -> Option<HashMap<String, GroupUsage>> {
+    let source = read_source(path, options.max_file_size).ok()?;
+    let pragma = Pragma::parse(&source);
+    if pragma.all || pragma.groups {
+        return Some(HashMap::new());
+    }
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source);
+    let mut module = parse_module(&fm, options.syntax, None).ok()?.0;
+
+    let mut group_transformer = GroupTransformer::new(&options.groups, &options.attributes);
+    module.visit_mut_with(&mut group_transformer);
+    Some(group_transformer.usages)
+}
+
+fn skipped(code: &'static str, message: String) -> Diagnostic {
+    Diagnostic {
+        code,
+        message,
+        start: 0,
+        end: 0,
+    }
+}
+
+fn check_size(size: u64, max_size: u64) -> Result<(), Diagnostic> {
+    if size > max_size {
+        return Err(skipped(OVERSIZED_FILE, format!("skipped: {} bytes exceeds max_file_size ({})", size, max_size)));
+    }
+    Ok(())
+}
+
+/// Rejects files whose first 8 KiB contain a NUL byte (git's binary heuristic).
+fn check_text(bytes: &[u8]) -> Result<(), Diagnostic> {
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return Err(skipped(BINARY_FILE, "skipped: looks like a binary file".to_string()));
+    }
+    Ok(())
+}
+
+/// Reads a source file, skipping files over `max_size` bytes and binary files.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn read_source(path: &Path, max_size: u64) -> Result<String, Diagnostic> {
+    let file = std::fs::File::open(path).map_err(|_| Diagnostic::unreadable())?;
+    check_size(file.metadata().map_err(|_| Diagnostic::unreadable())?.len(), max_size)?;
+    let mmap = unsafe { Mmap::map(&file).map_err(|_| Diagnostic::unreadable())? };
+    check_text(&mmap)?;
+    Ok(String::from_utf8_lossy(&mmap).to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn read_source(path: &Path, max_size: u64) -> Result<String, Diagnostic> {
+    let bytes = std::fs::read(path).map_err(|_| Diagnostic::unreadable())?;
+    check_size(bytes.len() as u64, max_size)?;
+    check_text(&bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Parses `fm`, collecting its comments into `comments` so a rewrite can emit them again.
+pub(crate) fn parse_module(
+    fm: &SourceFile,
+    syntax: SourceSyntax,
+    comments: Option<&dyn Comments>,
+) -> Result<(Module, Vec<Diagnostic>), Diagnostic> {
+    let syntax = match syntax {
+        SourceSyntax::Tsx => Syntax::Typescript(TsSyntax { tsx: true, ..Default::default() }),
+        SourceSyntax::Jsx => Syntax::Es(EsSyntax { jsx: true, ..Default::default() }),
+    };
+    let lexer = Lexer::new(
+        syntax,
+        Default::default(),
+        StringInput::from(fm),
+        comments,
+    );
+    let mut parser = Parser::new_from(lexer);
+    let module = parser
+        .parse_module()
+        .map_err(|e| Diagnostic::from_parse_error(&e, fm))?;
+    let diagnostics = parser
+        .take_errors()
+        .iter()
+        .map(|e| Diagnostic::from_parse_error(e, fm))
+        .collect();
+    Ok((module, diagnostics))
+}
+
+fn extract(
+    cm: &Arc<SourceMap>,
+    fm: &SourceFile,
+    source: String,
+    options: &Options,
+) -> Result<Extraction, Diagnostic> {
+    analyze(cm, fm, source, options, true)
+}
+
+/// Extracts `source`; without `emit`, the rewrite is skipped and `code` is left as `source`,
+/// while classes, ids and diagnostics are those a rewrite would produce.
+fn analyze(
+    cm: &Arc<SourceMap>,
+    fm: &SourceFile,
+    source: String,
+    options: &Options,
+    emit: bool,
+) -> Result<Extraction, Diagnostic> {
+    let Some(options) = Pragma::parse(&source).apply(options) else {
+        return Ok(Extraction {
+            classnames: HashSet::new(),
+            ids: HashSet::new(),
+            code: source.clone(),
+            source,
+            id_updates: Vec::new(),
+            elements: Vec::new(),
+            diagnostics: Vec::new(),
+        });
+    };
+    let options = options.as_ref();
+    // Kept so the rewrite does not drop comments, `dx-disable` pragmas among them.
+    let comments = SingleThreadedComments::default();
+    let (mut module, mut diagnostics) = parse_module(fm, options.syntax, Some(&comments))?;
+    if options.extract_inline_styles && options.rewrite {
+        module.visit_mut_with(&mut InlineStyleExtractor { attributes: &options.attributes, extracted: 0 });
+    }
+    if options.rewrite && !options.class_renames.is_empty() {
+        let rename = |classname: &str| renamed_class(classname, &options.class_renames).into_owned();
+        module.visit_mut_with(&mut ClassRenamer {
+            attributes: &options.attributes,
+            helpers: &options.helpers,
+            rename: &rename,
+        });
+    }
+
+    let (resolved_classes, unknown_groups) = transform_groups(&mut module, options);
+    for (name, span) in unknown_groups {
+        let (start, end) = relative_offsets(span, fm);
+        diagnostics.push(Diagnostic {
+            code: UNKNOWN_GROUP,
+            message: format!("`{}()` does not name a group in [groups]", name),
+            start,
+            end,
+        });
+    }
+    let (mut classnames, mut ids, mut id_map) = determine_css_entities_and_updates(&module, &resolved_classes, options);
+    let mut test_id_map = determine_test_id_updates(&module, &resolved_classes, options);
+    if !options.rewrite {
+        id_map.clear();
+        test_id_map.clear();
+    }
+
+    let path = match &*fm.name {
+        FileName::Real(path) => Some(path.as_path()),
+        _ => None,
+    };
+    options.run_extractors(path, &source, &mut classnames);
+    options.rename_classes(&mut classnames);
+
+    let mut info_collector = InfoCollector::new(options);
+    info_collector.visit_module(&module);
+
+    let mut references = IdReferenceCollector::default();
+    references.visit_module(&module);
+    let mut renames = HashMap::new();
+    for el in &info_collector.elements {
+        let (Some(previous), Some(id)) = (&el.current_id, id_map.get(&el.span)) else {
+            continue;
+        };
+        let referenced = references.ids.contains(previous);
+        let pinned = options.pinned_ids.contains(previous);
+        if !pinned && !(referenced && options.id_references == IdReferences::Refuse) {
+            if referenced {
+                renames.insert(previous.clone(), id.clone());
+            }
+            continue;
+        }
+        let (start, end) = relative_offsets(el.span, fm);
+        diagnostics.push(Diagnostic {
+            code: ID_REFERENCED,
+            message: format!(
+                "id `{}` is referenced {}; not renamed to `{}`",
+                previous,
+                if pinned { "from another file" } else { "in this file" },
+                id
+            ),
+            start,
+            end,
+        });
+        ids.remove(id);
+        ids.insert(previous.clone());
+        id_map.remove(&el.span);
+    }
+
+    let mut id_updates = Vec::new();
+    let mut elements = Vec::with_capacity(info_collector.elements.len());
+    for el in info_collector.elements {
+        let (start, end) = relative_offsets(el.span, fm);
+        let loc = cm.lookup_char_pos(el.span.lo);
+        let element_classnames = resolved_classes.get(&el.span).cloned().unwrap_or(el.class_names);
+        let generated_id = options.generate_ids && element_classnames.iter().any(|c| *c == options.id_trigger);
+        let generated_test_id =
+            options.generate_test_ids && element_classnames.iter().any(|c| *c == options.test_id_trigger);
+        let new_id = id_map.get(&el.span).cloned();
+        if let Some(id) = &new_id {
+            id_updates.push(IdUpdate {
+                previous: el.current_id.clone(),
+                id: id.clone(),
+                start,
+                end,
+            });
+        }
+        elements.push(ElementExtraction {
+            start,
+            end,
+            line: loc.line,
+            column: loc.col.0,
+            classnames: element_classnames,
+            class_spans: el
+                .class_spans
+                .iter()
+                .map(|(name, span)| {
+                    let (start, end) = relative_offsets(*span, fm);
+                    ClassSpan { name: name.clone(), start, end }
+                })
+                .collect(),
+            id_span: el
+                .id_span
+                .filter(|_| new_id.is_none() || new_id == el.current_id)
+                .map(|span| relative_offsets(span, fm)),
+            id: new_id.or(el.current_id),
+            generated_id,
+            test_id: test_id_map.get(&el.span).cloned().or(el.current_test_id),
+            generated_test_id,
+        });
+    }
+
+    let mut seen_ids = HashSet::new();
+    for el in &elements {
+        if let Some(id) = &el.id {
+            if !seen_ids.insert(id.as_str()) {
+                diagnostics.push(Diagnostic {
+                    code: ID_CONFLICT,
+                    message: format!("id `{}` is used by more than one element", id),
+                    start: el.start,
+                    end: el.end,
+                });
+            }
+        }
+    }
+
+    if !options.rewrite {
+        ids = elements.iter().filter_map(|el| el.id.clone()).collect();
+    }
+    if !options.rewrite || !emit {
+        return Ok(Extraction {
+            classnames,
+            ids,
+            code: source.clone(),
+            source,
+            id_updates,
+            elements,
+            diagnostics,
+        });
+    }
+
+    if !id_map.is_empty() {
+        let mut applier = IdApplier { id_map: &id_map, attribute: "id" };
+        module.visit_mut_with(&mut applier);
+    }
+    if !test_id_map.is_empty() {
+        let mut applier = IdApplier { id_map: &test_id_map, attribute: &options.test_id_attribute };
+        module.visit_mut_with(&mut applier);
+    }
+    if !renames.is_empty() {
+        module.visit_mut_with(&mut IdReferenceUpdater { renames: &renames, updated: 0 });
+    }
+    if let Some(scope) = &options.scope {
+        module.visit_mut_with(&mut ScopeApplier { scope });
+    }
+
+    if options.sort_classes || options.normalize_classes {
+        module.visit_mut_with(&mut ClassSorter {
+            sort: options.sort_classes,
+            attributes: &options.attributes,
+            class_prefix: &options.class_prefix,
+            important_modifier: &options.important_modifier,
+            sorted: 0,
+        });
+    }
+
+    if options.obfuscate {
+        module.visit_mut_with(&mut ClassRenamer {
+            attributes: &options.attributes,
+            helpers: &options.helpers,
+            rename: &|classname| obfuscated_name(classname).into_owned(),
+        });
+    }
+
+    let code = emit_module(cm, &module, &comments).ok_or_else(|| Diagnostic {
+        code: EMIT_FAILURE,
+        message: "Failed to emit the rewritten module".to_string(),
+        start: 0,
+        end: 0,
+    })?;
+
+    Ok(Extraction {
+        classnames,
+        ids,
+        code,
+        source,
+        id_updates,
+        elements,
+        diagnostics,
+    })
+}
+
+/// Expands groups in place, returning each element's resolved classes and every unknown `name()` reference.
+fn transform_groups(module: &mut Module, options: &Options) -> (HashMap<Span, Vec<String>>, Vec<(String, Span)>) {
+    if !options.transform_groups {
+        return (HashMap::new(), Vec::new());
+    }
+    let mut group_transformer = GroupTransformer::new(&options.groups, &options.attributes);
+    module.visit_mut_with(&mut group_transformer);
+    (group_transformer.resolved_classes, group_transformer.unknown_groups)
+}
+
+pub(crate) fn emit_module(cm: &Arc<SourceMap>, module: &Module, comments: &dyn Comments) -> Option<String> {
+    let mut output = Vec::new();
+    let mut emitter = Emitter {
+        cfg: Default::default(),
+        cm: cm.clone(),
+        comments: Some(comments),
+        wr: JsWriter::new(cm.clone(), "\n", &mut output, None),
+    };
+    emitter.emit_module(module).ok()?;
+    String::from_utf8(output).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pragma_parse() {
+        assert_eq!(Pragma::parse("/* dx-disable */\nexport {};"), Pragma { all: true, ..Default::default() });
+        assert_eq!(
+            Pragma::parse("\u{feff}// header\n// dx-disable ids, groups\nexport {};"),
+            Pragma { ids: true, groups: true, ..Default::default() }
+        );
+        assert_eq!(Pragma::parse("'use client';\n/* dx-disable */"), Pragma::default());
+        assert_eq!(Pragma::parse("/* dx-disabled */"), Pragma::default());
+        assert_eq!(Pragma::parse("/* dx-disable sort */"), Pragma { sort: true, ..Default::default() });
+    }
+
+    const GENERATES_ID: &str = "export const A = () => <div className=\"id p-4\" />;\n";
+
+    fn collect(name: &str, source: &str, options: &Options) -> (HashSet<String>, HashSet<String>) {
+        let path = std::env::temp_dir().join(format!("dx-{}-{}.tsx", name, std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let collected = collect_from_file(&path, &Default::default(), options);
+        std::fs::remove_file(&path).unwrap();
+        collected.unwrap()
+    }
+
+    #[test]
+    fn collect_matches_extract_when_rewriting() {
+        let options = Options::default();
+        let (classnames, ids) = collect("collect-rewrite", GENERATES_ID, &options);
+        let extraction = try_extract_from_source(GENERATES_ID, &options).unwrap();
+        assert!(!ids.is_empty());
+        assert_eq!(ids, extraction.ids);
+        assert_eq!(classnames, extraction.classnames);
+    }
+
+    #[test]
+    fn collect_skips_unwritten_ids_without_rewrite() {
+        let options = Options { rewrite: false, ..Default::default() };
+        let (_, ids) = collect("collect-no-rewrite", GENERATES_ID, &options);
+        let extraction = try_extract_from_source(GENERATES_ID, &options).unwrap();
+        assert!(ids.is_empty());
+        assert_eq!(ids, extraction.ids);
+    }
+
+    #[test]
+    fn rewrite_keeps_partial_pragma() {
+        let source = "/* dx-disable ids */\nexport const A = ()=><div className=\"id p-4\" />\n";
+        let extraction = try_extract_from_source(source, &Options::default()).unwrap();
+        assert!(extraction.is_modified());
+        assert!(extraction.ids.is_empty());
+        assert_eq!(Pragma::parse(&extraction.code), Pragma { ids: true, ..Default::default() });
+
+        let again = try_extract_from_source(&extraction.code, &Options::default()).unwrap();
+        assert!(again.ids.is_empty());
+        assert_eq!(again.code, extraction.code);
+    }
+}
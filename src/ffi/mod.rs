@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::config::Config;
+use crate::css::{render_css, CssOptions};
+use crate::extract::{try_extract_from_source, Options};
+
+pub const DX_OK: i32 = 0;
+pub const DX_PARSE_ERROR: i32 = 1;
+pub const DX_INVALID_ARGUMENT: i32 = 2;
+
+/// An owned array of NUL-terminated UTF-8 strings.
+#[repr(C)]
+pub struct DxStringArray {
+    pub items: *mut *mut c_char,
+    pub len: usize,
+}
+
+/// The result of `dx_extract`. Every pointer is owned by the struct and
+/// released by `dx_extraction_free`.
+#[repr(C)]
+pub struct DxExtraction {
+    pub status: i32,
+    pub modified: bool,
+    pub code: *mut c_char,
+    pub classnames: DxStringArray,
+    pub ids: DxStringArray,
+    pub error: *mut c_char,
+}
+
+fn to_c_string(value: &str) -> *mut c_char {
+    CString::new(value.replace('\0', ""))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+fn to_string_array(values: HashSet<String>) -> DxStringArray {
+    let mut sorted: Vec<_> = values.into_iter().collect();
+    sorted.sort();
+    let items: Box<[*mut c_char]> = sorted.iter().map(|s| to_c_string(s)).collect();
+    let len = items.len();
+    DxStringArray {
+        items: Box::into_raw(items) as *mut *mut c_char,
+        len,
+    }
+}
+
+unsafe fn from_string_array(array: &DxStringArray) -> Vec<String> {
+    if array.items.is_null() {
+        return Vec::new();
+    }
+    let items = unsafe { std::slice::from_raw_parts(array.items, array.len) };
+    items
+        .iter()
+        .filter(|item| !item.is_null())
+        .map(|&item| unsafe { CStr::from_ptr(item) }.to_string_lossy().into_owned())
+        .collect()
+}
+
+unsafe fn free_string_array(array: &mut DxStringArray) {
+    if array.items.is_null() {
+        return;
+    }
+    let items = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(array.items, array.len)) };
+    for &item in items.iter() {
+        if !item.is_null() {
+            drop(unsafe { CString::from_raw(item) });
+        }
+    }
+    array.items = ptr::null_mut();
+    array.len = 0;
+}
+
+fn failure(status: i32, message: &str) -> *mut DxExtraction {
+    Box::into_raw(Box::new(DxExtraction {
+        status,
+        modified: false,
+        code: ptr::null_mut(),
+        classnames: DxStringArray { items: ptr::null_mut(), len: 0 },
+        ids: DxStringArray { items: ptr::null_mut(), len: 0 },
+        error: to_c_string(message),
+    }))
+}
+
+/// Extracts classes and ids from a TSX `source`.
+///
+/// `config_toml` may be NULL or the contents of a dx.config.toml.
+///
+/// # Safety
+///
+/// `source` must be a valid NUL-terminated string, `config_toml` NULL or a
+/// valid NUL-terminated string. The result must be released with
+/// `dx_extraction_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dx_extract(source: *const c_char, config_toml: *const c_char) -> *mut DxExtraction {
+    if source.is_null() {
+        return failure(DX_INVALID_ARGUMENT, "source is NULL");
+    }
+    let source = unsafe { CStr::from_ptr(source) }.to_string_lossy();
+
+    let config = if config_toml.is_null() {
+        Config::default()
+    } else {
+        match Config::parse(&unsafe { CStr::from_ptr(config_toml) }.to_string_lossy()) {
+            Ok(config) => config,
+            Err(e) => return failure(DX_INVALID_ARGUMENT, &e.to_string()),
+        }
+    };
+
+    match try_extract_from_source(&source, &Options::from_config(&config)) {
+        Ok(extraction) => Box::into_raw(Box::new(DxExtraction {
+            status: DX_OK,
+            modified: extraction.is_modified(),
+            code: to_c_string(&extraction.code),
+            classnames: to_string_array(extraction.classnames),
+            ids: to_string_array(extraction.ids),
+            error: ptr::null_mut(),
+        })),
+        Err(diagnostic) => failure(DX_PARSE_ERROR, &diagnostic.message),
+    }
+}
+
+/// Renders the stylesheet for an extraction. Release it with `dx_string_free`.
+///
+/// # Safety
+///
+/// `extraction` must be NULL or a pointer returned by `dx_extract`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dx_generate_css(extraction: *const DxExtraction) -> *mut c_char {
+    let Some(extraction) = (unsafe { extraction.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    let classnames: HashSet<String> = unsafe { from_string_array(&extraction.classnames) }.into_iter().collect();
+    let ids: HashSet<String> = unsafe { from_string_array(&extraction.ids) }.into_iter().collect();
+    to_c_string(&render_css(&classnames, &ids, &CssOptions::default()))
+}
+
+/// # Safety
+///
+/// `extraction` must be NULL or a pointer returned by `dx_extract` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dx_extraction_free(extraction: *mut DxExtraction) {
+    if extraction.is_null() {
+        return;
+    }
+    let mut extraction = unsafe { Box::from_raw(extraction) };
+    unsafe {
+        dx_string_free(extraction.code);
+        dx_string_free(extraction.error);
+        free_string_array(&mut extraction.classnames);
+        free_string_array(&mut extraction.ids);
+    }
+}
+
+/// # Safety
+///
+/// `value` must be NULL or a string returned by this library that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dx_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(unsafe { CString::from_raw(value) });
+    }
+}
+
+/// The library version as a static NUL-terminated string.
+#[unsafe(no_mangle)]
+pub extern "C" fn dx_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
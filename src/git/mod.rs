@@ -0,0 +1,64 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::paths;
+
+fn git(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The root of the enclosing git work tree.
+pub fn repo_root() -> io::Result<PathBuf> {
+    Ok(PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim()))
+}
+
+fn absolute_paths(root: &Path, listing: &str) -> impl Iterator<Item = PathBuf> {
+    listing
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| root.join(line))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Files changed between `reference` and the working tree, including untracked ones.
+pub fn changed_since(reference: &str) -> io::Result<Vec<PathBuf>> {
+    let root = repo_root()?;
+    let diff = git(&["diff", "--name-only", "--no-renames", reference, "--"])?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name", ":/"])?;
+    let mut paths: Vec<_> = absolute_paths(&root, &diff).chain(absolute_paths(&root, &untracked)).collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Files added, copied, modified or renamed in the index.
+pub fn staged_files() -> io::Result<Vec<PathBuf>> {
+    let root = repo_root()?;
+    let staged = git(&["diff", "--cached", "--name-only", "--diff-filter=ACMR"])?;
+    Ok(absolute_paths(&root, &staged).collect())
+}
+
+/// The staged content of `path`, or `None` if it is not in the index.
+pub fn staged_content(path: &Path) -> io::Result<Option<String>> {
+    let root = repo_root()?;
+    let root = paths::canonicalize(&root).unwrap_or(root);
+    let path = paths::normalize(path);
+    let relative = path.strip_prefix(&root).unwrap_or(&path);
+    let spec = format!(":{}", relative.to_string_lossy().replace('\\', "/"));
+    let output = Command::new("git").args(["show", &spec]).output()?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+/// Where git looks for hooks, honoring `core.hooksPath`.
+pub fn hooks_dir() -> io::Result<PathBuf> {
+    Ok(PathBuf::from(git(&["rev-parse", "--git-path", "hooks"])?.trim()))
+}
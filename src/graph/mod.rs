@@ -0,0 +1,123 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use swc_common::{FileName, SourceMap};
+use swc_ecma_ast::{CallExpr, Callee, ExportAll, Expr, ImportDecl, Lit, NamedExport};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::extract::{parse_module, read_source, Options};
+use crate::paths;
+
+const EXTENSIONS: [&str; 4] = ["tsx", "ts", "jsx", "js"];
+
+#[derive(Default)]
+struct ImportCollector {
+    specifiers: Vec<String>,
+}
+
+impl Visit for ImportCollector {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.specifiers.push(import.src.value.to_string());
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        self.specifiers.push(export.src.value.to_string());
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            self.specifiers.push(src.value.to_string());
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Import(_) = call.callee {
+            if let Some(Expr::Lit(Lit::Str(src))) = call.args.first().map(|arg| &*arg.expr) {
+                self.specifiers.push(src.value.to_string());
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+/// Every static, re-exported and literal dynamic import specifier in the file.
+pub fn import_specifiers(path: &Path, cm: &Arc<SourceMap>, options: &Options) -> Option<Vec<String>> {
+    let source = read_source(path, options.max_file_size).ok()?;
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source);
+    let module = parse_module(&fm, options.syntax, None).ok()?.0;
+    let mut collector = ImportCollector::default();
+    module.visit_with(&mut collector);
+    Some(collector.specifiers)
+}
+
+fn with_extensions(base: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    std::iter::once(base.to_path_buf())
+        .chain(EXTENSIONS.iter().map(move |ext| {
+            let mut candidate = base.as_os_str().to_owned();
+            candidate.push(".");
+            candidate.push(ext);
+            PathBuf::from(candidate)
+        }))
+        .chain(EXTENSIONS.iter().map(move |ext| base.join(format!("index.{}", ext))))
+}
+
+/// Resolves a relative (`./`, `../`) or root-alias (`@/`, `~/`, mapped to `<root>/src`) specifier
+/// to a canonical file. Package imports resolve to `None`.
+pub fn resolve_import(from: &Path, specifier: &str, root: &Path) -> Option<PathBuf> {
+    let base = if specifier.starts_with("./") || specifier.starts_with("../") {
+        from.parent()?.join(specifier)
+    } else if let Some(rest) = specifier.strip_prefix("@/").or_else(|| specifier.strip_prefix("~/")) {
+        root.join("src").join(rest)
+    } else {
+        return None;
+    };
+    with_extensions(&base)
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| paths::canonicalize(candidate).ok())
+}
+
+pub type ImportGraph = HashMap<PathBuf, Vec<PathBuf>>;
+
+/// Resolved local imports of one file, restricted to files accepted by `known`.
+pub fn file_imports(path: &Path, root: &Path, options: &Options, known: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let cm: Arc<SourceMap> = Default::default();
+    import_specifiers(path, &cm, options)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|specifier| resolve_import(path, specifier, root))
+        .filter(|resolved| known(resolved))
+        .collect()
+}
+
+/// Resolved local imports of each file, restricted to `files`, parsing each with `options(path)`.
+pub fn import_graph<'a>(
+    files: &[PathBuf],
+    root: &Path,
+    options: impl Fn(&Path) -> Cow<'a, Options> + Sync,
+) -> ImportGraph {
+    use rayon::prelude::*;
+
+    let known: HashSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+    files
+        .par_iter()
+        .map(|path| (path.clone(), file_imports(path, root, &options(path), |p| known.contains(p))))
+        .collect()
+}
+
+/// Every file reachable from `entries`, including the entries themselves.
+pub fn reachable(graph: &ImportGraph, entries: &[PathBuf]) -> Vec<PathBuf> {
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut queue: VecDeque<PathBuf> = entries.iter().cloned().collect();
+    while let Some(path) = queue.pop_front() {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        if let Some(imports) = graph.get(&path) {
+            queue.extend(imports.iter().filter(|import| !seen.contains(*import)).cloned());
+        }
+    }
+    let mut files: Vec<_> = seen.into_iter().collect();
+    files.sort();
+    files
+}
@@ -1,27 +1,56 @@
 use regex::{Captures, Regex};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use swc_common::{Span};
 use swc_ecma_ast::{
     Module, VarDecl, VarDeclarator, Pat, Lit, Expr, JSXAttr, JSXAttrName, JSXAttrValue,
-    Ident, Stmt, Decl, ModuleItem,
+    JSXOpeningElement, Ident, IdentName, Stmt, Decl, ModuleItem,
 };
 use swc_ecma_visit::{VisitMut, VisitMutWith};
 
-pub struct GroupTransformer {
+use crate::config::split_group_classes;
+
+#[derive(Debug, Clone, Default)]
+pub struct GroupUsage {
+    pub expansion: Vec<String>,
+    pub uses: usize,
+    pub from_config: bool,
+}
+
+pub struct GroupTransformer<'a> {
     serializer_count: u32,
+    current_element: Option<Span>,
+    config_groups: &'a HashMap<String, String>,
+    attributes: &'a [String],
     pub new_vars: Vec<VarDecl>,
     pub resolved_classes: HashMap<Span, Vec<String>>,
+    pub usages: HashMap<String, GroupUsage>,
+    /// `name()` references with no matching config group, with the attribute value's span.
+    pub unknown_groups: Vec<(String, Span)>,
 }
 
-impl GroupTransformer {
-    pub fn new() -> Self {
+impl<'a> GroupTransformer<'a> {
+    pub fn new(config_groups: &'a HashMap<String, String>, attributes: &'a [String]) -> Self {
         GroupTransformer {
             serializer_count: 0,
+            current_element: None,
+            config_groups,
+            attributes,
             new_vars: Vec::new(),
             resolved_classes: HashMap::new(),
+            usages: HashMap::new(),
+            unknown_groups: Vec::new(),
         }
     }
 
+    fn record_usage(&mut self, name: &str, expansion: &[String], from_config: bool) {
+        let usage = self.usages.entry(name.to_string()).or_insert_with(|| GroupUsage {
+            expansion: expansion.to_vec(),
+            uses: 0,
+            from_config,
+        });
+        usage.uses += 1;
+    }
+
     fn get_abbreviated(&self, classes_str: &str) -> String {
         let classes: Vec<_> = classes_str.split('+').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
         if classes.is_empty() { return "".to_string(); }
@@ -50,23 +79,40 @@ impl GroupTransformer {
     }
 }
 
-impl VisitMut for GroupTransformer {
+impl<'a> VisitMut for GroupTransformer<'a> {
+    fn visit_mut_jsx_opening_element(&mut self, elem: &mut JSXOpeningElement) {
+        let parent = self.current_element.replace(elem.span);
+        elem.visit_mut_children_with(self);
+        self.current_element = parent;
+    }
+
     fn visit_mut_jsx_attr(&mut self, attr: &mut JSXAttr) {
         if let JSXAttrName::Ident(ident) = &attr.name {
-            if ident.sym == "className" {
+            if self.attributes.iter().any(|a| a.as_str() == &*ident.sym) {
                 if let Some(JSXAttrValue::Lit(Lit::Str(s))) = &mut attr.value {
                     let original_value = s.value.to_string();
-                    let re = Regex::new(r"(\w*)\(([^)]+)\)").unwrap();
+                    let re = Regex::new(r"(\w*)\(([^)]*)\)").unwrap();
                     
                     if re.is_match(&original_value) {
                         let mut full_class_list = Vec::new();
-                        let mut var_name = String::new();
+                        let mut unknown = Vec::new();
 
-                        let transformed_str = re.replace(&original_value, |caps: &Captures| {
+                        let transformed_str = re.replace_all(&original_value, |caps: &Captures| {
                             let prefix = caps.get(1).map_or("", |m| m.as_str());
-                            let classes_part = caps.get(2).map_or("", |m| m.as_str()).trim_end_matches('+');
+                            let classes_part = caps.get(2).map_or("", |m| m.as_str()).trim().trim_end_matches('+');
+
+                            if classes_part.is_empty() {
+                                if let Some(definition) = self.config_groups.get(prefix) {
+                                    let expansion = split_group_classes(definition);
+                                    self.record_usage(prefix, &expansion, true);
+                                    full_class_list.extend(expansion);
+                                } else if !prefix.is_empty() {
+                                    unknown.push(prefix.to_string());
+                                }
+                                return caps[0].to_string();
+                            }
                             
-                            var_name = if prefix.is_empty() {
+                            let var_name = if prefix.is_empty() {
                                 self.serializer_count += 1;
                                 format!("_{}", self.serializer_count)
                             } else {
@@ -75,6 +121,10 @@ impl VisitMut for GroupTransformer {
 
                             let classes_in_group: Vec<_> = classes_part.split('+').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
                             full_class_list.extend(classes_in_group.iter().map(|s| s.to_string()));
+                            if !prefix.is_empty() {
+                                let expansion: Vec<String> = classes_in_group.iter().map(|s| s.to_string()).collect();
+                                self.record_usage(prefix, &expansion, false);
+                            }
 
                             let var_value = classes_in_group.join(" ");
                             let abbreviated = self.get_abbreviated(classes_part);
@@ -100,15 +150,19 @@ impl VisitMut for GroupTransformer {
                             format!("{}({}+)", var_name, abbreviated)
                         }).to_string();
 
+                        self.unknown_groups.extend(unknown.into_iter().map(|name| (name, s.span)));
                         let remaining_classes: Vec<_> = re.replace_all(&original_value, "").split_whitespace().map(String::from).collect();
                         full_class_list.extend(remaining_classes);
-                        self.resolved_classes.insert(attr.span, full_class_list);
+                        let element_span = self.current_element.unwrap_or(attr.span);
+                        self.resolved_classes.insert(element_span, full_class_list);
 
-                        attr.value = Some(JSXAttrValue::Lit(Lit::Str(swc_ecma_ast::Str {
-                            value: transformed_str.into(),
-                            span: s.span,
-                            raw: None,
-                        })));
+                        if transformed_str != original_value {
+                            attr.value = Some(JSXAttrValue::Lit(Lit::Str(swc_ecma_ast::Str {
+                                value: transformed_str.into(),
+                                span: s.span,
+                                raw: None,
+                            })));
+                        }
                     }
                 }
             }
@@ -125,3 +179,83 @@ impl VisitMut for GroupTransformer {
         }
     }
 }
+
+/// A class attribute value with some of its classes moved into a group.
+#[derive(Debug, Clone)]
+pub struct GroupExtraction {
+    /// The attribute value after the transformer rewrote the group.
+    pub value: String,
+    /// The hoisted `let name = "...";` declaration.
+    pub declaration: String,
+}
+
+/// Moves `selected` classes of the attribute `value` into a `name(...)` group at the first
+/// selected class and runs the transformer over just that attribute, as `dx build` would.
+pub fn extract_group(value: &str, selected: &[String], name: &str) -> Option<GroupExtraction> {
+    if selected.is_empty() {
+        return None;
+    }
+    let mut group = Some(format!("{}({})", name, selected.join("+")));
+    let grouped: Vec<String> = value
+        .split_whitespace()
+        .filter_map(|class| if selected.iter().any(|s| s == class) { group.take() } else { Some(class.to_string()) })
+        .collect();
+
+    let attributes = ["className".to_string()];
+    let config_groups = HashMap::new();
+    let mut transformer = GroupTransformer::new(&config_groups, &attributes);
+    let mut attr = JSXAttr {
+        span: Default::default(),
+        name: JSXAttrName::Ident(IdentName::new("className".into(), Default::default())),
+        value: Some(JSXAttrValue::Lit(Lit::Str(swc_ecma_ast::Str {
+            span: Default::default(),
+            value: grouped.join(" ").into(),
+            raw: None,
+        }))),
+    };
+    attr.visit_mut_with(&mut transformer);
+
+    let Some(JSXAttrValue::Lit(Lit::Str(rewritten))) = &attr.value else {
+        return None;
+    };
+    let decl = transformer.new_vars.pop()?;
+    let Some(Expr::Lit(Lit::Str(init))) = decl.decls.first()?.init.as_deref() else {
+        return None;
+    };
+    Some(GroupExtraction {
+        value: rewritten.value.to_string(),
+        declaration: format!("let {} = \"{}\";", name, init.value),
+    })
+}
+
+pub fn merge_group_usages(
+    usages: impl IntoIterator<Item = HashMap<String, GroupUsage>>,
+    config_groups: &HashMap<String, String>,
+) -> BTreeMap<String, GroupUsage> {
+    let mut merged: BTreeMap<String, GroupUsage> = config_groups
+        .iter()
+        .map(|(name, definition)| {
+            (
+                name.clone(),
+                GroupUsage {
+                    expansion: split_group_classes(definition),
+                    uses: 0,
+                    from_config: true,
+                },
+            )
+        })
+        .collect();
+
+    for file_usages in usages {
+        for (name, usage) in file_usages {
+            let entry = merged.entry(name).or_insert_with(|| GroupUsage {
+                expansion: usage.expansion.clone(),
+                uses: 0,
+                from_config: usage.from_config,
+            });
+            entry.uses += usage.uses;
+        }
+    }
+
+    merged
+}
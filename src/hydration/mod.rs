@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::meta::FileMeta;
+
+/// Generated ids keyed by file (relative to the current directory), then by the
+/// `line:column` render position of the element carrying them.
+pub type HydrationMap = BTreeMap<String, BTreeMap<String, String>>;
+
+pub fn hydration_map(files: &BTreeMap<PathBuf, FileMeta>) -> std::io::Result<HydrationMap> {
+    let current_dir = std::env::current_dir()?;
+    Ok(files
+        .iter()
+        .filter_map(|(path, meta)| {
+            let positions: BTreeMap<String, String> = meta
+                .elements
+                .iter()
+                .filter(|el| el.generated_id)
+                .filter_map(|el| Some((format!("{}:{}", el.line, el.column), el.id.clone()?)))
+                .collect();
+            if positions.is_empty() {
+                return None;
+            }
+            let relative = path.strip_prefix(&current_dir).unwrap_or(path);
+            Some((relative.to_string_lossy().replace('\\', "/"), positions))
+        })
+        .collect())
+}
+
+/// Writes the map as plain JSON, or as an importable ES module when `output_path`
+/// ends in `.js`, `.mjs` or `.ts`.
+pub fn write_hydration_map(output_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(&hydration_map(files)?)?;
+    let extension = output_path.extension().and_then(|ext| ext.to_str());
+    let content = match extension {
+        Some("js" | "mjs") => format!("// Generated by dx. Do not edit.\nexport default {};\n", json),
+        Some("ts") => format!("// Generated by dx. Do not edit.\nexport default {} as const;\n", json),
+        _ => json,
+    };
+    std::fs::write(output_path, content)
+}
@@ -1,42 +1,169 @@
+use regex::Regex;
 use std::collections::{BTreeMap, HashMap, HashSet};
-use swc_common::{Span};
+use std::sync::LazyLock;
+use swc_common::{BytePos, Span};
 use swc_ecma_ast::{
-    IdentName, JSXAttr, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXOpeningElement, Lit, Str, Module,
+    CallExpr, Callee, Expr, IdentName, JSXAttr, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXElementName, JSXExpr,
+    JSXOpeningElement, Lit, MemberProp, Module, PropName, Str, TplElement,
 };
 use swc_ecma_visit::{Visit, VisitMut, VisitWith, VisitMutWith};
 
+use crate::css::SCOPE_ATTRIBUTE;
+use crate::extract::Options;
+
 #[derive(Debug, Clone)]
 pub struct ElementInfo {
     pub span: Span,
     pub class_names: Vec<String>,
+    /// Where each literal class token is written, in source order.
+    pub class_spans: Vec<(String, Span)>,
     pub current_id: Option<String>,
+    /// Where `current_id` is written, unless escapes make its source text differ.
+    pub id_span: Option<Span>,
+    pub current_test_id: Option<String>,
 }
 
-pub struct InfoCollector {
+pub struct InfoCollector<'a> {
     pub elements: Vec<ElementInfo>,
+    attributes: &'a [String],
+    helpers: &'a [String],
+    test_id_attribute: &'a str,
+}
+
+impl<'a> InfoCollector<'a> {
+    pub fn new(options: &'a Options) -> Self {
+        InfoCollector {
+            elements: Vec::new(),
+            attributes: &options.attributes,
+            helpers: &options.helpers,
+            test_id_attribute: &options.test_id_attribute,
+        }
+    }
+}
+
+/// The span of every whitespace-separated token of `text`, which starts at `lo` in the source.
+fn token_spans(text: &str, lo: BytePos, class_spans: &mut Vec<(String, Span)>) {
+    for part in text.split_whitespace() {
+        let start = lo + BytePos((part.as_ptr() as usize - text.as_ptr() as usize) as u32);
+        class_spans.push((part.to_string(), Span::new(start, start + BytePos(part.len() as u32))));
+    }
+}
+
+/// The span of a string literal's value without its quotes, or `None` for literals with
+/// escapes, whose value has no reliable position.
+fn value_span(s: &Str) -> Option<Span> {
+    let raw = s.raw.as_ref()?;
+    let inner = raw.get(1..raw.len().saturating_sub(1))?;
+    (inner == &*s.value).then(|| Span::new(s.span.lo + BytePos(1), s.span.hi - BytePos(1)))
+}
+
+/// Records the tokens of a string literal; literals with escapes are left out.
+fn str_spans(s: &Str, class_spans: &mut Vec<(String, Span)>) {
+    if let Some(span) = value_span(s) {
+        token_spans(&s.value, span.lo, class_spans);
+    }
+}
+
+struct HelperClassCollector<'a> {
+    class_names: &'a mut Vec<String>,
+    class_spans: &'a mut Vec<(String, Span)>,
+}
+
+impl<'a> Visit for HelperClassCollector<'a> {
+    fn visit_str(&mut self, s: &Str) {
+        self.class_names.extend(s.value.split_whitespace().map(String::from));
+        str_spans(s, self.class_spans);
+    }
+
+    fn visit_tpl_element(&mut self, el: &TplElement) {
+        self.class_names.extend(el.raw.split_whitespace().map(String::from));
+        token_spans(&el.raw, el.span.lo, self.class_spans);
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        if let PropName::Ident(ident) = name {
+            self.class_names.push(ident.sym.to_string());
+        }
+        name.visit_children_with(self);
+    }
 }
 
-impl Visit for InfoCollector {
+fn collect_expr_class_names(
+    expr: &Expr,
+    helpers: &[String],
+    class_names: &mut Vec<String>,
+    class_spans: &mut Vec<(String, Span)>,
+) {
+    match expr {
+        Expr::Lit(Lit::Str(s)) => {
+            class_names.extend(s.value.split_whitespace().map(String::from));
+            str_spans(s, class_spans);
+        }
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+            for quasi in &tpl.quasis {
+                class_names.extend(quasi.raw.split_whitespace().map(String::from));
+                token_spans(&quasi.raw, quasi.span.lo, class_spans);
+            }
+        }
+        Expr::Paren(paren) => collect_expr_class_names(&paren.expr, helpers, class_names, class_spans),
+        Expr::Call(call) => {
+            if let Callee::Expr(callee) = &call.callee {
+                if let Expr::Ident(ident) = &**callee {
+                    if helpers.iter().any(|h| h.as_str() == &*ident.sym) {
+                        let mut collector = HelperClassCollector { class_names, class_spans };
+                        call.args.visit_with(&mut collector);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<'a> Visit for InfoCollector<'a> {
     fn visit_jsx_opening_element(&mut self, elem: &JSXOpeningElement) {
         let mut all_class_names = Vec::new();
+        let mut class_spans = Vec::new();
         let mut current_id = None;
+        let mut id_span = None;
+        let mut current_test_id = None;
 
         for attr in &elem.attrs {
             if let JSXAttrOrSpread::JSXAttr(attr) = attr {
                 if let JSXAttrName::Ident(ident) = &attr.name {
                     match ident.sym.as_ref() {
-                        "className" => {
+                        "id" => {
                             if let Some(JSXAttrValue::Lit(Lit::Str(s))) = &attr.value {
                                 if !s.value.is_empty() {
-                                    all_class_names.extend(s.value.split_whitespace().map(String::from));
+                                    current_id = Some(s.value.to_string());
+                                    id_span = value_span(s);
                                 }
                             }
                         }
-                        "id" => {
+                        name if name == self.test_id_attribute => {
                             if let Some(JSXAttrValue::Lit(Lit::Str(s))) = &attr.value {
                                 if !s.value.is_empty() {
-                                    current_id = Some(s.value.to_string());
+                                    current_test_id = Some(s.value.to_string());
+                                }
+                            }
+                        }
+                        name if self.attributes.iter().any(|a| a == name) => {
+                            match &attr.value {
+                                Some(JSXAttrValue::Lit(Lit::Str(s))) => {
+                                    all_class_names.extend(s.value.split_whitespace().map(String::from));
+                                    str_spans(s, &mut class_spans);
+                                }
+                                Some(JSXAttrValue::JSXExprContainer(container)) => {
+                                    if let JSXExpr::Expr(expr) = &container.expr {
+                                        collect_expr_class_names(
+                                            expr,
+                                            self.helpers,
+                                            &mut all_class_names,
+                                            &mut class_spans,
+                                        );
+                                    }
                                 }
+                                _ => {}
                             }
                         }
                         _ => {}
@@ -52,7 +179,10 @@ impl Visit for InfoCollector {
             self.elements.push(ElementInfo {
                 span: elem.span,
                 class_names: all_class_names,
+                class_spans,
                 current_id,
+                id_span,
+                current_test_id,
             });
         }
         
@@ -60,8 +190,46 @@ impl Visit for InfoCollector {
     }
 }
 
+fn is_attribute(attr: &JSXAttr, name: &str) -> bool {
+    matches!(&attr.name, JSXAttrName::Ident(ident) if ident.sym == name)
+}
+
+/// Tags every intrinsic element (`div`, not `Button`) with [`SCOPE_ATTRIBUTE`].
+pub struct ScopeApplier<'a> {
+    pub scope: &'a str,
+}
+
+impl VisitMut for ScopeApplier<'_> {
+    fn visit_mut_jsx_opening_element(&mut self, elem: &mut JSXOpeningElement) {
+        let intrinsic =
+            matches!(&elem.name, JSXElementName::Ident(ident) if ident.sym.starts_with(|c: char| c.is_ascii_lowercase()));
+        if intrinsic {
+            let value = Some(JSXAttrValue::Lit(Lit::Str(Str {
+                value: self.scope.into(),
+                span: Default::default(),
+                raw: None,
+            })));
+            let existing = elem.attrs.iter_mut().find_map(|attr| match attr {
+                JSXAttrOrSpread::JSXAttr(attr) if is_attribute(attr, SCOPE_ATTRIBUTE) => Some(attr),
+                _ => None,
+            });
+            match existing {
+                Some(attr) => attr.value = value,
+                None => elem.attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
+                    name: JSXAttrName::Ident(IdentName::new(SCOPE_ATTRIBUTE.into(), Default::default())),
+                    value,
+                    span: Default::default(),
+                })),
+            }
+        }
+        elem.visit_mut_children_with(self);
+    }
+}
+
+/// Sets `attribute` (`id`, or the test id attribute) on the elements in `id_map`.
 pub struct IdApplier<'a> {
     pub id_map: &'a HashMap<Span, String>,
+    pub attribute: &'a str,
 }
 
 impl<'a> VisitMut for IdApplier<'a> {
@@ -71,7 +239,7 @@ impl<'a> VisitMut for IdApplier<'a> {
             for attr in &mut elem.attrs {
                 if let JSXAttrOrSpread::JSXAttr(jsx_attr) = attr {
                     if let JSXAttrName::Ident(ident) = &jsx_attr.name {
-                        if ident.sym == "id" {
+                        if ident.sym == self.attribute {
                             jsx_attr.value = Some(JSXAttrValue::Lit(Lit::Str(Str {
                                 value: new_id.clone().into(),
                                 span: Default::default(),
@@ -86,7 +254,7 @@ impl<'a> VisitMut for IdApplier<'a> {
 
             if !has_id_attr {
                 elem.attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
-                    name: JSXAttrName::Ident(IdentName::new("id".into(), Default::default())),
+                    name: JSXAttrName::Ident(IdentName::new(self.attribute.into(), Default::default())),
                     value: Some(JSXAttrValue::Lit(Lit::Str(Str {
                         value: new_id.clone().into(),
                         span: Default::default(),
@@ -100,15 +268,15 @@ impl<'a> VisitMut for IdApplier<'a> {
     }
 }
 
-pub fn determine_css_entities_and_updates(module: &Module, resolved_classes: &HashMap<Span, Vec<String>>) -> (HashSet<String>, HashSet<String>, HashMap<Span, String>) {
-    let mut info_collector = InfoCollector { elements: Vec::new() };
+pub fn determine_css_entities_and_updates(module: &Module, resolved_classes: &HashMap<Span, Vec<String>>, options: &Options) -> (HashSet<String>, HashSet<String>, HashMap<Span, String>) {
+    let mut info_collector = InfoCollector::new(options);
     info_collector.visit_module(&module);
 
     let mut final_classnames = HashSet::new();
     let mut final_ids = HashSet::new();
     let mut id_updates = HashMap::new();
     
-    let id_trigger_class = "id".to_string();
+    let id_trigger_class = options.id_trigger.clone();
 
     let mut managed_elements_with_base_id = Vec::new();
 
@@ -116,63 +284,276 @@ pub fn determine_css_entities_and_updates(module: &Module, resolved_classes: &Ha
         let classes_for_id = resolved_classes.get(&el.span).unwrap_or(&el.class_names);
         final_classnames.extend(classes_for_id.iter().cloned());
 
-        if !classes_for_id.contains(&id_trigger_class) {
+        if !options.generate_ids || !classes_for_id.contains(&id_trigger_class) {
             if let Some(id) = el.current_id {
                 final_ids.insert(id);
             }
         } else {
             let non_trigger_classes: Vec<_> = classes_for_id.iter().filter(|&cn| *cn != id_trigger_class).cloned().collect();
-            let base_id = if non_trigger_classes.is_empty() {
-                "G".to_string()
-            } else {
-                let classes_to_sample = if non_trigger_classes.len() > 5 {
-                    vec![
-                        non_trigger_classes[0].clone(),
-                        non_trigger_classes[1].clone(),
-                        non_trigger_classes[non_trigger_classes.len() / 2].clone(),
-                        non_trigger_classes[non_trigger_classes.len() - 2].clone(),
-                        non_trigger_classes[non_trigger_classes.len() - 1].clone(),
-                    ]
-                } else {
-                    non_trigger_classes
-                };
-                
-                let mut id_chars: Vec<char> = classes_to_sample
-                    .iter()
-                    .filter_map(|s| s.chars().next())
-                    .map(|c| c.to_ascii_uppercase())
-                    .collect();
-                
-                id_chars.sort_unstable();
-                id_chars.dedup();
-                id_chars.into_iter().collect()
-            };
+            let base_id = options.id_strategy.base_id(&non_trigger_classes);
             managed_elements_with_base_id.push((base_id, el));
         }
     }
 
-    let mut elements_by_base_id: BTreeMap<String, Vec<ElementInfo>> = BTreeMap::new();
-    for (base_id, el_info) in managed_elements_with_base_id {
-        elements_by_base_id.entry(base_id).or_insert_with(Vec::new).push(el_info);
+    let managed = managed_elements_with_base_id.into_iter().map(|(base_id, el)| (base_id, el.span, el.current_id));
+    let (generated_ids, updates) = number_ids(managed);
+    final_ids.extend(generated_ids);
+    id_updates.extend(updates);
+
+    (final_classnames, final_ids, id_updates)
+}
+
+/// Numbers elements sharing a base id (`base1`, `base2`, ...), returning every final id and the
+/// elements whose current id differs from theirs.
+fn number_ids(
+    managed: impl Iterator<Item = (String, Span, Option<String>)>,
+) -> (HashSet<String>, HashMap<Span, String>) {
+    let mut elements_by_base_id: BTreeMap<String, Vec<(Span, Option<String>)>> = BTreeMap::new();
+    for (base_id, span, current) in managed {
+        elements_by_base_id.entry(base_id).or_default().push((span, current));
     }
-    
+
+    let mut final_ids = HashSet::new();
+    let mut updates = HashMap::new();
     for (base_id, elements) in elements_by_base_id {
-        if elements.len() > 1 {
-            for (i, el) in elements.iter().enumerate() {
-                let final_id = format!("{}{}", base_id, i + 1);
-                if el.current_id.as_deref() != Some(&final_id) {
-                    id_updates.insert(el.span, final_id.clone());
-                }
-                final_ids.insert(final_id);
-            }
-        } else if let Some(el) = elements.first() {
-            let final_id = base_id.clone();
-            if el.current_id.as_deref() != Some(&final_id) {
-                id_updates.insert(el.span, final_id.clone());
+        let numbered = elements.len() > 1;
+        for (i, (span, current)) in elements.into_iter().enumerate() {
+            let final_id = if numbered { format!("{}{}", base_id, i + 1) } else { base_id.clone() };
+            if current.as_deref() != Some(&final_id) {
+                updates.insert(span, final_id.clone());
             }
             final_ids.insert(final_id);
         }
     }
-    
-    (final_classnames, final_ids, id_updates)
+    (final_ids, updates)
+}
+
+/// Test ids for elements carrying the test id trigger class, derived like generated ids, for the
+/// elements whose current test id differs.
+pub fn determine_test_id_updates(
+    module: &Module,
+    resolved_classes: &HashMap<Span, Vec<String>>,
+    options: &Options,
+) -> HashMap<Span, String> {
+    if !options.generate_test_ids {
+        return HashMap::new();
+    }
+    let mut info_collector = InfoCollector::new(options);
+    info_collector.visit_module(module);
+
+    let managed = info_collector.elements.into_iter().filter_map(|el| {
+        let classes = resolved_classes.get(&el.span).unwrap_or(&el.class_names);
+        if !classes.contains(&options.test_id_trigger) {
+            return None;
+        }
+        let classes: Vec<_> = classes.iter().filter(|c| **c != options.test_id_trigger).cloned().collect();
+        Some((options.id_strategy.base_id(&classes), el.span, el.current_test_id))
+    });
+    number_ids(managed).1
+}
+
+/// JSX attributes whose string values point at ids: whole for `htmlFor`, space-separated lists for
+/// the ARIA ones, and `#id` fragments for `href`.
+const REFERENCE_ATTRIBUTES: &[&str] = &["htmlFor", "aria-labelledby", "aria-describedby", "href"];
+
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"\b(htmlFor|aria-labelledby|aria-describedby|href)\s*=\s*(?:\{\s*)?["']([^"']*)["']|\b(getElementById)\(\s*["']([^"']*)["']"#,
+    )
+    .unwrap()
+});
+
+fn attribute_name(attr: &JSXAttr) -> Option<&str> {
+    match &attr.name {
+        JSXAttrName::Ident(ident) => Some(ident.sym.as_ref()),
+        JSXAttrName::JSXNamespacedName(_) => None,
+    }
+    .filter(|name| REFERENCE_ATTRIBUTES.contains(name))
+}
+
+/// The string an attribute holds, as `"x"` or `{"x"}`.
+fn attribute_str(value: &Option<JSXAttrValue>) -> Option<&Str> {
+    match value {
+        Some(JSXAttrValue::Lit(Lit::Str(s))) => Some(s),
+        Some(JSXAttrValue::JSXExprContainer(container)) => match &container.expr {
+            JSXExpr::Expr(expr) => match &**expr {
+                Expr::Lit(Lit::Str(s)) => Some(s),
+                _ => None,
+            },
+            JSXExpr::JSXEmptyExpr(_) => None,
+        },
+        _ => None,
+    }
+}
+
+fn attribute_str_mut(value: &mut Option<JSXAttrValue>) -> Option<&mut Str> {
+    match value {
+        Some(JSXAttrValue::Lit(Lit::Str(s))) => Some(s),
+        Some(JSXAttrValue::JSXExprContainer(container)) => match &mut container.expr {
+            JSXExpr::Expr(expr) => match &mut **expr {
+                Expr::Lit(Lit::Str(s)) => Some(s),
+                _ => None,
+            },
+            JSXExpr::JSXEmptyExpr(_) => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `call` looks an element up by id, e.g. `document.getElementById("nav")`.
+fn is_id_lookup(call: &CallExpr) -> bool {
+    let Callee::Expr(callee) = &call.callee else {
+        return false;
+    };
+    match &**callee {
+        Expr::Member(member) => matches!(&member.prop, MemberProp::Ident(prop) if prop.sym == "getElementById"),
+        Expr::Ident(ident) => ident.sym == "getElementById",
+        _ => false,
+    }
+}
+
+/// The ids `value` of attribute `name` refers to.
+fn referenced_ids<'a>(name: &str, value: &'a str) -> Vec<&'a str> {
+    match name {
+        "href" => value.strip_prefix('#').filter(|id| !id.is_empty()).into_iter().collect(),
+        "htmlFor" | "getElementById" => Some(value.trim()).filter(|id| !id.is_empty()).into_iter().collect(),
+        _ => value.split_whitespace().collect(),
+    }
+}
+
+/// `value` of attribute `name` with every renamed id replaced, or `None` if it names none of them.
+fn renamed_value(name: &str, value: &str, renames: &HashMap<String, String>) -> Option<String> {
+    let rename = |id: &str| renames.get(id).cloned().unwrap_or_else(|| id.to_string());
+    match name {
+        "href" => value.strip_prefix('#').filter(|id| renames.contains_key(*id)).map(|id| format!("#{}", rename(id))),
+        "htmlFor" | "getElementById" => renames.get(value.trim()).cloned(),
+        _ if value.split_whitespace().any(|id| renames.contains_key(id)) => {
+            Some(value.split_whitespace().map(rename).collect::<Vec<_>>().join(" "))
+        }
+        _ => None,
+    }
+}
+
+/// Ids referenced by a source's `htmlFor`, `aria-*`, `href` and `getElementById` strings, found
+/// without parsing it.
+pub fn source_id_references(source: &str) -> HashSet<String> {
+    REFERENCE
+        .captures_iter(source)
+        .flat_map(|caps| {
+            let name = caps.get(1).or_else(|| caps.get(3)).unwrap().as_str();
+            let value = caps.get(2).or_else(|| caps.get(4)).unwrap().as_str();
+            referenced_ids(name, value).into_iter().map(String::from).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Ids referenced by string-valued reference attributes and `getElementById` calls in a module.
+#[derive(Default)]
+pub struct IdReferenceCollector {
+    pub ids: HashSet<String>,
+}
+
+impl Visit for IdReferenceCollector {
+    fn visit_jsx_attr(&mut self, attr: &JSXAttr) {
+        if let (Some(name), Some(s)) = (attribute_name(attr), attribute_str(&attr.value)) {
+            self.ids.extend(referenced_ids(name, &s.value).into_iter().map(String::from));
+        }
+        attr.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Some(Expr::Lit(Lit::Str(s))) = call.args.first().map(|arg| &*arg.expr).filter(|_| is_id_lookup(call)) {
+            self.ids.extend(referenced_ids("getElementById", &s.value).into_iter().map(String::from));
+        }
+        call.visit_children_with(self);
+    }
+}
+
+/// Points reference attributes and `getElementById` calls at renamed ids.
+pub struct IdReferenceUpdater<'a> {
+    /// Previous id to new id.
+    pub renames: &'a HashMap<String, String>,
+    /// How many references were rewritten.
+    pub updated: usize,
+}
+
+impl IdReferenceUpdater<'_> {
+    fn update(&mut self, name: &str, s: &mut Str) {
+        if let Some(value) = renamed_value(name, &s.value, self.renames) {
+            *s = Str {
+                value: value.into(),
+                span: s.span,
+                raw: None,
+            };
+            self.updated += 1;
+        }
+    }
+}
+
+impl VisitMut for IdReferenceUpdater<'_> {
+    fn visit_mut_jsx_attr(&mut self, attr: &mut JSXAttr) {
+        if let Some(name) = attribute_name(attr).map(str::to_string) {
+            if let Some(s) = attribute_str_mut(&mut attr.value) {
+                self.update(&name, s);
+            }
+        }
+        attr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        if is_id_lookup(call) {
+            if let Some(Expr::Lit(Lit::Str(s))) = call.args.first_mut().map(|arg| &mut *arg.expr) {
+                self.update("getElementById", s);
+            }
+        }
+        call.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(lo: u32) -> Span {
+        Span::new(BytePos(lo), BytePos(lo + 1))
+    }
+
+    #[test]
+    fn number_ids_numbers_shared_bases() {
+        let managed = vec![
+            ("card".to_string(), span(1), None),
+            ("card".to_string(), span(2), Some("card2".to_string())),
+            ("nav".to_string(), span(3), Some("nav".to_string())),
+        ];
+        let (ids, updates) = number_ids(managed.into_iter());
+        assert_eq!(ids, HashSet::from(["card1".to_string(), "card2".to_string(), "nav".to_string()]));
+        assert_eq!(updates, HashMap::from([(span(1), "card1".to_string())]));
+    }
+
+    #[test]
+    fn referenced_ids_by_attribute() {
+        assert_eq!(referenced_ids("href", "#nav"), ["nav"]);
+        assert!(referenced_ids("href", "/about").is_empty());
+        assert!(referenced_ids("href", "#").is_empty());
+        assert_eq!(referenced_ids("htmlFor", " email "), ["email"]);
+        assert_eq!(referenced_ids("aria-labelledby", "title  subtitle"), ["title", "subtitle"]);
+    }
+
+    #[test]
+    fn renamed_value_replaces_only_renamed_ids() {
+        let renames = HashMap::from([("a".to_string(), "x".to_string()), ("nav".to_string(), "menu".to_string())]);
+        assert_eq!(renamed_value("href", "#nav", &renames).as_deref(), Some("#menu"));
+        assert_eq!(renamed_value("href", "#other", &renames), None);
+        assert_eq!(renamed_value("htmlFor", "a", &renames).as_deref(), Some("x"));
+        assert_eq!(renamed_value("aria-describedby", "a b", &renames).as_deref(), Some("x b"));
+        assert_eq!(renamed_value("aria-describedby", "b c", &renames), None);
+    }
+
+    #[test]
+    fn source_id_references_finds_every_form() {
+        let source = r##"<label htmlFor="email" /><a href="#top" /><p aria-describedby={"hint note"} />;
+document.getElementById('main');"##;
+        let expected = ["email", "top", "hint", "note", "main"].map(String::from);
+        assert_eq!(source_id_references(source), HashSet::from(expected));
+    }
 }
@@ -0,0 +1,152 @@
+use swc_ecma_ast::{
+    Expr, IdentName, JSXAttr, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXExpr, JSXOpeningElement, Lit, ObjectLit,
+    Prop, PropName, PropOrSpread, Str,
+};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+/// Properties React renders without a `px` unit when given a number.
+const UNITLESS: &[&str] = &[
+    "animation-iteration-count",
+    "aspect-ratio",
+    "flex",
+    "flex-grow",
+    "flex-shrink",
+    "font-weight",
+    "line-height",
+    "opacity",
+    "order",
+    "orphans",
+    "widows",
+    "z-index",
+    "zoom",
+];
+
+/// `paddingTop` as `padding-top`; vendor prefixes such as `WebkitTransition` keep their leading dash.
+fn kebab_case(key: &str) -> String {
+    let mut property = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            property.push('-');
+            property.push(c.to_ascii_lowercase());
+        } else {
+            property.push(c);
+        }
+    }
+    property
+}
+
+/// The arbitrary-property class for one style entry, e.g. `[padding:16px]` for `padding: 16`.
+/// `None` for values a class cannot carry (quotes, brackets, underscores) or that are not literals.
+fn style_class(key: &str, value: &Expr) -> Option<String> {
+    let property = kebab_case(key);
+    let value = match value {
+        Expr::Lit(Lit::Str(s)) => s.value.trim().to_string(),
+        Expr::Lit(Lit::Num(n)) if n.value == 0.0 || UNITLESS.contains(&property.as_str()) => n.value.to_string(),
+        Expr::Lit(Lit::Num(n)) => format!("{}px", n.value),
+        _ => return None,
+    };
+    if value.is_empty() || value.contains(['_', '[', ']', '"', '\'', '\\']) {
+        return None;
+    }
+    Some(format!("[{}:{}]", property, value.split_whitespace().collect::<Vec<_>>().join("_")))
+}
+
+/// Classes for every entry of a literal style object, or `None` if any entry cannot be converted.
+fn style_classes(object: &ObjectLit) -> Option<Vec<String>> {
+    object
+        .props
+        .iter()
+        .map(|prop| {
+            let PropOrSpread::Prop(prop) = prop else {
+                return None;
+            };
+            let Prop::KeyValue(kv) = &**prop else {
+                return None;
+            };
+            let key = match &kv.key {
+                PropName::Ident(ident) => ident.sym.to_string(),
+                PropName::Str(s) => s.value.to_string(),
+                _ => return None,
+            };
+            style_class(&key, &kv.value)
+        })
+        .collect()
+}
+
+/// Replaces literal `style={{ padding: 16, color: "#333" }}` objects with arbitrary-property
+/// classes (`[padding:16px] [color:#333]`) appended to the element's class attribute. Elements
+/// whose style has dynamic entries, or whose class attribute is not a plain string, are left alone.
+pub struct InlineStyleExtractor<'a> {
+    /// Class attributes; the first one is added when an element has none.
+    pub attributes: &'a [String],
+    /// How many style objects were replaced.
+    pub extracted: usize,
+}
+
+impl VisitMut for InlineStyleExtractor<'_> {
+    fn visit_mut_jsx_opening_element(&mut self, elem: &mut JSXOpeningElement) {
+        elem.visit_mut_children_with(self);
+
+        let attr_index = |name: &str| {
+            elem.attrs.iter().position(|attr| match attr {
+                JSXAttrOrSpread::JSXAttr(JSXAttr { name: JSXAttrName::Ident(ident), .. }) => ident.sym == name,
+                _ => false,
+            })
+        };
+        let Some(style_index) = attr_index("style") else {
+            return;
+        };
+        let Some(class_attribute) = self.attributes.first() else {
+            return;
+        };
+        let class_index = self.attributes.iter().find_map(|name| attr_index(name));
+
+        let JSXAttrOrSpread::JSXAttr(style) = &elem.attrs[style_index] else {
+            return;
+        };
+        let Some(JSXAttrValue::JSXExprContainer(container)) = &style.value else {
+            return;
+        };
+        let JSXExpr::Expr(expr) = &container.expr else {
+            return;
+        };
+        let Expr::Object(object) = &**expr else {
+            return;
+        };
+        let Some(classes) = style_classes(object) else {
+            return;
+        };
+
+        match class_index.map(|index| &mut elem.attrs[index]) {
+            Some(JSXAttrOrSpread::JSXAttr(attr)) => {
+                let Some(JSXAttrValue::Lit(Lit::Str(s))) = &mut attr.value else {
+                    return;
+                };
+                let mut value = s.value.trim_end().to_string();
+                for class in &classes {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+                    value.push_str(class);
+                }
+                *s = Str {
+                    value: value.into(),
+                    span: s.span,
+                    raw: None,
+                };
+            }
+            Some(JSXAttrOrSpread::SpreadElement(_)) => return,
+            None => elem.attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
+                name: JSXAttrName::Ident(IdentName::new(class_attribute.as_str().into(), Default::default())),
+                value: Some(JSXAttrValue::Lit(Lit::Str(Str {
+                    value: classes.join(" ").into(),
+                    span: Default::default(),
+                    raw: None,
+                }))),
+                span: Default::default(),
+            })),
+        }
+        elem.attrs.remove(style_index);
+        self.extracted += 1;
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cache::content_hash;
+
+pub const INTENT_FILE: &str = ".dx/intent.json";
+
+/// A file dx is about to write, with the hash of the content it will write when that is known
+/// up front (sources), or `None` (stylesheets, rendered while writing).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedWrite {
+    pub path: PathBuf,
+    pub hash: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Intent {
+    /// Milliseconds since the Unix epoch.
+    started: u64,
+    writes: Vec<PlannedWrite>,
+}
+
+/// A batch of writes a previous run started but never finished, e.g. because it crashed.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptedBatch {
+    pub started: u64,
+    /// Sources that already hold their planned content.
+    pub applied: Vec<PathBuf>,
+    /// Sources that still hold their old content.
+    pub pending: Vec<PathBuf>,
+    /// Stylesheets whose content cannot be checked; they may be stale.
+    pub stylesheets: Vec<PathBuf>,
+}
+
+/// The write-ahead log in `.dx/intent.json`: a batch of writes is recorded before any of them
+/// is applied and the log is removed once they all succeeded, so a log found on startup means
+/// the sources and stylesheets may disagree.
+pub struct IntentLog {
+    path: PathBuf,
+}
+
+impl IntentLog {
+    pub fn new(root: &Path) -> Self {
+        IntentLog { path: root.join(INTENT_FILE) }
+    }
+
+    pub fn begin(&self, writes: Vec<PlannedWrite>) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let json = serde_json::to_string(&Intent { started, writes })?;
+        // Written through a temporary so a crash mid-write cannot leave a truncated log.
+        crate::io::write_files(&[(self.path.clone(), json)])
+    }
+
+    pub fn commit(&self) -> io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// The batch a previous run left unfinished, checked against the files on disk.
+    pub fn interrupted(&self) -> Option<InterruptedBatch> {
+        let json = std::fs::read_to_string(&self.path).ok()?;
+        let intent: Intent = serde_json::from_str(&json).ok()?;
+        let mut batch = InterruptedBatch { started: intent.started, ..Default::default() };
+        for write in intent.writes {
+            let Some(hash) = write.hash else {
+                batch.stylesheets.push(write.path);
+                continue;
+            };
+            match std::fs::read(&write.path) {
+                Ok(content) if content_hash(&content) == hash => batch.applied.push(write.path),
+                _ => batch.pending.push(write.path),
+            }
+        }
+        Some(batch)
+    }
+}
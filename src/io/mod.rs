@@ -1,46 +1,62 @@
-use regex::Regex;
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-pub fn write_file(path: &Path, content: &str) {
-    let file = File::create(path).expect("Could not create file");
+use crate::stylesheet::{parse_rules, subject_name, SelectorName};
+
+pub fn write_file(path: &Path, content: &str) -> io::Result<()> {
+    let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
-    writer
-        .write_all(content.as_bytes())
-        .expect("Failed to write to file");
+    writer.write_all(content.as_bytes())?;
+    writer.flush()
 }
 
-pub fn read_existing_css(path: &Path) -> (HashSet<String>, HashSet<String>) {
-    let mut classes = HashSet::new();
-    let mut ids = HashSet::new();
-
-    if !path.exists() {
-        return (classes, ids);
+/// Writes every file or, as far as the filesystem allows, none: each is written to a temporary
+/// file next to it first, and the temporaries replace the originals only once all were written.
+pub fn write_files(files: &[(PathBuf, String)]) -> io::Result<()> {
+    let temporary = |path: &Path| {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".dx-tmp");
+        path.with_file_name(name)
+    };
+    for (i, (path, content)) in files.iter().enumerate() {
+        if let Err(error) = write_file(&temporary(path), content) {
+            for (path, _) in &files[..=i] {
+                let _ = std::fs::remove_file(temporary(path));
+            }
+            return Err(error);
+        }
+    }
+    for (path, _) in files {
+        std::fs::rename(temporary(path), path)?;
     }
+    Ok(())
+}
 
-    let file = match File::open(path) {
-        Ok(file) => file,
-        Err(_) => return (classes, ids),
-    };
+pub fn read_existing_css(path: &Path) -> (HashSet<String>, HashSet<String>) {
+    match std::fs::read_to_string(path) {
+        Ok(css) => parse_existing_css(&css),
+        Err(_) => (HashSet::new(), HashSet::new()),
+    }
+}
 
-    let re = match Regex::new(r"^\s*[.#]([\w-]+)") {
-        Ok(re) => re,
-        Err(_) => return (classes, ids),
-    };
+/// The classes and ids of a stylesheet previously written by [`write_css`]: the subject of every
+/// selector, e.g. `.group-hover:x` in `.group:hover .group-hover\:x` or `#nav` in `.my-app #nav`.
+pub fn parse_existing_css(css: &str) -> (HashSet<String>, HashSet<String>) {
+    let mut classes = HashSet::new();
+    let mut ids = HashSet::new();
 
-    for line in BufReader::new(file).lines() {
-        if let Ok(line_content) = line {
-            if let Some(caps) = re.captures(&line_content) {
-                if let Some(name_match) = caps.get(1) {
-                    let name = name_match.as_str().to_string();
-                    if line_content.trim().starts_with('.') {
-                        classes.insert(name);
-                    } else if line_content.trim().starts_with('#') {
-                        ids.insert(name);
-                    }
+    for rule in parse_rules(css) {
+        for selector in rule.selectors() {
+            match subject_name(selector) {
+                Some(SelectorName::Class(class)) => {
+                    classes.insert(class);
+                }
+                Some(SelectorName::Id(id)) => {
+                    ids.insert(id);
                 }
+                None => {}
             }
         }
     }
@@ -48,19 +64,6 @@ pub fn read_existing_css(path: &Path) -> (HashSet<String>, HashSet<String>) {
     (classes, ids)
 }
 
-pub fn write_css(classnames: &HashSet<String>, ids: &HashSet<String>, output_path: &Path) {
-    let file = File::create(output_path).expect("Could not create styles.css for writing");
-    let mut writer = BufWriter::new(file);
-
-    let mut sorted_classnames: Vec<_> = classnames.iter().collect();
-    sorted_classnames.sort();
-    for classname in sorted_classnames {
-        writeln!(writer, ".{} {{}}", classname).expect("Failed to write to styles.css");
-    }
-
-    let mut sorted_ids: Vec<_> = ids.iter().collect();
-    sorted_ids.sort();
-    for id in sorted_ids {
-        writeln!(writer, "#{} {{}}", id).expect("Failed to write to styles.css");
-    }
+pub fn write_css(css: &str, output_path: &Path) -> io::Result<()> {
+    write_file(output_path, css)
 }
@@ -0,0 +1,80 @@
+//! dx-styles scans TSX sources for class names and ids, hoists class groups,
+//! generates ids for elements carrying the `id` trigger class, and renders the
+//! resulting stylesheet.
+//!
+//! The [`Scanner`] keeps a whole project in sync on disk, while
+//! [`extract_from_source`] and [`generate_css`] expose the same pipeline for a
+//! single in-memory file:
+//!
+//! ```no_run
+//! use dx::{extract_from_source, generate_css, CssOptions, Options};
+//!
+//! let source = r#"export const A = () => <div className="flex id" />;"#;
+//! let extraction = extract_from_source(source, &Options::default()).unwrap();
+//! let css = generate_css(&extraction, &CssOptions::default());
+//! println!("{}\n{}", extraction.code, css);
+//! ```
+
+pub mod assets;
+pub mod audit;
+pub mod baseline;
+pub mod cache;
+pub mod cascade;
+pub mod codemod;
+pub mod codes;
+pub mod component;
+pub mod config;
+pub mod css;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod daemon;
+pub mod diff;
+pub mod extract;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod git;
+pub mod graph;
+pub mod group;
+pub mod hydration;
+pub mod id;
+pub mod inline;
+pub mod intent;
+pub mod io;
+pub mod lint;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lsp;
+pub mod meta;
+pub mod metrics;
+pub mod migrate;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod notifications;
+pub mod obfuscate;
+pub mod paths;
+pub mod plugin;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod remote;
+pub mod report;
+pub mod routes;
+pub mod scanner;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serve;
+pub mod sort;
+pub mod stories;
+pub mod stylesheet;
+pub mod symbols;
+pub mod testid;
+pub mod types;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ui;
+pub mod undo;
+pub mod utility;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
+pub mod workspace;
+
+pub use config::Config;
+pub use css::{generate_css, CssOptions};
+pub use extract::{extract_from_source, EntityKind, EntitySpan, Extraction, Options};
+pub use scanner::{ChangeSummary, CheckReport, ClassUsage, DeadCode, IdUsage, ScanSummary, Scanner, UnstableRewrite};
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Utilities that set the same property to different keywords.
+const KEYWORD_FAMILIES: &[(&str, &[&str])] = &[
+    (
+        "display",
+        &[
+            "block", "inline-block", "inline", "flex", "inline-flex", "grid", "inline-grid", "hidden", "contents",
+            "table", "flow-root",
+        ],
+    ),
+    ("position", &["static", "fixed", "absolute", "relative", "sticky"]),
+    ("visibility", &["visible", "invisible"]),
+    ("flex-direction", &["flex-row", "flex-row-reverse", "flex-col", "flex-col-reverse"]),
+    ("flex-wrap", &["flex-wrap", "flex-wrap-reverse", "flex-nowrap"]),
+    ("text-align", &["text-left", "text-center", "text-right", "text-justify", "text-start", "text-end"]),
+    (
+        "font-weight",
+        &[
+            "font-thin", "font-extralight", "font-light", "font-normal", "font-medium", "font-semibold", "font-bold",
+            "font-extrabold", "font-black",
+        ],
+    ),
+];
+
+/// Utilities that set the same property through a value suffix. Longer prefixes come first.
+const PREFIX_FAMILIES: &[(&str, &str)] = &[
+    ("gap-x-", "column-gap"),
+    ("gap-y-", "row-gap"),
+    ("gap-", "gap"),
+    ("px-", "padding-x"),
+    ("py-", "padding-y"),
+    ("pt-", "padding-top"),
+    ("pr-", "padding-right"),
+    ("pb-", "padding-bottom"),
+    ("pl-", "padding-left"),
+    ("p-", "padding"),
+    ("mx-", "margin-x"),
+    ("my-", "margin-y"),
+    ("mt-", "margin-top"),
+    ("mr-", "margin-right"),
+    ("mb-", "margin-bottom"),
+    ("ml-", "margin-left"),
+    ("m-", "margin"),
+    ("min-w-", "min-width"),
+    ("max-w-", "max-width"),
+    ("w-", "width"),
+    ("min-h-", "min-height"),
+    ("max-h-", "max-height"),
+    ("h-", "height"),
+    ("z-", "z-index"),
+    ("opacity-", "opacity"),
+    ("justify-items-", "justify-items"),
+    ("justify-self-", "justify-self"),
+    ("justify-", "justify-content"),
+    ("items-", "align-items"),
+];
+
+/// Two or more utilities on one element that set the same property under the same variants.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub property: &'static str,
+    pub classes: Vec<String>,
+}
+
+/// A conflict located in a scanned file.
+#[derive(Debug, Clone)]
+pub struct ConflictFinding {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub conflict: Conflict,
+}
+
+fn utility_property(utility: &str) -> Option<&'static str> {
+    let utility = utility.strip_prefix('-').unwrap_or(utility);
+    KEYWORD_FAMILIES
+        .iter()
+        .find(|(_, keywords)| keywords.contains(&utility))
+        .map(|(property, _)| *property)
+        .or_else(|| {
+            PREFIX_FAMILIES
+                .iter()
+                .find(|(prefix, _)| utility.len() > prefix.len() && utility.starts_with(prefix))
+                .map(|(_, property)| *property)
+        })
+}
+
+/// Contradictory utilities in one element's class list. `hover:p-2 p-4` is not a conflict.
+pub fn find_conflicts(classnames: &[String]) -> Vec<Conflict> {
+    let mut by_property: BTreeMap<(&str, &'static str), Vec<String>> = BTreeMap::new();
+    for class in classnames {
+        let (variants, utility) = match class.rfind(':') {
+            Some(i) => (&class[..i], &class[i + 1..]),
+            None => ("", class.as_str()),
+        };
+        if let Some(property) = utility_property(utility) {
+            let classes = by_property.entry((variants, property)).or_default();
+            if !classes.contains(class) {
+                classes.push(class.clone());
+            }
+        }
+    }
+
+    by_property
+        .into_iter()
+        .filter(|(_, classes)| classes.len() > 1)
+        .map(|((_, property), classes)| Conflict { property, classes })
+        .collect()
+}
+
+/// An element's class list, located in a scanned file.
+#[derive(Debug, Clone)]
+pub struct ClassSetOccurrence {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub classnames: Vec<String>,
+}
+
+/// Elements with identical or highly similar class lists.
+#[derive(Debug, Clone)]
+pub struct ClassSetCluster {
+    /// Classes every element in the cluster carries; a candidate for a named group.
+    pub shared: Vec<String>,
+    pub occurrences: Vec<ClassSetOccurrence>,
+}
+
+fn jaccard(a: &BTreeSet<&str>, b: &BTreeSet<&str>) -> f64 {
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    if union == 0 { 0.0 } else { intersection as f64 / union as f64 }
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Clusters class lists of at least `min_classes` classes whose Jaccard similarity reaches `threshold`.
+///
+/// A `threshold` of `1.0` only reports exact duplicates. Clusters are ordered by occurrence count.
+pub fn cluster_class_sets(
+    occurrences: Vec<ClassSetOccurrence>,
+    min_classes: usize,
+    threshold: f64,
+) -> Vec<ClassSetCluster> {
+    let mut by_set: BTreeMap<BTreeSet<String>, Vec<ClassSetOccurrence>> = BTreeMap::new();
+    for occurrence in occurrences {
+        let set: BTreeSet<String> = occurrence.classnames.iter().filter(|c| *c != "id").cloned().collect();
+        if set.len() >= min_classes {
+            by_set.entry(set).or_default().push(occurrence);
+        }
+    }
+
+    let sets: Vec<_> = by_set.into_iter().collect();
+    let borrowed: Vec<BTreeSet<&str>> = sets.iter().map(|(set, _)| set.iter().map(String::as_str).collect()).collect();
+    let mut parents: Vec<usize> = (0..sets.len()).collect();
+    if threshold < 1.0 {
+        for i in 0..sets.len() {
+            for j in i + 1..sets.len() {
+                if jaccard(&borrowed[i], &borrowed[j]) >= threshold {
+                    let (a, b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    parents[a] = b;
+                }
+            }
+        }
+    }
+
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..sets.len() {
+        let root = find_root(&mut parents, i);
+        members.entry(root).or_default().push(i);
+    }
+
+    let mut clusters: Vec<ClassSetCluster> = members
+        .into_values()
+        .filter_map(|indices| {
+            let mut shared = sets[indices[0]].0.clone();
+            for &i in &indices[1..] {
+                shared.retain(|c| sets[i].0.contains(c));
+            }
+            let occurrences: Vec<_> = indices.iter().flat_map(|&i| sets[i].1.iter().cloned()).collect();
+            (occurrences.len() > 1 && !shared.is_empty()).then(|| ClassSetCluster {
+                shared: shared.into_iter().collect(),
+                occurrences,
+            })
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.occurrences.len().cmp(&a.occurrences.len()).then_with(|| a.shared.cmp(&b.shared)));
+    clusters
+}
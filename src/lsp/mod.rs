@@ -0,0 +1,713 @@
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, GotoDefinition, HoverRequest, InlayHintRequest, References, Rename,
+    Request as LspRequest,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability,
+    CodeActionResponse, CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, InlayHint, InlayHintLabel, InlayHintParams, Location,
+    MarkupContent, MarkupKind, NumberOrString, OneOf, Position, PublishDiagnosticsParams, Range, ReferenceParams,
+    RenameParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::codemod::{ClassEdit, Codemod};
+use crate::codes::UNRESOLVED_CLASS;
+use crate::css::{render_css, CssOptions};
+use crate::extract::ElementExtraction;
+use crate::group::extract_group;
+use crate::paths;
+use crate::scanner::Scanner;
+use crate::stylesheet::{parse_rules, Rule};
+use crate::symbols::Symbol;
+use crate::utility::{escape_class, parse_class, variant_expansion};
+
+/// A class token inside a class attribute, located by UTF-16 line/column.
+#[derive(Debug, Clone)]
+pub struct ClassToken {
+    pub name: String,
+    pub line: u32,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl ClassToken {
+    pub fn range(&self) -> Range {
+        Range::new(
+            Position::new(self.line, self.start),
+            Position::new(self.line, self.end),
+        )
+    }
+
+    fn contains(&self, position: Position) -> bool {
+        position.line == self.line && position.character >= self.start && position.character <= self.end
+    }
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.encode_utf16().count() as u32
+}
+
+fn attribute_pattern(attributes: &[String]) -> String {
+    attributes
+        .iter()
+        .map(|a| regex::escape(a))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// The value of a literal class attribute, located by UTF-16 line/column between its quotes.
+#[derive(Debug, Clone)]
+pub struct ClassAttribute {
+    pub line: u32,
+    pub start: u32,
+    pub end: u32,
+    /// Byte offset of the value in the document.
+    pub offset: usize,
+    pub tokens: Vec<ClassToken>,
+}
+
+impl ClassAttribute {
+    pub fn range(&self) -> Range {
+        Range::new(
+            Position::new(self.line, self.start),
+            Position::new(self.line, self.end),
+        )
+    }
+}
+
+/// Every literal class attribute in `text`.
+pub fn class_attributes(text: &str, attributes: &[String]) -> Vec<ClassAttribute> {
+    let re = Regex::new(&format!(
+        r#"\b(?:{})\s*=\s*\{{?\s*(?:"([^"]*)"|'([^']*)'|`([^`$]*)`)"#,
+        attribute_pattern(attributes)
+    ))
+    .unwrap();
+
+    let mut found = Vec::new();
+    let mut line_offset = 0;
+    for (line_index, line) in text.split_inclusive('\n').enumerate() {
+        let line_start = line_offset;
+        line_offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        for caps in re.captures_iter(line) {
+            let Some(value) = caps.get(1).or(caps.get(2)).or(caps.get(3)) else {
+                continue;
+            };
+            let mut tokens = Vec::new();
+            let mut offset = value.start();
+            for part in value.as_str().split(' ') {
+                if !part.trim().is_empty() {
+                    let part_start = offset + (part.len() - part.trim_start().len());
+                    let name = part.trim();
+                    let start = utf16_len(&line[..part_start]);
+                    tokens.push(ClassToken {
+                        name: name.to_string(),
+                        line: line_index as u32,
+                        start,
+                        end: start + utf16_len(name),
+                    });
+                }
+                offset += part.len() + 1;
+            }
+            found.push(ClassAttribute {
+                line: line_index as u32,
+                start: utf16_len(&line[..value.start()]),
+                end: utf16_len(&line[..value.end()]),
+                offset: line_start + value.start(),
+                tokens,
+            });
+        }
+    }
+    found
+}
+
+/// Every whitespace-separated token of every literal class attribute in `text`.
+pub fn class_tokens(text: &str, attributes: &[String]) -> Vec<ClassToken> {
+    class_attributes(text, attributes).into_iter().flat_map(|attribute| attribute.tokens).collect()
+}
+
+/// The range covering all of `text`, for edits that replace a whole document.
+fn full_range(text: &str) -> Range {
+    let lines = text.split('\n').count() as u32;
+    let last = text.rsplit('\n').next().unwrap_or_default();
+    Range::new(Position::new(0, 0), Position::new(lines - 1, utf16_len(last)))
+}
+
+fn byte_offset(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= character {
+            return index;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line.len()
+}
+
+struct Server {
+    scanner: Scanner,
+    documents: HashMap<Url, String>,
+    stylesheets: Vec<(PathBuf, Vec<Rule>)>,
+}
+
+impl Server {
+    fn new(mut scanner: Scanner) -> Self {
+        scanner.index();
+        scanner.open_symbols();
+        scanner.save_symbols();
+        let stylesheets = scanner.stylesheets();
+        Server {
+            scanner,
+            documents: HashMap::new(),
+            stylesheets,
+        }
+    }
+
+    fn known_classes(&self) -> HashSet<String> {
+        let mut classes = self.scanner.classnames.clone();
+        for (_, rules) in &self.stylesheets {
+            for rule in rules {
+                classes.extend(rule.classes());
+            }
+        }
+        classes
+    }
+
+    fn generated_rules(&self, class: &str) -> Vec<Rule> {
+        let classnames = HashSet::from([class.to_string()]);
+        // Hovers show the rule as generated, not lowered or minified for the configured targets.
+        let options = CssOptions { targets: Vec::new(), minify: false, ..self.scanner.css_options.clone() };
+        parse_rules(&render_css(&classnames, &HashSet::new(), &options))
+    }
+
+    fn handwritten_rules(&self, class: &str) -> Vec<(&PathBuf, &Rule)> {
+        self.stylesheets
+            .iter()
+            .flat_map(|(path, rules)| rules.iter().map(move |rule| (path, rule)))
+            .filter(|(_, rule)| rule.classes().iter().any(|c| c == class))
+            .collect()
+    }
+
+    fn resolves(&self, class: &str) -> bool {
+        self.generated_rules(class).iter().any(Rule::has_declarations)
+            || self.handwritten_rules(class).iter().any(|(_, rule)| rule.has_declarations())
+    }
+
+    /// Whether `token` should resolve to CSS but does not; the id trigger and group calls never do.
+    fn is_unknown(&self, token: &ClassToken) -> bool {
+        token.name != self.scanner.options.id_trigger && !token.name.contains('(') && !self.resolves(&token.name)
+    }
+
+    /// The elements of the open document at `uri` as a scan would rewrite them.
+    fn elements(&self, uri: &Url) -> Vec<ElementExtraction> {
+        let (Some(text), Ok(path)) = (self.documents.get(uri), uri.to_file_path()) else {
+            return Vec::new();
+        };
+        self.scanner.preview(&paths::normalize(&path), text).map(|extraction| extraction.elements).unwrap_or_default()
+    }
+
+    fn token_at(&self, uri: &Url, position: Position) -> Option<ClassToken> {
+        let text = self.documents.get(uri)?;
+        class_tokens(text, &self.scanner.options.attributes)
+            .into_iter()
+            .find(|token| token.contains(position))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position.position;
+        let text = self.documents.get(&params.text_document_position.text_document.uri)?;
+        let line = text.lines().nth(position.line as usize)?;
+        let prefix = &line[..byte_offset(line, position.character)];
+
+        let context = Regex::new(&format!(
+            r#"\b(?:{})\s*=\s*\{{?\s*["'`][^"'`]*$"#,
+            attribute_pattern(&self.scanner.options.attributes)
+        ))
+        .unwrap();
+        if !context.is_match(prefix) {
+            return None;
+        }
+
+        let mut classes: Vec<_> = self.known_classes().into_iter().collect();
+        classes.sort();
+        let items = classes
+            .into_iter()
+            .map(|class| CompletionItem {
+                detail: self.generated_rules(&class).first().map(|rule| format!("{} {{ {} }}", rule.selector, rule.body)),
+                label: class,
+                kind: Some(CompletionItemKind::CONSTANT),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params.position;
+        let token = self.token_at(&params.text_document_position_params.text_document.uri, position)?;
+
+        let mut blocks = Vec::new();
+        for rule in self.generated_rules(&token.name) {
+            blocks.push(format!("```css\n{} {{ {} }}\n```", rule.selector, rule.body));
+        }
+        for (path, rule) in self.handwritten_rules(&token.name) {
+            blocks.push(format!(
+                "```css\n{} {{ {} }}\n```\n{}:{}",
+                rule.selector,
+                rule.body,
+                path.display(),
+                rule.line + 1
+            ));
+        }
+        let variants: Vec<String> = parse_class(&token.name)
+            .variants
+            .iter()
+            .map(|variant| match variant_expansion(variant, &self.scanner.css_options.screens) {
+                Some(expansion) => format!("- `{}:` → `{}`", variant, expansion),
+                None => format!("- `{}:` is not a known variant", variant),
+            })
+            .collect();
+        if !variants.is_empty() {
+            blocks.push(format!("**Variants**\n{}", variants.join("\n")));
+        }
+        if blocks.is_empty() {
+            return None;
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: blocks.join("\n\n"),
+            }),
+            range: Some(token.range()),
+        })
+    }
+
+    /// The rules for `class` in the generated stylesheet and the handwritten ones.
+    fn class_definitions(&self, class: &str) -> Vec<Location> {
+        let mut locations = Vec::new();
+        if let Ok(output_path) = paths::canonicalize(&self.scanner.output_path) {
+            let output_css = std::fs::read_to_string(&output_path).unwrap_or_default();
+            locations.extend(
+                parse_rules(&output_css)
+                    .iter()
+                    .filter(|rule| rule.classes().iter().any(|c| c == class))
+                    .filter_map(|rule| location(&output_path, rule)),
+            );
+        }
+        locations.extend(
+            self.handwritten_rules(class)
+                .into_iter()
+                .filter_map(|(path, rule)| location(&paths::canonicalize(&path).ok()?, rule)),
+        );
+        locations
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params.position;
+        let token = self.token_at(&params.text_document_position_params.text_document.uri, position)?;
+
+        let mut locations = self.class_definitions(&token.name);
+        match locations.len() {
+            0 => None,
+            1 => Some(GotoDefinitionResponse::Scalar(locations.remove(0))),
+            _ => Some(GotoDefinitionResponse::Array(locations)),
+        }
+    }
+
+    /// The classes and ids of the stylesheet selector at `position`: the one under the cursor, or
+    /// every one of the rule when the cursor is elsewhere in it.
+    fn selector_at(&self, text: &str, position: Position) -> (Vec<String>, Vec<String>) {
+        let Some(rule) = parse_rules(text).into_iter().rev().find(|rule| rule.line <= position.line as usize) else {
+            return (Vec::new(), Vec::new());
+        };
+        let (classes, ids) = (rule.classes(), rule.ids());
+        let line = text.lines().nth(position.line as usize).unwrap_or_default();
+        let cursor = byte_offset(line, position.character);
+        let under_cursor = |prefix: char, name: &str| {
+            let needle = format!("{}{}", prefix, escape_class(name));
+            line.match_indices(&needle).any(|(start, _)| (start..=start + needle.len()).contains(&cursor))
+        };
+        if let Some(class) = classes.iter().find(|class| under_cursor('.', class)) {
+            return (vec![class.clone()], Vec::new());
+        }
+        if let Some(id) = ids.iter().find(|id| under_cursor('#', id)) {
+            return (Vec::new(), vec![id.clone()]);
+        }
+        (classes, ids)
+    }
+
+    /// Every element using the class under the cursor, or the classes and ids of the stylesheet
+    /// selector under it.
+    fn references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let position = params.text_document_position.position;
+        let uri = &params.text_document_position.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let (classes, ids) = if uri.path().ends_with(".css") {
+            self.selector_at(text, position)
+        } else {
+            (vec![self.token_at(uri, position)?.name], Vec::new())
+        };
+
+        let symbols = self.scanner.symbols.as_ref()?;
+        let site = |(path, symbol): (&std::path::Path, &Symbol)| {
+            let uri = Url::from_file_path(paths::canonicalize(path).ok()?).ok()?;
+            let start = Position::new(symbol.line, symbol.column);
+            Some(Location::new(uri, Range::new(start, Position::new(symbol.end_line, symbol.end_column))))
+        };
+        let mut locations = Vec::new();
+        for class in &classes {
+            locations.extend(symbols.class_sites(class).into_iter().filter_map(site));
+            if params.context.include_declaration {
+                locations.extend(self.class_definitions(class));
+            }
+        }
+        for id in &ids {
+            locations.extend(symbols.id_sites(id).into_iter().filter_map(site));
+        }
+        Some(locations)
+    }
+
+    fn diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
+        let Some(text) = self.documents.get(uri) else {
+            return Vec::new();
+        };
+        class_tokens(text, &self.scanner.options.attributes)
+            .into_iter()
+            .filter(|token| self.is_unknown(token))
+            .map(|token| Diagnostic {
+                range: token.range(),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String(UNRESOLVED_CLASS.to_string())),
+                source: Some("dx".to_string()),
+                message: format!("`{}` does not resolve to any CSS declarations", token.name),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// After each class attribute: the id dx generates for its element and how many of its classes
+    /// are unknown.
+    fn inlay_hints(&self, params: InlayHintParams) -> Option<Vec<InlayHint>> {
+        let uri = &params.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let elements = self.elements(uri);
+        let lines = params.range.start.line..=params.range.end.line;
+        let hints = class_attributes(text, &self.scanner.options.attributes)
+            .into_iter()
+            .filter(|attribute| lines.contains(&attribute.line))
+            .filter_map(|attribute| {
+                let mut parts = Vec::new();
+                let id = owner(&elements, &attribute).filter(|el| el.generated_id).and_then(|el| el.id.as_ref());
+                if let Some(id) = id {
+                    parts.push(format!("#{}", id));
+                }
+                let unknown = attribute.tokens.iter().filter(|token| self.is_unknown(token)).count();
+                if unknown > 0 {
+                    parts.push(format!("{} unknown", unknown));
+                }
+                if parts.is_empty() {
+                    return None;
+                }
+                Some(InlayHint {
+                    // Past the closing quote.
+                    position: Position::new(attribute.line, attribute.end + 1),
+                    label: InlayHintLabel::String(parts.join(" · ")),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                })
+            })
+            .collect();
+        Some(hints)
+    }
+
+    fn code_actions(&self, params: CodeActionParams) -> Option<CodeActionResponse> {
+        let uri = &params.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let elements = self.elements(uri);
+        let trigger = &self.scanner.options.id_trigger;
+        let lines = params.range.start.line..=params.range.end.line;
+        let mut actions = Vec::new();
+        for attribute in class_attributes(text, &self.scanner.options.attributes) {
+            if !lines.contains(&attribute.line) {
+                continue;
+            }
+            if let Some(action) = self.extract_group_action(uri, text, &attribute, params.range) {
+                actions.push(action);
+            }
+            if attribute.tokens.iter().any(|token| token.name == *trigger) {
+                continue;
+            }
+            // Elements with a hand-written id keep it, so the trigger would do nothing.
+            if owner(&elements, &attribute).is_none_or(|el| el.id.is_some()) {
+                continue;
+            }
+            let new_text = if attribute.tokens.is_empty() { trigger.clone() } else { format!(" {}", trigger) };
+            let end = attribute.range().end;
+            let edit = TextEdit::new(Range::new(end, end), new_text);
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Add `{}` to generate an id", trigger),
+                kind: Some(CodeActionKind::REFACTOR),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+        Some(actions)
+    }
+
+    /// "Extract to dx group" for the classes of `attribute` that `selection` touches.
+    fn extract_group_action(
+        &self,
+        uri: &Url,
+        text: &str,
+        attribute: &ClassAttribute,
+        selection: Range,
+    ) -> Option<CodeActionOrCommand> {
+        let selected: Vec<String> = attribute
+            .tokens
+            .iter()
+            .filter(|token| token.range().start < selection.end && selection.start < token.range().end)
+            .filter(|token| !token.name.contains('('))
+            .map(|token| token.name.clone())
+            .collect();
+        if selected.len() < 2 {
+            return None;
+        }
+        let name = (1..)
+            .map(|n| format!("group{}", n))
+            .find(|name| !Regex::new(&format!(r"\b{}\b", name)).is_ok_and(|re| re.is_match(text)))?;
+        let line = text.lines().nth(attribute.line as usize)?;
+        let value = &line[byte_offset(line, attribute.start)..byte_offset(line, attribute.end)];
+        let extraction = extract_group(value, &selected, &name)?;
+
+        let edits = vec![
+            // The transformer hoists its declarations to the top of the module.
+            TextEdit::new(Range::default(), format!("{}\n", extraction.declaration)),
+            TextEdit::new(attribute.range(), extraction.value),
+        ];
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Extract to dx group".to_string(),
+            kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }))
+    }
+
+    /// Renames the class under the cursor in every indexed source, through the same rewrite
+    /// `dx codemod` uses, and regenerates the stylesheet in the same edit.
+    fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let position = params.text_document_position.position;
+        let Some(token) = self.token_at(&params.text_document_position.text_document.uri, position) else {
+            return Ok(None);
+        };
+        if token.name.contains('(') {
+            return Err("group references cannot be renamed".to_string());
+        }
+        // Renaming `hover:btn` renames `btn` under every variant.
+        let old = parse_class(&token.name).utility.to_string();
+        let variants = &token.name[..token.name.len() - old.len()];
+        let new = params.new_name.strip_prefix(variants).unwrap_or(&params.new_name).to_string();
+        if new.is_empty() || new.contains(char::is_whitespace) || parse_class(&new).utility != new {
+            return Err(format!("`{}` is not a single class name without variants", params.new_name));
+        }
+        let codemod = Codemod { edits: HashMap::from([(old.clone(), ClassEdit::Rename(new.clone()))]) };
+
+        let open: HashMap<PathBuf, (&Url, &String)> = self
+            .documents
+            .iter()
+            .filter_map(|(uri, text)| Some((paths::normalize(&uri.to_file_path().ok()?), (uri, text))))
+            .collect();
+        let mut files: HashSet<PathBuf> = self
+            .scanner
+            .file_map
+            .iter()
+            .filter(|(_, (classnames, _))| classnames.iter().any(|c| parse_class(c).utility == old))
+            .map(|(path, _)| path.clone())
+            .collect();
+        files.extend(open.keys().cloned());
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for path in files {
+            let (uri, source) = match open.get(&path) {
+                Some((uri, text)) => ((*uri).clone(), (*text).clone()),
+                None => {
+                    let Ok(source) = std::fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    let Ok(uri) = Url::from_file_path(paths::canonicalize(&path).unwrap_or(path.clone())) else {
+                        continue;
+                    };
+                    (uri, source)
+                }
+            };
+            let options = self.scanner.options_for(&path);
+            if let Ok(Some(rewrite)) = codemod.apply(&path, &source, &options) {
+                changes.insert(uri, vec![TextEdit::new(full_range(&source), rewrite.code)]);
+            }
+        }
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let output_path = paths::canonicalize(&self.scanner.output_path).unwrap_or(self.scanner.output_path.clone());
+        if let (Ok(css), Ok(uri)) = (std::fs::read_to_string(&output_path), Url::from_file_path(&output_path)) {
+            let renames = HashMap::from([(old, new)]);
+            changes.insert(uri, vec![TextEdit::new(full_range(&css), self.scanner.css_with_renames(&renames))]);
+        }
+        Ok(Some(WorkspaceEdit { changes: Some(changes), ..Default::default() }))
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        let result = match request.method.as_str() {
+            Completion::METHOD => parse_params(request.params).map(|p| to_value(self.completion(p))),
+            HoverRequest::METHOD => parse_params(request.params).map(|p| to_value(self.hover(p))),
+            GotoDefinition::METHOD => parse_params(request.params).map(|p| to_value(self.definition(p))),
+            InlayHintRequest::METHOD => parse_params(request.params).map(|p| to_value(self.inlay_hints(p))),
+            CodeActionRequest::METHOD => parse_params(request.params).map(|p| to_value(self.code_actions(p))),
+            Rename::METHOD => parse_params(request.params).and_then(|p| self.rename(p)).map(to_value),
+            References::METHOD => parse_params(request.params).map(|p| to_value(self.references(p))),
+            method => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unknown method {}", method),
+                );
+            }
+        };
+        response(request.id, result)
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Option<Url> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents.insert(uri.clone(), params.text_document.text);
+                Some(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                let change = params.content_changes.into_iter().last()?;
+                self.documents.insert(uri.clone(), change.text);
+                Some(uri)
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: lsp_types::DidSaveTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                let path = params.text_document.uri.to_file_path().ok()?;
+                if path.extension().and_then(|e| e.to_str()) == Some("css") {
+                    self.stylesheets = self.scanner.stylesheets();
+                } else {
+                    let path = paths::normalize(&path);
+                    self.scanner.index_file(&path);
+                    self.scanner.update_symbols(&path);
+                }
+                Some(params.text_document.uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The element whose opening tag holds `attribute`.
+fn owner<'a>(elements: &'a [ElementExtraction], attribute: &ClassAttribute) -> Option<&'a ElementExtraction> {
+    elements.iter().find(|el| (el.start as usize..el.end as usize).contains(&attribute.offset))
+}
+
+fn location(path: &std::path::Path, rule: &Rule) -> Option<Location> {
+    let uri = Url::from_file_path(path).ok()?;
+    let line = rule.line as u32;
+    Some(Location::new(uri, Range::new(Position::new(line, 0), Position::new(line, 0))))
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, String> {
+    serde_json::from_value(params).map_err(|e| e.to_string())
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn response(id: RequestId, result: Result<Value, String>) -> Response {
+    match result {
+        Ok(value) => Response::new_ok(id, value),
+        Err(message) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+    }
+}
+
+/// Runs the language server over stdin/stdout until the client shuts it down.
+pub fn run(scanner: Scanner) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["\"".to_string(), "'".to_string(), " ".to_string()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    })?;
+    connection.initialize(capabilities)?;
+
+    let mut server = Server::new(scanner);
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(uri) = server.handle_notification(notification) {
+                    let params = PublishDiagnosticsParams {
+                        diagnostics: server.diagnostics(&uri),
+                        uri,
+                        version: None,
+                    };
+                    connection.sender.send(Message::Notification(Notification::new(
+                        PublishDiagnostics::METHOD.to_string(),
+                        params,
+                    )))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    server.scanner.save_symbols();
+    io_threads.join()?;
+    Ok(())
+}
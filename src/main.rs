@@ -1,119 +1,360 @@
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
-use glob::glob;
-use memmap2::Mmap;
-use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::env;
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::Table;
+use indicatif::{ProgressBar, ProgressStyle};
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use std::sync::Arc;
-use std::thread;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
-use swc_common::{SourceMap, FileName};
-use swc_ecma_codegen::{text_writer::JsWriter, Emitter};
-use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax, TsSyntax};
-use swc_ecma_visit::{VisitMutWith};
-
-pub mod id;
-pub mod io;
-pub mod group;
-use id::{determine_css_entities_and_updates, IdApplier};
-use io::{read_existing_css, write_css, write_file};
-use group::GroupTransformer;
-
-fn parse_and_modify_file(
-    path: &Path,
-    cm: &Arc<SourceMap>,
-) -> Option<(HashSet<String>, HashSet<String>, String, String)> {
-    let file = std::fs::File::open(path).ok()?;
-    let mmap = unsafe { Mmap::map(&file).ok()? };
-    let source = String::from_utf8_lossy(&mmap).to_string();
-    let fm = cm.new_source_file(
-        Arc::new(FileName::Real(path.to_path_buf())),
-        source.clone(),
-    );
-    let lexer = Lexer::new(
-        Syntax::Typescript(TsSyntax { tsx: true, ..Default::default() }),
-        Default::default(),
-        StringInput::from(&*fm),
-        None,
-    );
-    let mut parser = Parser::new_from(lexer);
-    let mut module = match parser.parse_module() {
-        Ok(module) => module,
-        Err(_) => return None,
-    };
 
-    let mut group_transformer = GroupTransformer::new();
-    module.visit_mut_with(&mut group_transformer);
-    let resolved_classes = group_transformer.resolved_classes;
+use dx::assets::{read_asset_manifest, Compression};
+use dx::audit::unused_selectors;
+use dx::baseline::{Baseline, BASELINE_FILE};
+use dx::cache::{Approvals, Cache, APPROVALS_FILE};
+use dx::codes;
+use dx::component::ElementSelector;
+use dx::config::{Config, Severity, CONFIG_FILE};
+use dx::extract::Diagnostic;
+use dx::lint::{cluster_class_sets, ClassSetOccurrence, ConflictFinding};
+use dx::graph::ImportGraph;
+use dx::intent::{IntentLog, INTENT_FILE};
+use dx::meta::FileMeta;
+use dx::serve::{normalize_addr, DevServer};
+use dx::undo::{Journal, UNDO_DIR};
+use dx::stylesheet::parse_rules;
+use dx::symbols::SYMBOLS_FILE;
+use dx::plugin::PluginRegistry;
+use dx::report::{line_column, Finding, Level};
+use dx::watch::WatchEvent;
+use dx::workspace;
+use dx::scanner::ReviewRewrite;
+use dx::{ChangeSummary, DeadCode, ScanSummary, Scanner};
+
+#[derive(Parser)]
+#[command(name = "dx", about = "Enhance Developer Experience!")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Write per-file classes, ids and element spans to this JSON file
+    #[arg(long, global = true)]
+    emit_meta: Option<PathBuf>,
+
+    /// Write each story file's classes, generated ids and CSS to this JSON file (default dx-stories.json)
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "dx-stories.json")]
+    emit_stories: Option<PathBuf>,
+
+    /// Write generated ids per element position for SSR; a .js/.mjs/.ts path emits an importable module
+    #[arg(long, global = true)]
+    emit_hydration: Option<PathBuf>,
+
+    /// Write a TypeScript declaration of every known class name (default dx-classes.d.ts)
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "dx-classes.d.ts")]
+    emit_types: Option<PathBuf>,
+
+    /// Write every element's test id and position per file to this JSON file (default dx-test-ids.json)
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "dx-test-ids.json")]
+    emit_test_ids: Option<PathBuf>,
+
+    /// Expose Prometheus metrics on this address (e.g. :9464) in watch and daemon mode
+    #[arg(long, global = true)]
+    metrics_addr: Option<String>,
+
+    /// Append raw watcher events, debounce decisions and processing outcomes to this NDJSON file
+    #[arg(long, global = true)]
+    event_log: Option<PathBuf>,
+
+    /// Print a colored unified diff of every source rewrite; `--diff=only` prints it without writing
+    #[arg(long, global = true, value_enum, num_args = 0..=1, default_missing_value = "show")]
+    diff: Option<DiffMode>,
+
+    /// Show the diff and ask before the first rewrite of each file; approvals are kept in .dx/approved.json
+    #[arg(long, global = true, conflicts_with = "diff")]
+    interactive: bool,
+
+    /// Fail `check`, `lint` and `duplicates` (exit 1) when they report more than N conflicts or duplicate clusters
+    #[arg(long, global = true)]
+    max_warnings: Option<usize>,
+
+    /// Also report the findings of `check`, `fmt-check`, `lint` and `duplicates` as JUnit XML, SARIF, JSON or
+    /// GitHub Actions annotations (printed to stdout)
+    #[arg(long, global = true, value_enum)]
+    report: Option<ReportFormat>,
+
+    /// Where --report writes (default dx-report.xml, dx-report.sarif or dx-report.json)
+    #[arg(long, global = true, requires = "report")]
+    report_path: Option<PathBuf>,
 
-    let (final_classnames, final_ids, id_updates) = determine_css_entities_and_updates(&module, &resolved_classes);
+    /// After each build, also write every stylesheet precompressed next to it (styles.css.br, styles.css.gz)
+    #[arg(long, global = true, value_enum, value_delimiter = ',')]
+    compress: Vec<CompressFormat>,
 
-    if !id_updates.is_empty() {
-        let mut applier = IdApplier { id_map: &id_updates };
-        module.visit_mut_with(&mut applier);
+    /// Print extra diagnostics, such as how many file watches were installed
+    #[arg(long, global = true)]
+    verbose: bool,
+
+    /// Only run for workspace packages matching this name, glob (e.g. @app/*) or ./path
+    #[arg(long, global = true)]
+    filter: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Watch ./src and keep styles.css in sync (default)
+    Watch {
+        /// Serve styles.css and a live-reload WebSocket on this address (e.g. :4545)
+        #[arg(long)]
+        serve: Option<String>,
+        /// Show a live dashboard instead of log lines
+        #[arg(long)]
+        ui: bool,
+    },
+    /// List every named group, its expansion and usage count
+    Groups,
+    /// Serve JSON-RPC requests over stdio (or a unix socket) from a warm index
+    Daemon {
+        /// Listen on this unix socket instead of stdin/stdout
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Run the language server over stdio
+    Lsp,
+    /// Report selectors in handwritten stylesheets that no scanned source uses
+    Audit {
+        /// Stylesheet to audit (defaults to the configured stylesheets)
+        #[arg(long)]
+        css: Vec<PathBuf>,
+    },
+    /// Report contradictory utilities such as `p-2 p-4` on one element
+    Lint {
+        /// Accept the current conflicts into .dx/baseline.json so later runs only report new ones
+        #[arg(long)]
+        update_baseline: bool,
+    },
+    /// Cluster elements with identical or similar class lists and suggest groups
+    Duplicates {
+        /// Minimum Jaccard similarity for two class lists to cluster (1.0 = exact duplicates only)
+        #[arg(long, default_value_t = 0.8)]
+        threshold: f64,
+        /// Ignore elements with fewer classes than this
+        #[arg(long, default_value_t = 3)]
+        min_classes: usize,
+        /// Accept the current clusters into .dx/baseline.json so later runs only report new ones
+        #[arg(long)]
+        update_baseline: bool,
+    },
+    /// Explain a diagnostic code such as DX0102
+    Explain {
+        code: String,
+    },
+    /// Scan once, rewrite sources and write styles.css, then exit
+    Build {
+        /// Only re-extract files changed since this git ref; take the rest from the cache
+        #[arg(long)]
+        since: Option<String>,
+        /// Also write one stylesheet per Next.js App Router route to styles/routes/ plus dx-routes.json
+        #[arg(long)]
+        routes: bool,
+        /// Write classes to styles.css under short hashed names plus dx-classmap.json (implies features.obfuscate)
+        #[arg(long)]
+        obfuscate: bool,
+        /// Only generate CSS; never rewrite sources with ids or expanded groups (same as `rewrite = false`)
+        #[arg(long)]
+        no_rewrite: bool,
+        /// Build from the sources alone, ignoring caches and existing outputs, then build again and fail
+        /// unless both runs write byte-identical outputs free of machine-specific paths
+        #[arg(long, conflicts_with = "since")]
+        deterministic: bool,
+    },
+    /// Exit non-zero if building would change styles.css or rewrite any source
+    Check {
+        /// Check the staged contents of staged files instead of the working tree
+        #[arg(long)]
+        staged: bool,
+    },
+    /// Rewrite every source twice without writing and fail if the second pass changes anything
+    FmtCheck,
+    /// Inspect the config file format
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Translate tailwind.config.js or uno.config.ts into dx.config.toml and report classes dx would not cover
+    Migrate {
+        /// The config to migrate (default: the first tailwind or uno config found)
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// Replace an existing dx.config.toml
+        #[arg(long)]
+        force: bool,
+    },
+    /// Manage git hooks
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+    /// Drop classes only used by files no entry point imports from styles.css
+    Prune {
+        /// Also delete per-route stylesheets in styles/routes/ whose route no longer exists
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Print every file and line where a class is used
+    Why {
+        /// The class to look for
+        class: String,
+        /// Treat CLASS as a regular expression
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Rename, split or remove classes across every scanned source, as listed in a JSON mapping file
+    Codemod {
+        /// JSON object of class to replacement: `"btn-old": "btn"`, `"card": "rounded shadow p-4"`, `"legacy": null`
+        map: PathBuf,
+        /// Print what would change without writing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Finish a batch of writes an earlier run left half applied: rewrite pending sources and regenerate styles.css
+    Repair,
+    /// Restore sources from the journal of contents saved before each rewrite (.dx/undo/)
+    Undo {
+        /// Undo the last N rewrite batches
+        #[arg(long, default_value_t = 1, conflicts_with = "file")]
+        last: usize,
+        /// Undo the last rewrite of this file only
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Move a JSX subtree into a new component file and render the component in its place
+    ExtractComponent {
+        /// The file containing the element
+        file: PathBuf,
+        /// The outermost element whose opening tag starts on this line
+        #[arg(long, required_unless_present = "id", conflicts_with = "id")]
+        line: Option<usize>,
+        /// The element with this id
+        #[arg(long)]
+        id: Option<String>,
+        /// The new component's name; it is written to NAME.<ext> next to FILE
+        #[arg(long)]
+        name: String,
+    },
+}
+
+/// The exit codes CI can rely on; when several apply, the highest wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Exit {
+    Ok = 0,
+    /// Sources or stylesheets would change, or findings exceed `--max-warnings`.
+    WouldChange = 1,
+    ParseErrors = 2,
+    IoErrors = 3,
+}
+
+impl Exit {
+    fn exit_unless_ok(self) {
+        if self != Exit::Ok {
+            std::process::exit(self as i32);
+        }
     }
+}
 
-    let mut output = Vec::new();
-    let mut emitter = Emitter {
-        cfg: Default::default(),
-        cm: cm.clone(),
-        comments: None,
-        wr: JsWriter::new(cm.clone(), "\n", &mut output, None),
-    };
-    emitter.emit_module(&module).ok()?;
-    let modified_code = String::from_utf8(output).ok()?;
-
-    Some((final_classnames, final_ids, modified_code, source))
-}
-
-fn collect_css_entities(
-    path: &Path,
-    cm: &Arc<SourceMap>,
-) -> Option<(HashSet<String>, HashSet<String>)> {
-    let file = std::fs::File::open(path).ok()?;
-    let mmap = unsafe { Mmap::map(&file).ok()? };
-    let source = String::from_utf8_lossy(&mmap);
-    let fm = cm.new_source_file(
-        Arc::new(FileName::Real(path.to_path_buf())),
-        source.into_owned(),
-    );
-    let lexer = Lexer::new(
-        Syntax::Typescript(TsSyntax { tsx: true, ..Default::default() }),
-        Default::default(),
-        StringInput::from(&*fm),
-        None,
-    );
-    let mut parser = Parser::new_from(lexer);
-    let mut module = match parser.parse_module() {
-        Ok(module) => module,
-        Err(_) => return None,
+/// [`Exit::WouldChange`] when `count` findings exceed `--max-warnings`.
+fn warnings_exit(count: usize, max_warnings: Option<usize>) -> Exit {
+    match max_warnings {
+        Some(max) if count > max => {
+            eprintln!(
+                "{} {} warning(s) exceed --max-warnings {}",
+                "✗".bright_red(),
+                count.to_string().bright_red(),
+                max
+            );
+            Exit::WouldChange
+        }
+        _ => Exit::Ok,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    /// JUnit XML for CI test tabs
+    Junit,
+    /// SARIF 2.1.0 for GitHub and GitLab code scanning
+    Sarif,
+    /// A plain JSON array of findings
+    Json,
+    /// `::error` workflow commands that annotate pull request diffs
+    Github,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CompressFormat {
+    /// Brotli (.br)
+    Br,
+    /// gzip (.gz)
+    Gz,
+}
+
+impl From<CompressFormat> for Compression {
+    fn from(format: CompressFormat) -> Self {
+        match format {
+            CompressFormat::Br => Compression::Brotli,
+            CompressFormat::Gz => Compression::Gzip,
+        }
+    }
+}
+
+/// Writes `findings` for `--report`; `suite` names the pass in JUnit reports.
+fn write_report(format: ReportFormat, path: Option<&Path>, suite: &str, findings: &[Finding]) {
+    let base = std::env::current_dir().unwrap_or_default();
+    let (content, default_path) = match format {
+        ReportFormat::Junit => (dx::report::junit(suite, findings, &base), "dx-report.xml"),
+        ReportFormat::Sarif => (dx::report::sarif(findings, &base), "dx-report.sarif"),
+        ReportFormat::Json => (dx::report::json(findings, &base), "dx-report.json"),
+        ReportFormat::Github => {
+            // Workflow commands only take effect on the job's stdout.
+            print!("{}", dx::report::github(findings, &base));
+            return;
+        }
     };
+    let path = path.unwrap_or(Path::new(default_path));
+    match std::fs::write(path, content) {
+        Ok(()) => print_written(path),
+        Err(e) => eprintln!("{} Failed to write {}: {}", "✗".bright_red(), path.display(), e),
+    }
+}
 
-    let mut group_transformer = GroupTransformer::new();
-    module.visit_mut_with(&mut group_transformer);
-    let resolved_classes = group_transformer.resolved_classes;
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiffMode {
+    /// Print the diff and write the rewrite
+    Show,
+    /// Print the diff and leave the file as it is
+    Only,
+}
 
-    let (classnames, ids, _) = determine_css_entities_and_updates(&module, &resolved_classes);
-    Some((classnames, ids))
+#[derive(Subcommand)]
+enum HookAction {
+    /// Install a pre-commit hook that runs `dx check --staged`
+    Install {
+        /// Replace an existing pre-commit hook
+        #[arg(long)]
+        force: bool,
+    },
 }
 
-fn calculate_global_classnames_and_ids(
-    file_map: &HashMap<PathBuf, (HashSet<String>, HashSet<String>)>,
-) -> (HashSet<String>, HashSet<String>) {
-    let classnames = file_map
-        .par_iter()
-        .flat_map(|(_, (classes, _))| classes.clone())
-        .collect();
-    let ids = file_map
-        .par_iter()
-        .flat_map(|(_, (_, ids))| ids.clone())
-        .collect();
-    (classnames, ids)
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a JSON Schema for dx.config.toml, for editor validation and completion
+    Schema,
 }
 
+const PRE_COMMIT_HOOK: &str = "#!/bin/sh\n# Installed by `dx hook install`.\nexec dx check --staged\n";
+
 fn format_duration(duration: Duration) -> String {
     let micros = duration.as_micros();
     if micros < 1000 {
@@ -123,236 +364,1790 @@ fn format_duration(duration: Duration) -> String {
     }
 }
 
-fn initial_scan() -> (
-    HashMap<PathBuf, (HashSet<String>, HashSet<String>)>,
-    HashSet<String>,
-    HashSet<String>,
-) {
+fn print_diff(path: &Path, old: &str, new: &str) {
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let label = path.strip_prefix(&current_dir).unwrap_or(path).display().to_string();
+    let Some(diff) = dx::diff::unified_diff(&label, old, new) else {
+        return;
+    };
+    for line in diff.lines() {
+        let line = if line.starts_with("---") || line.starts_with("+++") {
+            line.bold()
+        } else if line.starts_with("@@") {
+            line.bright_cyan()
+        } else if line.starts_with('-') {
+            line.bright_red()
+        } else if line.starts_with('+') {
+            line.bright_green()
+        } else {
+            line.normal()
+        };
+        println!("{}", line);
+    }
+}
+
+/// Asks before the first rewrite of each file: y approves it, a approves it and every later one
+/// this run, anything else leaves it. Approved files are not asked about again.
+fn interactive_review(root: &Path) -> ReviewRewrite {
+    let approvals_path = root.join(APPROVALS_FILE);
+    let root = root.to_path_buf();
+    let state = Mutex::new((Approvals::load(&approvals_path), false));
+    Box::new(move |path: &Path, old: &str, new: &str| {
+        let relative = path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+        let mut state = state.lock().unwrap();
+        let (approvals, approve_all) = &mut *state;
+        if approvals.files.contains(&relative) {
+            return true;
+        }
+        if !*approve_all {
+            print_diff(path, old, new);
+            print!("{} Rewrite {}? [y/N/a] ", "?".bright_cyan(), relative.display().to_string().bright_blue());
+            let _ = std::io::stdout().flush();
+            let mut answer = String::new();
+            let _ = std::io::stdin().read_line(&mut answer);
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => {}
+                "a" | "all" => *approve_all = true,
+                _ => return false,
+            }
+        }
+        approvals.files.insert(relative);
+        if let Err(e) = approvals.save(&approvals_path) {
+            eprintln!("{} Failed to write {}: {}", "✗".bright_red(), approvals_path.display(), e);
+        }
+        true
+    })
+}
+
+fn initial_scan(scanner: &mut Scanner) {
     println!(
         "{}",
         "🚀 dx-styles starting initial scan...".bold().bright_purple()
     );
-    let start = Instant::now();
-    let cm: Arc<SourceMap> = Default::default();
-    let output_path = PathBuf::from("./styles.css");
 
-    let (existing_classnames, existing_ids) = read_existing_css(&output_path);
+    let summary = with_progress(|on_progress| scanner.warm_start(on_progress));
+    print_scan_summary(scanner, &summary);
+    // The language server creates the symbol index; once it exists the watcher keeps it current.
+    if scanner.root.join(SYMBOLS_FILE).exists() {
+        scanner.open_symbols();
+    }
+}
 
-    let current_dir = env::current_dir().expect("Failed to get current directory");
-    let paths: Vec<_> = glob("./src/**/*.tsx")
-        .expect("Failed to read glob pattern")
-        .filter_map(Result::ok)
-        .map(|path| path.canonicalize().unwrap_or_else(|_| current_dir.join(path)))
-        .collect();
+fn scan_with_progress(scanner: &mut Scanner, changed: Option<&[PathBuf]>) -> ScanSummary {
+    with_progress(|on_progress| match changed {
+        Some(changed) => scanner.scan_changed(changed, on_progress),
+        None => scanner.initial_scan_with_progress(on_progress),
+    })
+}
 
-    let check_results: Vec<_> = paths
-        .par_iter()
-        .filter_map(|path| collect_css_entities(path, &cm))
-        .collect();
+fn with_progress(scan: impl FnOnce(&(dyn Fn(usize, usize) + Sync)) -> ScanSummary) -> ScanSummary {
+    let progress = ProgressBar::new(0);
+    progress.set_style(
+        ProgressStyle::with_template("{spinner:.magenta} [{bar:40.magenta/blue}] {pos}/{len} files \u{2022} {elapsed}")
+            .expect("valid progress template")
+            .progress_chars("=> "),
+    );
+    let on_progress = |done: usize, total: usize| {
+        progress.set_length(total as u64);
+        progress.set_position(done as u64);
+    };
+    let summary = scan(&on_progress);
+    progress.finish_and_clear();
+    summary
+}
+
+fn print_scan_summary(scanner: &Scanner, summary: &ScanSummary) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec!["Scan", ""]);
+    table.add_row(vec!["Files scanned".to_string(), summary.files.to_string()]);
+    table.add_row(vec!["Parse failures".to_string(), summary.parse_failures.to_string()]);
+    if summary.skipped > 0 {
+        table.add_row(vec!["Skipped files".to_string(), summary.skipped.to_string()]);
+    }
+    table.add_row(vec!["Classes".to_string(), summary.classnames.to_string()]);
+    table.add_row(vec!["IDs".to_string(), summary.ids.to_string()]);
+    table.add_row(vec!["Rewritten files".to_string(), summary.rewritten.to_string()]);
+    table.add_row(vec!["Cache hits".to_string(), summary.cache_hits.to_string()]);
+    if summary.warm {
+        table.add_row(vec!["Warm start".to_string(), "restored from snapshot".to_string()]);
+    }
+    if scanner.remote_cache.is_some() {
+        table.add_row(vec!["Remote cache hits".to_string(), summary.remote_hits.to_string()]);
+    }
+    table.add_row(vec!["Total time".to_string(), format_duration(summary.duration)]);
+    println!("{table}");
 
-    let mut expected_classnames = HashSet::new();
-    let mut expected_ids = HashSet::new();
-    for (classes, ids) in &check_results {
-        expected_classnames.extend(classes.clone());
-        expected_ids.extend(ids.clone());
+    let mut skipped: Vec<_> = scanner.skipped.iter().collect();
+    skipped.sort_by(|a, b| a.0.cmp(b.0));
+    for (path, diagnostic) in skipped {
+        print_skipped(path, diagnostic);
     }
 
-    if expected_classnames == existing_classnames && expected_ids == existing_ids {
+    if summary.up_to_date {
         println!(
-            "{} CSS is up-to-date. Skipping file modifications. \u{2022} {}",
+            "{} CSS is up-to-date. Skipping file modifications.",
+            "✓".bright_green()
+        );
+    } else {
+        println!(
+            "{} Wrote {}",
             "✓".bright_green(),
-            format_duration(start.elapsed()).bright_cyan()
+            scanner.output_path.display().to_string().bright_yellow()
         );
-        let file_map: HashMap<_, _> = paths
-            .par_iter()
-            .filter_map(|path| {
-                collect_css_entities(path, &cm).map(|(classes, ids)| (path.clone(), (classes, ids)))
-            })
-            .collect();
-        return (file_map, existing_classnames, existing_ids);
-    }
-
-    println!("{}", "Changes detected, performing full scan and modification...".yellow());
-    let file_map: HashMap<PathBuf, (HashSet<String>, HashSet<String>)> = paths
-        .par_iter()
-        .filter_map(|path| {
-            if let Some((classnames, ids, modified_code, original_code)) =
-                parse_and_modify_file(path, &cm)
-            {
-                if original_code != modified_code {
-                    write_file(path, &modified_code);
-                }
-                Some((path.clone(), (classnames, ids)))
-            } else {
-                None
-            }
-        })
-        .collect();
+    }
+}
+
+fn print_parse_failure(scanner: &Scanner, path: &Path, diagnostic: &Diagnostic) {
+    eprintln!(
+        "{} [{}] {} no longer parses: {}",
+        "✗".bright_red(),
+        diagnostic.code,
+        path.display().to_string().bright_blue(),
+        diagnostic.message
+    );
+    print_quarantine(scanner);
+}
 
-    let (global_classnames, global_ids) = calculate_global_classnames_and_ids(&file_map);
-    write_css(&global_classnames, &global_ids, &output_path);
+fn print_recovered(scanner: &Scanner, path: &Path) {
+    println!("{} {} parses again", "✓".bright_green(), path.display().to_string().bright_blue());
+    print_quarantine(scanner);
+}
 
-    let duration = start.elapsed();
+/// The files still failing to parse, which are retried on every change and every few seconds.
+fn print_quarantine(scanner: &Scanner) {
+    if scanner.parse_errors.is_empty() {
+        return;
+    }
+    let mut paths: Vec<_> = scanner.parse_errors.keys().map(|path| path.display().to_string()).collect();
+    paths.sort();
     println!(
-        "{} Initial scan found {} classes and {} IDs in {} files \u{2022} {}",
-        "✓".bright_green(),
-        global_classnames.len().to_string().bright_green(),
-        global_ids.len().to_string().bright_green(),
-        paths.len().to_string().bright_yellow(),
-        format_duration(duration).bright_cyan()
+        "  {} {} quarantined, retried on change: {}",
+        paths.len(),
+        if paths.len() == 1 { "file" } else { "files" },
+        paths.join(", ").bright_blue()
     );
-    (file_map, global_classnames, global_ids)
 }
 
-fn process_change(
-    path: &Path,
-    file_map: &mut HashMap<PathBuf, (HashSet<String>, HashSet<String>)>,
-    old_global_classnames: &HashSet<String>,
-    old_global_ids: &HashSet<String>,
-) -> Option<(HashSet<String>, HashSet<String>)> {
-    let start = Instant::now();
-    let cm: Arc<SourceMap> = Default::default();
+fn print_unwritable(path: &Path, diagnostic: &Diagnostic) {
+    eprintln!(
+        "{} [{}] {} {}; will retry on the next change",
+        "✗".bright_red(),
+        diagnostic.code,
+        path.display().to_string().bright_blue(),
+        diagnostic.message
+    );
+}
 
-    let (old_file_classnames, old_file_ids) = file_map.get(path).cloned().unwrap_or_default();
+fn print_written(path: &Path) {
+    println!("{} {} written", "✓".bright_green(), path.display().to_string().bright_blue());
+}
 
-    if !path.exists() {
-        file_map.remove(path);
-        let (new_global_classnames, new_global_ids) = calculate_global_classnames_and_ids(file_map);
-        if &new_global_classnames != old_global_classnames || &new_global_ids != old_global_ids {
-             write_css(&new_global_classnames, &new_global_ids, &PathBuf::from("./styles.css"));
-        }
-        return Some((new_global_classnames, new_global_ids));
+fn print_skipped(path: &Path, diagnostic: &Diagnostic) {
+    eprintln!(
+        "{} [{}] {} {}",
+        "⚠".yellow(),
+        diagnostic.code,
+        path.display().to_string().bright_blue(),
+        diagnostic.message
+    );
+}
+
+fn print_change(scanner: &Scanner, summary: &ChangeSummary) {
+    let path_str = summary.path.to_string_lossy().to_string();
+    let display_name = path_str.bright_blue();
+
+    let output_path_str = dx::paths::canonicalize(&scanner.output_path)
+        .unwrap_or(scanner.output_path.clone())
+        .to_string_lossy()
+        .to_string();
+    let output_display = output_path_str.bright_yellow();
+
+    println!(
+        "{} (+{}, -{}) -> {} (+{}, -{}) \u{2022} {}",
+        display_name,
+        summary.source_added.to_string().bright_green(),
+        summary.source_removed.to_string().bright_red(),
+        output_display,
+        summary.output_added.to_string().bright_green(),
+        summary.output_removed.to_string().bright_red(),
+        format_duration(summary.duration).bright_cyan()
+    );
+}
+
+fn print_rebuild(scanner: &Scanner, files: usize, summary: &ScanSummary) {
+    println!(
+        "{} {} files changed at once -> rebuilt {} ({} classes, {} ids) \u{2022} {}",
+        "⚡".bold(),
+        files.to_string().bright_yellow(),
+        scanner.output_path.display().to_string().bright_yellow(),
+        summary.classnames,
+        summary.ids,
+        format_duration(summary.duration).bright_cyan()
+    );
+}
+
+fn report_groups(scanner: &Scanner) {
+    let groups = scanner.group_usages();
+    if groups.is_empty() {
+        println!("{}", "No named groups found.".yellow());
+        return;
     }
 
-    let (new_file_classnames, new_file_ids, modified_code, original_code) =
-        if let Some(data) = parse_and_modify_file(path, &cm) {
-            data
+    println!(
+        "{} {} groups across {} files",
+        "📦".bold(),
+        groups.len().to_string().bright_green(),
+        scanner.source_paths().len().to_string().bright_yellow()
+    );
+
+    let mut unused = 0;
+    for (name, usage) in &groups {
+        let source = if usage.from_config { CONFIG_FILE } else { "inline" };
+        let uses = if usage.uses == 0 {
+            if usage.from_config {
+                unused += 1;
+            }
+            "unused".bright_red().to_string()
         } else {
-            return None;
+            format!("×{}", usage.uses).bright_green().to_string()
         };
+        println!(
+            "  {} = \"{}\" {} ({})",
+            name.bright_blue(),
+            usage.expansion.join(" "),
+            uses,
+            source.dimmed()
+        );
+    }
 
-    let code_was_modified = original_code != modified_code;
-    let data_was_modified =
-        new_file_classnames != old_file_classnames || new_file_ids != old_file_ids;
+    if unused > 0 {
+        println!(
+            "{} {} group(s) defined in {} are never referenced and can be removed.",
+            "⚠".yellow(),
+            unused.to_string().bright_red(),
+            CONFIG_FILE
+        );
+    }
+}
 
-    if !code_was_modified && !data_was_modified {
-        return None;
+fn write_meta(meta_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) {
+    if let Err(e) = dx::meta::write_meta(meta_path, files) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), meta_path.display(), e);
     }
+}
 
-    file_map.insert(
-        path.to_path_buf(),
-        (new_file_classnames.clone(), new_file_ids.clone()),
-    );
+fn write_stories(scanner: &Scanner, stories_path: &Path, files: &BTreeMap<PathBuf, FileMeta>, graph: &ImportGraph) {
+    let stories = dx::stories::stories(files, graph, &scanner.css_options);
+    if let Err(e) = dx::stories::write_stories(stories_path, &stories) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), stories_path.display(), e);
+    }
+}
 
-    if code_was_modified {
-        write_file(path, &modified_code);
+fn write_hydration_map(hydration_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) {
+    if let Err(e) = dx::hydration::write_hydration_map(hydration_path, files) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), hydration_path.display(), e);
     }
+}
 
-    let (new_global_classnames, new_global_ids) = calculate_global_classnames_and_ids(file_map);
-    
-    let globals_did_change =
-        &new_global_classnames != old_global_classnames || &new_global_ids != old_global_ids;
+fn write_test_ids(test_ids_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) {
+    if let Err(e) = dx::testid::write_test_id_manifest(test_ids_path, files) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), test_ids_path.display(), e);
+    }
+}
 
-    if !globals_did_change {
-        return Some((new_global_classnames, new_global_ids));
+fn write_class_types(scanner: &Scanner, types_path: &Path) {
+    if let Err(e) = dx::types::write_class_declarations(types_path, &scanner.classnames) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), types_path.display(), e);
     }
+}
 
-    let source_added = new_file_classnames.difference(&old_file_classnames).count();
-    let source_removed = old_file_classnames.difference(&new_file_classnames).count();
+/// Side outputs derived from per-file metadata.
+struct Manifests {
+    meta: Option<PathBuf>,
+    stories: Option<PathBuf>,
+    hydration: Option<PathBuf>,
+    types: Option<PathBuf>,
+    test_ids: Option<PathBuf>,
+}
 
-    let path_str = path.to_string_lossy().to_string();
-    let display_name = path_str.bright_blue();
+impl Manifests {
+    fn any(&self) -> bool {
+        self.meta.is_some()
+            || self.stories.is_some()
+            || self.hydration.is_some()
+            || self.types.is_some()
+            || self.test_ids.is_some()
+    }
 
-    let output_added = new_global_classnames
-        .difference(old_global_classnames)
-        .count()
-        + new_global_ids.difference(old_global_ids).count();
-    let output_removed = old_global_classnames
-        .difference(&new_global_classnames)
-        .count()
-        + old_global_ids.difference(&new_global_ids).count();
-
-    let output_path = PathBuf::from("./styles.css");
-    write_css(&new_global_classnames, &new_global_ids, &output_path);
-
-    let output_path_str = output_path
-        .canonicalize()
-        .unwrap_or(output_path.clone())
-        .to_string_lossy()
-        .to_string();
-    let output_display = output_path_str.bright_yellow();
+    fn in_root(&self, root: &Path) -> Manifests {
+        Manifests {
+            meta: self.meta.as_ref().map(|path| root.join(path)),
+            stories: self.stories.as_ref().map(|path| root.join(path)),
+            hydration: self.hydration.as_ref().map(|path| root.join(path)),
+            types: self.types.as_ref().map(|path| root.join(path)),
+            test_ids: self.test_ids.as_ref().map(|path| root.join(path)),
+        }
+    }
+
+    fn write(&self, scanner: &Scanner, files: &BTreeMap<PathBuf, FileMeta>, graph: Option<&ImportGraph>) {
+        if let Some(meta_path) = &self.meta {
+            write_meta(meta_path, files);
+        }
+        if let (Some(stories_path), Some(graph)) = (&self.stories, graph) {
+            write_stories(scanner, stories_path, files, graph);
+        }
+        if let Some(hydration_path) = &self.hydration {
+            write_hydration_map(hydration_path, files);
+        }
+        if let Some(types_path) = &self.types {
+            write_class_types(scanner, types_path);
+        }
+        if let Some(test_ids_path) = &self.test_ids {
+            write_test_ids(test_ids_path, files);
+        }
+    }
+}
+
+fn audit(scanner: &mut Scanner, css: Vec<PathBuf>) {
+    scanner.index();
+
+    let stylesheets = if css.is_empty() {
+        scanner.stylesheets()
+    } else {
+        css.into_iter()
+            .filter_map(|path| match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    let rules = parse_rules(&source);
+                    Some((path, rules))
+                }
+                Err(e) => {
+                    eprintln!("{} Failed to read {}: {}", "✗".bright_red(), path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    };
+
+    let mut total = 0;
+    for (path, rules) in &stylesheets {
+        for unused in unused_selectors(rules, &scanner.classnames, &scanner.ids) {
+            total += 1;
+            let missing: Vec<_> = unused
+                .missing_classes
+                .iter()
+                .map(|c| format!(".{}", c))
+                .chain(unused.missing_ids.iter().map(|id| format!("#{}", id)))
+                .collect();
+            println!(
+                "{}:{} [{}] {} ({})",
+                path.display().to_string().bright_blue(),
+                unused.line + 1,
+                codes::UNUSED_SELECTOR,
+                unused.selector.bright_yellow(),
+                format!("unused: {}", missing.join(", ")).dimmed()
+            );
+        }
+    }
 
-    let duration = start.elapsed();
+    if total == 0 {
+        println!(
+            "{} Every selector in {} stylesheet(s) is used.",
+            "✓".bright_green(),
+            stylesheets.len().to_string().bright_yellow()
+        );
+    } else {
+        println!(
+            "{} {} unused selector(s) across {} stylesheet(s)",
+            "⚠".yellow(),
+            total.to_string().bright_red(),
+            stylesheets.len().to_string().bright_yellow()
+        );
+    }
+}
+
+fn print_conflict(finding: &ConflictFinding, severity: Severity) {
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let path = finding.path.strip_prefix(&current_dir).unwrap_or(&finding.path);
+    let marker = match severity {
+        Severity::Error => "✗".bright_red(),
+        _ => "⚠".yellow(),
+    };
     println!(
-        "{} (+{}, -{}) -> {} (+{}, -{}) \u{2022} {}",
-        display_name,
-        source_added.to_string().bright_green(),
-        source_removed.to_string().bright_red(),
-        output_display,
-        output_added.to_string().bright_green(),
-        output_removed.to_string().bright_red(),
-        format_duration(duration).bright_cyan()
+        "{} [{}] {}:{}:{} conflicting {} utilities: {}",
+        marker,
+        codes::CONFLICTING_UTILITIES,
+        path.display().to_string().bright_blue(),
+        finding.line,
+        finding.column + 1,
+        finding.conflict.property,
+        finding.conflict.classes.join(" ").bright_yellow()
     );
+}
 
-    Some((new_global_classnames, new_global_ids))
+fn conflict_finding(finding: &ConflictFinding, severity: Severity) -> Finding {
+    Finding {
+        code: codes::CONFLICTING_UTILITIES,
+        level: if severity == Severity::Error { Level::Error } else { Level::Warning },
+        path: finding.path.clone(),
+        line: finding.line,
+        column: finding.column + 1,
+        message: format!(
+            "conflicting {} utilities: {}",
+            finding.conflict.property,
+            finding.conflict.classes.join(" ")
+        ),
+    }
 }
 
-fn main() {
-    let (mut file_map, mut global_classnames, mut global_ids) = initial_scan();
-    let (tx, rx) = mpsc::channel();
-    let mut watcher = RecommendedWatcher::new(
-        tx,
-        Config::default().with_poll_interval(Duration::from_millis(200)),
-    )
-    .expect("Failed to create file watcher");
-
-    let watch_path = env::current_dir().unwrap().join("src");
-    watcher
-        .watch(&watch_path, RecursiveMode::Recursive)
-        .expect("Failed to watch ./src directory");
+/// Replaces `code`'s entries in the baseline with `findings`.
+fn update_baseline(scanner: &Scanner, code: &str, findings: &[Finding]) -> Exit {
+    let mut baseline = Baseline::load(&scanner.root);
+    baseline.update(code, findings, &scanner.root);
+    if let Err(e) = baseline.save(&scanner.root) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), BASELINE_FILE, e);
+        return Exit::IoErrors;
+    }
+    println!(
+        "{} {} finding(s) recorded in {}",
+        "✓".bright_green(),
+        findings.len().to_string().bright_yellow(),
+        BASELINE_FILE
+    );
+    Exit::Ok
+}
+
+fn print_baselined(count: usize, noun: &str) {
+    if count > 0 {
+        println!("  {}", format!("{} {} accepted in {} not shown", count, noun, BASELINE_FILE).dimmed());
+    }
+}
+
+fn lint(scanner: &mut Scanner, max_warnings: Option<usize>, update: bool, report: &mut Vec<Finding>) -> Exit {
+    scanner.index();
+
+    // Running `dx lint` explicitly opts in even when the config leaves the lint off.
+    let severity = match scanner.config.lint.conflicts {
+        Severity::Off => Severity::Warn,
+        severity => severity,
+    };
+    let conflicts = scanner.conflicts();
+    let findings: Vec<Finding> = conflicts.iter().map(|conflict| conflict_finding(conflict, severity)).collect();
+    if update {
+        return update_baseline(scanner, codes::CONFLICTING_UTILITIES, &findings);
+    }
+
+    let new = Baseline::load(&scanner.root).is_new(&findings, &scanner.root);
+    let mut count = 0;
+    for ((conflict, finding), new) in conflicts.iter().zip(findings).zip(new) {
+        if new {
+            print_conflict(conflict, severity);
+            report.push(finding);
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        println!("{} No conflicting utilities found.", "✓".bright_green());
+        print_baselined(conflicts.len(), "conflict(s)");
+        return Exit::Ok;
+    }
 
     println!(
-        "{}",
-        "👀 Watching for file changes in ./src...".bold().bright_purple()
+        "{} {} conflict(s) found",
+        "⚠".yellow(),
+        count.to_string().bright_red()
     );
+    print_baselined(conflicts.len() - count, "conflict(s)");
+    if severity == Severity::Error {
+        return Exit::WouldChange;
+    }
+    warnings_exit(count, max_warnings)
+}
 
-    let mut debounce_map: HashMap<PathBuf, Instant> = HashMap::new();
-    let debounce_duration = Duration::from_millis(100);
-
-    loop {
-        while let Ok(Ok(event)) = rx.try_recv() {
-            if matches!(
-                event.kind,
-                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
-            ) {
-                for path in event.paths {
-                    if path.extension().and_then(|s| s.to_str()) == Some("tsx") {
-                        let canonical_path = path.canonicalize().unwrap_or(path);
-                        debounce_map.insert(canonical_path, Instant::now());
-                    }
-                }
-            }
+fn duplicates(
+    scanner: &mut Scanner,
+    threshold: f64,
+    min_classes: usize,
+    max_warnings: Option<usize>,
+    update: bool,
+    report: &mut Vec<Finding>,
+) -> Exit {
+    scanner.index();
+
+    let occurrences = scanner
+        .metadata()
+        .into_iter()
+        .flat_map(|(path, meta)| {
+            meta.elements.into_iter().map(move |el| ClassSetOccurrence {
+                path: path.clone(),
+                line: el.line,
+                column: el.column,
+                classnames: el.classnames,
+            })
+        })
+        .collect();
+    let clusters = cluster_class_sets(occurrences, min_classes, threshold);
+    // The message leaves out the cluster size, so a baselined cluster growing only reports the new element.
+    let findings: Vec<Finding> = clusters
+        .iter()
+        .flat_map(|cluster| {
+            cluster.occurrences.iter().map(|occurrence| Finding {
+                code: codes::DUPLICATE_CLASS_SET,
+                level: Level::Warning,
+                path: occurrence.path.clone(),
+                line: occurrence.line,
+                column: occurrence.column + 1,
+                message: format!("class set \"{}\" is repeated; consider a group", cluster.shared.join(" ")),
+            })
+        })
+        .collect();
+    if update {
+        return update_baseline(scanner, codes::DUPLICATE_CLASS_SET, &findings);
+    }
+    let mut new = Baseline::load(&scanner.root).is_new(&findings, &scanner.root).into_iter();
+    let mut findings = findings.into_iter();
+    let new_clusters: Vec<_> = clusters
+        .iter()
+        .filter_map(|cluster| {
+            let findings: Vec<Finding> = findings.by_ref().take(cluster.occurrences.len()).collect();
+            let new: Vec<bool> = new.by_ref().take(cluster.occurrences.len()).collect();
+            new.contains(&true).then_some((cluster, findings))
+        })
+        .collect();
+    let baselined = clusters.len() - new_clusters.len();
+
+    if new_clusters.is_empty() {
+        println!("{} No duplicated class sets found.", "✓".bright_green());
+        print_baselined(baselined, "cluster(s)");
+        return Exit::Ok;
+    }
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    for (cluster, findings) in &new_clusters {
+        println!(
+            "{} {} elements share \"{}\"",
+            "📦".bold(),
+            cluster.occurrences.len().to_string().bright_green(),
+            cluster.shared.join(" ").bright_yellow()
+        );
+        for occurrence in &cluster.occurrences {
+            let path = occurrence.path.strip_prefix(&current_dir).unwrap_or(&occurrence.path);
+            println!(
+                "  {}:{}:{} {}",
+                path.display().to_string().bright_blue(),
+                occurrence.line,
+                occurrence.column + 1,
+                occurrence.classnames.join(" ").dimmed()
+            );
         }
+        report.extend(findings.iter().cloned());
+        println!(
+            "  {} add a group to {}: name = \"{}\"",
+            "→".bright_cyan(),
+            CONFIG_FILE,
+            cluster.shared.join(" ")
+        );
+    }
 
-        let mut paths_to_process = Vec::new();
-        debounce_map.retain(|_path, last_event_time| {
-            if last_event_time.elapsed() > debounce_duration {
-                paths_to_process.push(_path.clone());
-                false
-            } else {
-                true
-            }
-        });
+    println!(
+        "{} {} cluster(s) could be extracted into groups",
+        "⚠".yellow(),
+        new_clusters.len().to_string().bright_red()
+    );
+    print_baselined(baselined, "cluster(s)");
+    warnings_exit(new_clusters.len(), max_warnings)
+}
 
-        for path in paths_to_process {
-            if let Some((new_classnames, new_ids)) =
-                process_change(&path, &mut file_map, &global_classnames, &global_ids)
-            {
-                global_classnames = new_classnames;
-                global_ids = new_ids;
-            }
+fn build(scanner: &mut Scanner, since: Option<String>) {
+    let changed = match since.as_deref().map(dx::git::changed_since).transpose() {
+        Ok(changed) => changed,
+        Err(e) => {
+            eprintln!("{} Failed to ask git for changed files: {}", "✗".bright_red(), e);
+            std::process::exit(1);
         }
+    };
+
+    if let (Some(reference), Some(changed)) = (&since, &changed) {
+        println!(
+            "{} {} file(s) changed since {}",
+            "🚀".bold(),
+            changed.len().to_string().bright_yellow(),
+            reference.bright_blue()
+        );
+    } else {
+        println!("{}", "🚀 dx-styles building...".bold().bright_purple());
+    }
+
+    let summary = scan_with_progress(scanner, changed.as_deref());
+    print_scan_summary(scanner, &summary);
+}
+
+/// [`Exit::IoErrors`] if a build could not write a file, [`Exit::ParseErrors`] if a source did not parse.
+fn build_exit(scanner: &Scanner) -> Exit {
+    if !scanner.write_errors.is_empty() {
+        Exit::IoErrors
+    } else if !scanner.parse_errors.is_empty() {
+        Exit::ParseErrors
+    } else {
+        Exit::Ok
+    }
+}
+
+/// Every file a build of `scanner` writes besides its sources.
+fn build_outputs(scanner: &Scanner, manifests: &Manifests, routes: bool) -> Vec<PathBuf> {
+    let mut outputs = scanner.stylesheet_outputs();
+    outputs.extend(scanner.cascade.outputs());
+    if scanner.css_options.obfuscate {
+        outputs.push(scanner.root.join(dx::obfuscate::CLASS_MAP_FILE));
+    }
+    if scanner.config.css.hashed_filenames {
+        let manifest_path = scanner.root.join(dx::assets::ASSET_MANIFEST_FILE);
+        outputs.extend(read_asset_manifest(&manifest_path).into_values().map(|name| scanner.root.join(name)));
+        outputs.push(manifest_path);
+    }
+    let manifests = manifests.in_root(&scanner.root);
+    outputs.extend(
+        [manifests.meta, manifests.stories, manifests.hydration, manifests.types, manifests.test_ids]
+            .into_iter()
+            .flatten(),
+    );
+    let compressed: Vec<PathBuf> = outputs
+        .iter()
+        .filter(|path| path.extension().is_some_and(|extension| extension == "css"))
+        .flat_map(|path| scanner.compression.iter().map(|compression| compression.path(path)))
+        .collect();
+    outputs.extend(compressed);
+    if routes && !scanner.routes().is_empty() {
+        let css_dir = scanner.root.join("styles").join("routes");
+        outputs.extend(scanner.routes().iter().map(|route| css_dir.join(route.css_file_name())));
+        outputs.push(scanner.root.join(dx::routes::MANIFEST_FILE));
+    }
+    outputs
+}
+
+fn read_outputs(outputs: &[PathBuf]) -> Vec<Option<Vec<u8>>> {
+    outputs.iter().map(|path| std::fs::read(path).ok()).collect()
+}
+
+/// [`Exit::WouldChange`] if an output differs from its `first` build or holds an absolute path
+/// of this machine (the project root or the current directory), which no other checkout shares.
+fn verify_outputs(scanner: &Scanner, outputs: &[PathBuf], first: &[Option<Vec<u8>>]) -> Exit {
+    let mut local_paths = vec![scanner.root.clone(), std::env::current_dir().unwrap_or_default()];
+    local_paths.extend(local_paths.iter().filter_map(|path| dx::paths::canonicalize(path).ok()).collect::<Vec<_>>());
+    let local_paths: Vec<String> = local_paths
+        .iter()
+        .filter(|path| path.is_absolute() && path.parent().is_some())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    let mut problems = 0;
+    for (path, (first, second)) in outputs.iter().zip(first.iter().zip(read_outputs(outputs))) {
+        if *first != second {
+            problems += 1;
+            eprintln!("{} {} differs between two builds", "✗".bright_red(), path.display());
+        }
+        let content = second.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+        if let Some(local) = local_paths.iter().find(|local| content.contains(local.as_str())) {
+            problems += 1;
+            eprintln!("{} {} contains the machine-specific path {}", "✗".bright_red(), path.display(), local);
+        }
+    }
+    if problems > 0 {
+        return Exit::WouldChange;
+    }
+    println!(
+        "{} {} output(s) are byte-identical across two builds",
+        "✓".bright_green(),
+        outputs.iter().filter(|path| path.exists()).count().to_string().bright_yellow()
+    );
+    Exit::Ok
+}
 
-        thread::sleep(Duration::from_millis(50));
+fn write_route_styles(scanner: &Scanner) {
+    let css_dir = scanner.root.join("styles").join("routes");
+    match scanner.write_route_styles(&css_dir) {
+        Ok(routes) if routes.is_empty() => {
+            println!("{} No src/app/**/page files found; skipped per-route CSS", "⚠".yellow())
+        }
+        Ok(routes) => {
+            for route in &routes {
+                println!(
+                    "  {} {} ({} file(s))",
+                    route.path.bright_blue(),
+                    route.css_file_name().bright_green(),
+                    route.files.len()
+                );
+            }
+            println!(
+                "{} Wrote {} route stylesheet(s) and {}",
+                "✓".bright_green(),
+                routes.len().to_string().bright_yellow(),
+                dx::routes::MANIFEST_FILE
+            );
+        }
+        Err(e) => {
+            eprintln!("{} Failed to write per-route CSS: {}", "✗".bright_red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn write_class_map(scanner: &Scanner) {
+    let map = dx::obfuscate::class_map(&scanner.classnames);
+    for token in dx::obfuscate::collisions(&map) {
+        eprintln!(
+            "{} Several classes share the production name {}; rename one of them",
+            "⚠".yellow(),
+            token.bright_red()
+        );
+    }
+    let map_path = scanner.root.join(dx::obfuscate::CLASS_MAP_FILE);
+    match dx::obfuscate::write_class_map(&map_path, &map) {
+        Ok(()) => println!(
+            "{} Renamed {} class(es); wrote {}",
+            "✓".bright_green(),
+            map.len().to_string().bright_yellow(),
+            map_path.display()
+        ),
+        Err(e) => eprintln!("{} Failed to write {}: {}", "✗".bright_red(), map_path.display(), e),
+    }
+}
+
+fn print_dead_code(dead: &DeadCode) {
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    println!(
+        "{} {} file(s) are not imported from any entry point; {} class(es) are used only there",
+        "⚠".yellow(),
+        dead.files.len().to_string().bright_yellow(),
+        dead.classnames.len().to_string().bright_red()
+    );
+    for path in &dead.files {
+        let path = path.strip_prefix(&current_dir).unwrap_or(path);
+        println!("  {}", path.display().to_string().bright_blue());
+    }
+    if !dead.classnames.is_empty() {
+        println!("  classes: {}", dead.classnames.join(" ").bright_red());
+    }
+}
+
+fn prune(scanner: &mut Scanner, delete: bool) {
+    scan_with_progress(scanner, None);
+
+    let Some(dead) = scanner.prune() else {
+        eprintln!(
+            "{} No entry points found; set `entries` in {} (e.g. [\"src/main.tsx\"])",
+            "✗".bright_red(),
+            CONFIG_FILE
+        );
+        std::process::exit(1);
+    };
+    if dead.files.is_empty() {
+        println!("{} Every file is reachable from an entry point", "✓".bright_green());
+    } else {
+        print_dead_code(&dead);
+        println!(
+            "{} Dropped {} class(es) from {}; add `entries` to {} to keep them out",
+            "✓".bright_green(),
+            dead.classnames.len().to_string().bright_yellow(),
+            scanner.output_path.display(),
+            CONFIG_FILE
+        );
+    }
+
+    if delete {
+        let css_dir = scanner.root.join("styles").join("routes");
+        for path in dx::routes::stale_route_styles(&css_dir, &scanner.routes()) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => println!("{} Deleted {}", "✓".bright_green(), path.display()),
+                Err(e) => eprintln!("{} Failed to delete {}: {}", "✗".bright_red(), path.display(), e),
+            }
+        }
+    }
+}
+
+/// Reports sources whose id application or group expansion is not stable across passes.
+fn fmt_check(scanner: &Scanner, report: &mut Vec<Finding>) -> Exit {
+    let unstable = scanner.unstable_rewrites();
+    if unstable.is_empty() {
+        println!("{} Rewrites are idempotent.", "✓".bright_green());
+        return Exit::Ok;
+    }
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    for rewrite in &unstable {
+        let path = rewrite.path.strip_prefix(&current_dir).unwrap_or(&rewrite.path);
+        eprintln!(
+            "{} {}:{} changes again on a second rewrite",
+            "✗".bright_red(),
+            path.display().to_string().bright_blue(),
+            rewrite.line
+        );
+        eprintln!("  {} {}", "first: ".dimmed(), rewrite.first.trim());
+        eprintln!("  {} {}", "second:".dimmed(), rewrite.second.trim());
+        report.push(Finding {
+            code: codes::UNSTABLE_REWRITE,
+            level: Level::Error,
+            path: rewrite.path.clone(),
+            line: rewrite.line,
+            column: 1,
+            message: "changes again on a second rewrite".to_string(),
+        });
+    }
+    eprintln!("{} {} file(s) with unstable rewrites", "✗".bright_red(), unstable.len());
+    Exit::WouldChange
+}
+
+fn check(scanner: &mut Scanner, staged: bool, report: &mut Vec<Finding>) -> Exit {
+    scanner.index();
+
+    let extension = scanner.options.syntax.extension();
+    let source_paths: HashSet<PathBuf> = scanner.source_paths().into_iter().collect();
+    let (sources, existing_css) = if staged {
+        let staged_files = match dx::git::staged_files() {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("{} Failed to list staged files: {}", "✗".bright_red(), e);
+                std::process::exit(Exit::IoErrors as i32);
+            }
+        };
+        let sources: Vec<_> = staged_files
+            .into_iter()
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(extension))
+            .map(|path| dx::paths::normalize(&path))
+            .filter(|path| source_paths.contains(path))
+            .filter_map(|path| {
+                let source = dx::git::staged_content(&path).ok()??;
+                Some((path, source))
+            })
+            .collect();
+        let existing_css = dx::git::staged_content(&scanner.output_path).ok().flatten().unwrap_or_default();
+        (sources, existing_css)
+    } else {
+        let sources = source_paths
+            .into_iter()
+            .filter_map(|path| {
+                let source = std::fs::read_to_string(&path).ok()?;
+                Some((path, source))
+            })
+            .collect();
+        (sources, std::fs::read_to_string(&scanner.output_path).unwrap_or_default())
+    };
+
+    let checked = sources.len();
+    let outcome = scanner.check(sources, &existing_css);
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let display = |path: &Path| path.strip_prefix(&current_dir).unwrap_or(path).display().to_string();
+    // Offsets are converted against the same content that was checked.
+    let finding = |path: &Path, diagnostic: &Diagnostic, level: Level| {
+        let source = if staged {
+            dx::git::staged_content(path).ok().flatten()
+        } else {
+            std::fs::read_to_string(path).ok()
+        };
+        let (line, column) = source.map_or((0, 0), |source| line_column(&source, diagnostic.start as usize));
+        Finding {
+            code: diagnostic.code,
+            level,
+            path: path.to_path_buf(),
+            line,
+            column,
+            message: diagnostic.message.clone(),
+        }
+    };
+    for (path, diagnostic) in &outcome.id_conflicts {
+        let conflict = finding(path, diagnostic, Level::Warning);
+        eprintln!(
+            "{} [{}] {}:{}:{} {}",
+            "⚠".yellow(),
+            diagnostic.code,
+            display(path).bright_blue(),
+            conflict.line,
+            conflict.column,
+            diagnostic.message
+        );
+        report.push(conflict);
+    }
+    if outcome.is_clean() {
+        println!(
+            "{} {} file(s) checked, styles.css and sources are up-to-date.",
+            "✓".bright_green(),
+            checked.to_string().bright_yellow()
+        );
+        return Exit::Ok;
+    }
+
+    for (path, diagnostic) in &outcome.parse_errors {
+        eprintln!(
+            "{} [{}] {}: {}",
+            "✗".bright_red(),
+            diagnostic.code,
+            display(path).bright_blue(),
+            diagnostic.message
+        );
+        report.push(finding(path, diagnostic, Level::Error));
+    }
+    for path in &outcome.rewrites {
+        eprintln!("{} {} would be rewritten", "✗".bright_red(), display(path).bright_blue());
+        report.push(Finding {
+            code: codes::STALE_SOURCE,
+            level: Level::Error,
+            path: path.clone(),
+            line: 0,
+            column: 0,
+            message: "would be rewritten by `dx build`".to_string(),
+        });
+    }
+    if !outcome.css_added.is_empty() || !outcome.css_removed.is_empty() {
+        eprintln!(
+            "{} {} is stale (+{}, -{})",
+            "✗".bright_red(),
+            display(&scanner.output_path).bright_yellow(),
+            outcome.css_added.len().to_string().bright_green(),
+            outcome.css_removed.len().to_string().bright_red()
+        );
+        report.push(Finding {
+            code: codes::STALE_STYLESHEET,
+            level: Level::Error,
+            path: scanner.output_path.clone(),
+            line: 0,
+            column: 0,
+            message: format!(
+                "is stale: {} selector(s) missing, {} extra",
+                outcome.css_added.len(),
+                outcome.css_removed.len()
+            ),
+        });
+        for selector in &outcome.css_added {
+            eprintln!("  {} {}", "+".bright_green(), selector);
+        }
+        for selector in &outcome.css_removed {
+            eprintln!("  {} {}", "-".bright_red(), selector);
+        }
+    }
+    eprintln!("Run `dx build` and stage the result.");
+    if outcome.parse_errors.is_empty() { Exit::WouldChange } else { Exit::ParseErrors }
+}
+
+fn install_hook(force: bool) {
+    let hooks_dir = match dx::git::hooks_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{} Not inside a git repository: {}", "✗".bright_red(), e);
+            std::process::exit(1);
+        }
+    };
+    let hook_path = hooks_dir.join("pre-commit");
+
+    if let Ok(existing) = std::fs::read_to_string(&hook_path) {
+        if existing == PRE_COMMIT_HOOK {
+            println!("{} {} is already installed.", "✓".bright_green(), hook_path.display());
+            return;
+        }
+        if !force {
+            eprintln!(
+                "{} {} already exists. Re-run with --force to replace it, or add `dx check --staged` to it.",
+                "✗".bright_red(),
+                hook_path.display()
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let result = std::fs::create_dir_all(&hooks_dir).and_then(|_| std::fs::write(&hook_path, PRE_COMMIT_HOOK));
+    #[cfg(unix)]
+    let result = result.and_then(|_| {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))
+    });
+    match result {
+        Ok(()) => println!("{} Installed {}", "✓".bright_green(), hook_path.display().to_string().bright_yellow()),
+        Err(e) => {
+            eprintln!("{} Failed to write {}: {}", "✗".bright_red(), hook_path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn migrate(from: Option<PathBuf>, force: bool) {
+    let Some(source) = from.or_else(|| dx::migrate::find_config(Path::new("."))) else {
+        eprintln!(
+            "{} No config to migrate; expected one of {}",
+            "✗".bright_red(),
+            dx::migrate::MIGRATABLE_CONFIGS.join(", ")
+        );
+        std::process::exit(1);
+    };
+    let migration = match dx::migrate::read_config(&source) {
+        Ok(migration) => migration,
+        Err(e) => {
+            eprintln!("{} Failed to read {}: {}", "✗".bright_red(), source.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let config_path = PathBuf::from(CONFIG_FILE);
+    if config_path.exists() && !force {
+        eprintln!(
+            "{} {} already exists. Re-run with --force to replace it.",
+            "✗".bright_red(),
+            CONFIG_FILE
+        );
+        std::process::exit(1);
+    }
+
+    let toml = migration.config_toml(&source);
+    let config = match Config::parse(&toml) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} Migrated config is invalid: {}", "✗".bright_red(), e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = std::fs::write(&config_path, &toml) {
+        eprintln!("{} Failed to write {}: {}", "✗".bright_red(), CONFIG_FILE, e);
+        std::process::exit(1);
+    }
+    println!(
+        "{} Wrote {} from {}",
+        "✓".bright_green(),
+        CONFIG_FILE.bright_yellow(),
+        source.display()
+    );
+
+    for setting in &migration.unsupported {
+        println!("{} Not migrated: {}", "⚠".yellow(), setting);
+    }
+    for glob in migration.outside_src() {
+        println!("{} Content glob {} is outside src/ and will not be scanned", "⚠".yellow(), glob.bright_yellow());
+    }
+
+    let mut scanner = Scanner::new(config);
+    scanner.index();
+    let uncovered = dx::migrate::uncovered_classes(&scanner);
+    if uncovered.is_empty() {
+        println!(
+            "{} All {} classes found in src/ get CSS from dx",
+            "✓".bright_green(),
+            scanner.classnames.len()
+        );
+        return;
+    }
+    println!(
+        "{} {} of {} classes found in src/ get no CSS from dx:",
+        "⚠".yellow(),
+        uncovered.len().to_string().bright_red(),
+        scanner.classnames.len()
+    );
+    for class in &uncovered {
+        println!("  {}", class);
+    }
+}
+
+fn explain(code: &str) {
+    match codes::explain(code) {
+        Some(entry) => {
+            println!("{} {}", entry.code.bold().bright_red(), entry.title.bold());
+            println!();
+            println!("{}", entry.explanation);
+        }
+        None => {
+            eprintln!("{} Unknown code {}. Known codes:", "✗".bright_red(), code);
+            for entry in codes::CODES {
+                eprintln!("  {} {}", entry.code.bright_blue(), entry.title);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn why(scanner: &mut Scanner, class: &str, regex: bool) {
+    scanner.index();
+
+    let usages = if regex {
+        match Regex::new(class) {
+            Ok(re) => scanner.class_usages(|c| re.is_match(c)),
+            Err(e) => {
+                eprintln!("{} Invalid pattern {}: {}", "✗".bright_red(), class, e);
+                return;
+            }
+        }
+    } else {
+        scanner.class_usages(|c| c == class)
+    };
+
+    if usages.is_empty() {
+        println!("{} is not used in any scanned file.", class.bright_blue());
+        return;
+    }
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let mut files = 0;
+    let mut last_path = None;
+    for usage in &usages {
+        if last_path != Some(&usage.path) {
+            files += 1;
+            last_path = Some(&usage.path);
+        }
+        let path = usage.path.strip_prefix(&current_dir).unwrap_or(&usage.path);
+        println!(
+            "{}:{}:{} {}",
+            path.display().to_string().bright_blue(),
+            usage.line,
+            usage.column + 1,
+            usage.classname.bright_green()
+        );
+    }
+
+    println!(
+        "{} {} use(s) across {} file(s)",
+        "🔎".bold(),
+        usages.len().to_string().bright_green(),
+        files.to_string().bright_yellow()
+    );
+}
+
+/// Warns when a previous run stopped in the middle of writing sources and stylesheets.
+fn report_interrupted(scanner: &Scanner) {
+    let Some(batch) = IntentLog::new(&scanner.root).interrupted() else {
+        return;
+    };
+    eprintln!(
+        "{} A previous run stopped while writing: {} source(s) written, {} not; {} may be stale. Run `dx repair`.",
+        "⚠".yellow(),
+        batch.applied.len().to_string().bright_yellow(),
+        batch.pending.len().to_string().bright_red(),
+        batch.stylesheets.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")
+    );
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    for path in &batch.pending {
+        let path = path.strip_prefix(&current_dir).unwrap_or(path);
+        eprintln!("  {} {}", path.display().to_string().bright_blue(), "not written".dimmed());
+    }
+}
+
+fn repair(scanner: &mut Scanner) {
+    let log = IntentLog::new(&scanner.root);
+    let Some(batch) = log.interrupted() else {
+        println!("{} No interrupted writes to repair", "✓".bright_green());
+        return;
+    };
+    // Regenerating the stylesheets from scratch makes the scan rewrite every pending source
+    // instead of finding the CSS up to date.
+    for stylesheet in &batch.stylesheets {
+        let _ = std::fs::remove_file(stylesheet);
+    }
+    scanner.cache = Cache::new(scanner.cache.fingerprint());
+    let summary = scan_with_progress(scanner, None);
+    print_scan_summary(scanner, &summary);
+    if !scanner.write_errors.is_empty() {
+        eprintln!("{} Some files still could not be written; run `dx repair` again", "✗".bright_red());
+        std::process::exit(1);
+    }
+    if let Err(e) = log.commit() {
+        eprintln!("{} Failed to clear {}: {}", "✗".bright_red(), INTENT_FILE, e);
+    }
+    println!(
+        "{} Repaired {} pending source(s) and regenerated {} stylesheet(s)",
+        "✓".bright_green(),
+        batch.pending.len().to_string().bright_yellow(),
+        batch.stylesheets.len()
+    );
+}
+
+/// Saves the files about to be written to the undo journal, so `dx undo` can restore them.
+fn journal(scanner: &Scanner, files: &[(PathBuf, String)]) {
+    let paths: Vec<&Path> = files.iter().map(|(path, _)| path.as_path()).collect();
+    if let Err(e) = Journal::new(&scanner.root).record(&paths) {
+        eprintln!("{} Failed to save the undo journal: {}", "⚠".yellow(), e);
+    }
+}
+
+fn undo(scanner: &Scanner, last: usize, file: Option<PathBuf>) {
+    let journal = Journal::new(&scanner.root);
+    let entries = match file {
+        Some(file) => {
+            let path = dx::paths::canonicalize(&file).unwrap_or(file);
+            journal.last_for(&path).into_iter().collect()
+        }
+        None => journal.last_batches(last),
+    };
+    if entries.is_empty() {
+        println!("{} Nothing to undo in {}", "✓".bright_green(), UNDO_DIR);
+        return;
+    }
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let mut restored = 0;
+    for entry in &entries {
+        let path = entry.path.strip_prefix(&current_dir).unwrap_or(&entry.path);
+        match journal.restore(entry) {
+            Ok(()) => {
+                restored += 1;
+                let action = if entry.content.is_some() { "restored" } else { "removed" };
+                println!("  {} {}", path.display().to_string().bright_blue(), action.dimmed());
+            }
+            Err(e) => eprintln!("{} Failed to restore {}: {}", "✗".bright_red(), path.display(), e),
+        }
+    }
+    println!(
+        "{} Undid {} file rewrite(s); a running `dx watch` rewrites them again unless rewriting is off",
+        "✓".bright_green(),
+        restored.to_string().bright_yellow()
+    );
+}
+
+fn codemod(scanner: &mut Scanner, map: &Path, dry_run: bool) {
+    let codemod = match dx::codemod::Codemod::read(map) {
+        Ok(codemod) => codemod,
+        Err(e) => {
+            eprintln!("{} Failed to read {}: {}", "✗".bright_red(), map.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let mut rewrites = Vec::new();
+    let mut total = dx::codemod::CodemodStats::default();
+    for path in scanner.source_paths() {
+        let Ok(source) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let result = match codemod.apply(&path, &source, &scanner.options_for(&path)) {
+            Ok(Some(result)) => result,
+            Ok(None) => continue,
+            Err(diagnostic) => {
+                print_skipped(&path, &diagnostic);
+                continue;
+            }
+        };
+        let stats = result.stats;
+        let mut parts = Vec::new();
+        for (count, label) in [(stats.renamed, "renamed"), (stats.split, "split"), (stats.removed, "removed")] {
+            if count > 0 {
+                parts.push(format!("{} {}", count, label));
+            }
+        }
+        println!(
+            "  {} {}",
+            path.strip_prefix(&current_dir).unwrap_or(&path).display().to_string().bright_blue(),
+            parts.join(", ").dimmed()
+        );
+        total.renamed += stats.renamed;
+        total.split += stats.split;
+        total.removed += stats.removed;
+        rewrites.push((path, result.code));
+    }
+
+    if rewrites.is_empty() {
+        println!("{} No source uses a class from {}", "✓".bright_green(), map.display());
+        return;
+    }
+    let summary = format!(
+        "{} renamed, {} split, {} removed across {} file(s)",
+        total.renamed,
+        total.split,
+        total.removed,
+        rewrites.len()
+    );
+    if dry_run {
+        println!("{} Would change {}; nothing written (--dry-run)", "⚠".yellow(), summary);
+        return;
+    }
+    journal(scanner, &rewrites);
+    if let Err(e) = dx::io::write_files(&rewrites) {
+        eprintln!("{} Failed to write the codemod: {}", "✗".bright_red(), e);
+        std::process::exit(1);
+    }
+    println!("{} Changed {}", "✓".bright_green(), summary);
+
+    let changed: Vec<PathBuf> = rewrites.into_iter().map(|(path, _)| path).collect();
+    let summary = scan_with_progress(scanner, Some(&changed));
+    print_scan_summary(scanner, &summary);
+}
+
+fn extract_component(scanner: &mut Scanner, file: &Path, element: &ElementSelector, name: &str) {
+    let source = match std::fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{} Failed to read {}: {}", "✗".bright_red(), file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let extraction = match dx::component::extract_component(file, &source, &scanner.options_for(file), element, name) {
+        Ok(extraction) => extraction,
+        Err(e) => {
+            eprintln!("{} Cannot extract a component from {}: {}", "✗".bright_red(), file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    if extraction.path.exists() {
+        eprintln!("{} {} already exists", "✗".bright_red(), extraction.path.display());
+        std::process::exit(1);
+    }
+
+    let files = [(extraction.path.clone(), extraction.component), (file.to_path_buf(), extraction.source)];
+    journal(scanner, &files);
+    if let Err(e) = dx::io::write_files(&files) {
+        eprintln!("{} Failed to write the component: {}", "✗".bright_red(), e);
+        std::process::exit(1);
+    }
+    println!(
+        "{} Moved the element into {}{}",
+        "✓".bright_green(),
+        extraction.path.display().to_string().bright_blue(),
+        if extraction.props.is_empty() {
+            String::new()
+        } else {
+            format!(" with props {}", extraction.props.join(", ").bright_yellow())
+        }
+    );
+
+    // Rescan both files so generated ids and styles.css follow the moved classes.
+    let changed = [file.to_path_buf(), extraction.path];
+    let summary = scan_with_progress(scanner, Some(&changed));
+    print_scan_summary(scanner, &summary);
+}
+
+/// Set by Ctrl-C so the watch loop returns and the snapshot for the next warm start is saved.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// The stop flag for watch loops, set on the first Ctrl-C; a second one exits immediately.
+fn stop_on_ctrl_c() -> &'static AtomicBool {
+    let handler = ctrlc::set_handler(|| {
+        if SHUTDOWN.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    });
+    if let Err(e) = handler {
+        eprintln!("{} Failed to handle Ctrl-C; no snapshot will be saved: {}", "⚠".yellow(), e);
+    }
+    &SHUTDOWN
+}
+
+fn save_snapshot(scanner: &Scanner) {
+    if let Err(e) = scanner.save_snapshot() {
+        eprintln!("{} Failed to save the warm-start snapshot: {}", "✗".bright_red(), e);
+    }
+    scanner.save_symbols();
+}
+
+fn watch(
+    scanner: &mut Scanner,
+    serve: Option<String>,
+    ui: bool,
+    manifests: Manifests,
+) {
+    initial_scan(scanner);
+
+    let mut meta = manifests.any().then(|| scanner.metadata());
+    let mut graph = manifests.stories.is_some().then(|| scanner.import_graph());
+    if let Some(files) = &meta {
+        manifests.write(scanner, files, graph.as_ref());
+    }
+
+    let dev_server = serve.and_then(|addr| {
+        let addr = normalize_addr(&addr);
+        match DevServer::start(addr.as_str(), scanner.output_path.clone()) {
+            Ok(server) => {
+                println!(
+                    "{} Serving styles.css on {} (add <script src=\"http://{}/client.js\"></script>)",
+                    "🌐".bold(),
+                    format!("http://{}", addr).bright_yellow(),
+                    addr
+                );
+                Some(server)
+            }
+            Err(e) => {
+                eprintln!("{} Failed to serve on {}: {}", "✗".bright_red(), addr, e);
+                None
+            }
+        }
+    });
+
+    let notifications = scanner.config.features.notifications;
+    if notifications {
+        dx::notifications::notify_on_panic();
+    }
+
+    let mut on_event = |scanner: &Scanner, event: WatchEvent| match event {
+        WatchEvent::Changed(summary) => {
+            if let Some(files) = &mut meta {
+                files.retain(|path, _| scanner.file_map.contains_key(path));
+                if let Some(file_meta) = scanner.file_metadata(&summary.path) {
+                    files.insert(summary.path.clone(), file_meta);
+                }
+                if let Some(graph) = &mut graph {
+                    scanner.update_import_graph(graph, &summary.path);
+                }
+                manifests.write(scanner, files, graph.as_ref());
+            }
+            if let Some(server) = &dev_server {
+                server.notify_css_updated();
+            }
+        }
+        WatchEvent::Rebuilt { .. } => {
+            if let Some(files) = &mut meta {
+                *files = scanner.metadata();
+                if let Some(graph) = &mut graph {
+                    *graph = scanner.import_graph();
+                }
+                manifests.write(scanner, files, graph.as_ref());
+            }
+            if let Some(server) = &dev_server {
+                server.notify_css_updated();
+            }
+        }
+        WatchEvent::Failed { path, diagnostic } => {
+            if notifications {
+                dx::notifications::notify_error(
+                    "dx: file stopped parsing",
+                    &format!("{}: {}", path.display(), diagnostic.message),
+                );
+            }
+        }
+        WatchEvent::Recovered { .. }
+        | WatchEvent::Unwritable { .. }
+        | WatchEvent::Written { .. }
+        | WatchEvent::Skipped { .. } => {}
+    };
+
+    if ui {
+        if let Err(e) = dx::ui::run(scanner, on_event) {
+            eprintln!("{} Dashboard failed: {}", "✗".bright_red(), e);
+        }
+        save_snapshot(scanner);
+        return;
+    }
+
+    println!(
+        "{}",
+        "👀 Watching for file changes in ./src...".bold().bright_purple()
+    );
+
+    // Reachability needs the whole import graph, so re-check it at most once a minute.
+    let mut dead_code = scanner.dead_code().filter(|dead| !dead.files.is_empty());
+    if let Some(dead) = &dead_code {
+        print_dead_code(dead);
+        println!("  run {} to drop them from the output", "dx prune".bright_blue());
+    }
+    let mut last_dead_code_check = Instant::now();
+
+    dx::watch::watch_events(scanner, stop_on_ctrl_c(), |scanner, event| {
+        match &event {
+            WatchEvent::Changed(summary) => {
+                print_change(scanner, summary);
+                if last_dead_code_check.elapsed() >= Duration::from_secs(60) {
+                    last_dead_code_check = Instant::now();
+                    let current = scanner.dead_code().filter(|dead| !dead.files.is_empty());
+                    if let Some(dead) = current.as_ref().filter(|dead| Some(*dead) != dead_code.as_ref()) {
+                        print_dead_code(dead);
+                    }
+                    dead_code = current;
+                }
+                let severity = scanner.config.lint.conflicts;
+                if severity != Severity::Off {
+                    for finding in scanner.file_conflicts(&summary.path) {
+                        print_conflict(&finding, severity);
+                    }
+                }
+            }
+            WatchEvent::Failed { path, diagnostic } => print_parse_failure(scanner, path, diagnostic),
+            WatchEvent::Recovered { path } => print_recovered(scanner, path),
+            WatchEvent::Unwritable { path, diagnostic } => print_unwritable(path, diagnostic),
+            WatchEvent::Written { path } => print_written(path),
+            WatchEvent::Skipped { path, diagnostic } => print_skipped(path, diagnostic),
+            WatchEvent::Rebuilt { files, summary } => print_rebuild(scanner, *files, summary),
+        }
+        on_event(scanner, event);
+    });
+    save_snapshot(scanner);
+}
+
+fn daemon(scanner: Scanner, socket: Option<PathBuf>) {
+    match socket {
+        #[cfg(unix)]
+        Some(path) => {
+            eprintln!(
+                "{} {}",
+                "🔌 dx daemon listening on".bold().bright_purple(),
+                path.display().to_string().bright_yellow()
+            );
+            if let Err(e) = dx::daemon::serve_socket(scanner, &path) {
+                eprintln!("{} Failed to serve {}: {}", "✗".bright_red(), path.display(), e);
+            }
+        }
+        #[cfg(not(unix))]
+        Some(_) => eprintln!("{} Unix sockets are not supported on this platform", "✗".bright_red()),
+        None => dx::daemon::serve_stdio(scanner),
+    }
+}
+
+/// One scanner per selected workspace package, or a single scanner for the current directory.
+fn load_scanners(filters: &[String]) -> Vec<Scanner> {
+    let current_dir = std::env::current_dir().expect("Failed to get current directory");
+    let packages = workspace::packages(&current_dir);
+
+    if packages.is_empty() {
+        if !filters.is_empty() {
+            eprintln!(
+                "{} --filter needs a workspace with packages that have a {}",
+                "✗".bright_red(),
+                CONFIG_FILE
+            );
+            std::process::exit(1);
+        }
+        return vec![Scanner::new(Config::load(&PathBuf::from(CONFIG_FILE)))];
+    }
+
+    let packages = workspace::filter_packages(packages, filters);
+    if packages.is_empty() {
+        eprintln!("{} No workspace package matches {}", "✗".bright_red(), filters.join(", "));
+        std::process::exit(1);
+    }
+
+    let cache_dir = current_dir.join(".dx");
+    packages
+        .into_iter()
+        .map(|package| {
+            let config = Config::load(&package.root.join(CONFIG_FILE));
+            let mut scanner = Scanner::with_root(config, PluginRegistry::with_builtins(), &package.root);
+            let cache_name: String = package
+                .name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect();
+            scanner.set_cache_path(cache_dir.join(format!("cache-{}.json", cache_name)));
+            scanner
+        })
+        .collect()
+}
+
+fn single_scanner(mut scanners: Vec<Scanner>) -> Scanner {
+    if scanners.len() > 1 {
+        eprintln!(
+            "{} This command runs on one package; select it with --filter <package>",
+            "✗".bright_red()
+        );
+        std::process::exit(1);
+    }
+    scanners.remove(0)
+}
+
+fn print_package(scanner: &Scanner) {
+    println!("{} {}", "📦".bold(), scanner.root.display().to_string().bright_blue());
+}
+
+fn watch_workspace(scanners: &mut [Scanner]) {
+    for scanner in scanners.iter_mut() {
+        print_package(scanner);
+        initial_scan(scanner);
+    }
+
+    println!(
+        "{}",
+        format!("👀 Watching {} packages for file changes...", scanners.len())
+            .bold()
+            .bright_purple()
+    );
+
+    dx::watch::watch_scanners(scanners, stop_on_ctrl_c(), |scanner, event| match event {
+        WatchEvent::Changed(summary) => print_change(scanner, summary),
+        WatchEvent::Failed { path, diagnostic } => print_parse_failure(scanner, path, diagnostic),
+        WatchEvent::Recovered { path } => print_recovered(scanner, path),
+        WatchEvent::Unwritable { path, diagnostic } => print_unwritable(path, diagnostic),
+        WatchEvent::Written { path } => print_written(path),
+        WatchEvent::Skipped { path, diagnostic } => print_skipped(path, diagnostic),
+        WatchEvent::Rebuilt { files, summary } => print_rebuild(scanner, files, summary),
+    });
+    for scanner in scanners.iter() {
+        save_snapshot(scanner);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    dx::watch::VERBOSE.store(cli.verbose, Ordering::Relaxed);
+    if let Some(path) = &cli.event_log {
+        if let Err(e) = dx::watch::log_events_to(path) {
+            eprintln!("{} Failed to open {}: {}", "✗".bright_red(), path.display(), e);
+        }
+    }
+    let mut scanners = load_scanners(&cli.filter);
+    if let Some(mode) = cli.diff {
+        for scanner in &mut scanners {
+            scanner.review_rewrite = Some(Box::new(move |path: &Path, old: &str, new: &str| {
+                print_diff(path, old, new);
+                mode == DiffMode::Show
+            }));
+        }
+    }
+    if !matches!(cli.command, Some(Command::Repair)) {
+        for scanner in &scanners {
+            report_interrupted(scanner);
+        }
+    }
+    for scanner in &mut scanners {
+        scanner.compression = cli.compress.iter().map(|&format| format.into()).collect();
+    }
+    if cli.interactive {
+        for scanner in &mut scanners {
+            scanner.review_rewrite = Some(interactive_review(&scanner.root));
+        }
+    }
+
+    if let Some(addr) = cli.metrics_addr.as_deref().map(normalize_addr) {
+        match dx::metrics::serve_metrics(addr.as_str()) {
+            Ok(()) => eprintln!(
+                "{} Serving metrics on {}",
+                "📈".bold(),
+                format!("http://{}/metrics", addr).bright_yellow()
+            ),
+            Err(e) => eprintln!("{} Failed to serve metrics on {}: {}", "✗".bright_red(), addr, e),
+        }
+    }
+
+    let manifests = Manifests {
+        meta: cli.emit_meta,
+        stories: cli.emit_stories,
+        hydration: cli.emit_hydration,
+        types: cli.emit_types,
+        test_ids: cli.emit_test_ids,
+    };
+    let multiple = scanners.len() > 1;
+    let mut findings = Vec::new();
+    let report = |suite: &str, findings: &[Finding]| {
+        if let Some(format) = cli.report {
+            write_report(format, cli.report_path.as_deref(), suite, findings);
+        }
+    };
+    match cli.command.unwrap_or(Command::Watch { serve: None, ui: false }) {
+        Command::Watch { serve, ui } if multiple => {
+            if serve.is_some() || ui || manifests.any() {
+                eprintln!(
+                    "{} --serve, --ui and --emit-* run on one package; select it with --filter <package>",
+                    "✗".bright_red()
+                );
+                std::process::exit(1);
+            }
+            watch_workspace(&mut scanners);
+        }
+        Command::Watch { serve, ui } => {
+            watch(&mut single_scanner(scanners), serve, ui, manifests)
+        }
+        Command::Groups => report_groups(&single_scanner(scanners)),
+        Command::Daemon { socket } => daemon(single_scanner(scanners), socket),
+        Command::Audit { css } => audit(&mut single_scanner(scanners), css),
+        Command::Lint { update_baseline } => {
+            let status = lint(&mut single_scanner(scanners), cli.max_warnings, update_baseline, &mut findings);
+            report("dx lint", &findings);
+            status.exit_unless_ok();
+        }
+        Command::Duplicates { threshold, min_classes, update_baseline } => {
+            let mut scanner = single_scanner(scanners);
+            let status =
+                duplicates(&mut scanner, threshold, min_classes, cli.max_warnings, update_baseline, &mut findings);
+            report("dx duplicates", &findings);
+            status.exit_unless_ok();
+        }
+        Command::Build { since, routes, obfuscate, no_rewrite, deterministic } => {
+            let mut status = Exit::Ok;
+            let build_package = |scanner: &mut Scanner| {
+                build(scanner, since.clone());
+                if scanner.css_options.obfuscate {
+                    write_class_map(scanner);
+                }
+                if manifests.any() {
+                    let graph = manifests.stories.is_some().then(|| scanner.import_graph());
+                    manifests.in_root(&scanner.root).write(scanner, &scanner.metadata(), graph.as_ref());
+                }
+                if routes {
+                    write_route_styles(scanner);
+                }
+            };
+            for scanner in &mut scanners {
+                if multiple {
+                    print_package(scanner);
+                }
+                scanner.css_options.obfuscate |= obfuscate;
+                scanner.options.rewrite &= !no_rewrite;
+                scanner.deterministic = deterministic;
+                build_package(scanner);
+                let built = build_exit(scanner);
+                status = status.max(built);
+                if deterministic && built == Exit::Ok {
+                    let outputs = build_outputs(scanner, &manifests, routes);
+                    let first = read_outputs(&outputs);
+                    println!("{}", "🔁 Building again to compare the outputs...".bold().bright_purple());
+                    build_package(scanner);
+                    status = status.max(build_exit(scanner)).max(verify_outputs(scanner, &outputs, &first));
+                }
+            }
+            status.exit_unless_ok();
+        }
+        Command::Check { staged } => {
+            let mut status = Exit::Ok;
+            for scanner in &mut scanners {
+                if multiple {
+                    print_package(scanner);
+                }
+                status = status.max(check(scanner, staged, &mut findings));
+                if cli.max_warnings.is_some() || cli.report.is_some() {
+                    let severity = scanner.config.lint.conflicts;
+                    let conflicts: Vec<Finding> =
+                        scanner.conflicts().iter().map(|conflict| conflict_finding(conflict, severity)).collect();
+                    let new = Baseline::load(&scanner.root).is_new(&conflicts, &scanner.root);
+                    let conflicts: Vec<Finding> =
+                        conflicts.into_iter().zip(new).filter_map(|(finding, new)| new.then_some(finding)).collect();
+                    status = status.max(warnings_exit(conflicts.len(), cli.max_warnings));
+                    findings.extend(conflicts);
+                }
+            }
+            report("dx check", &findings);
+            status.exit_unless_ok();
+        }
+        Command::FmtCheck => {
+            let mut status = Exit::Ok;
+            for scanner in &scanners {
+                if multiple {
+                    print_package(scanner);
+                }
+                status = status.max(fmt_check(scanner, &mut findings));
+            }
+            report("dx fmt-check", &findings);
+            status.exit_unless_ok();
+        }
+        Command::Hook {
+            action: HookAction::Install { force },
+        } => install_hook(force),
+        Command::Explain { code } => explain(&code),
+        Command::Migrate { from, force } => migrate(from, force),
+        Command::Config {
+            action: ConfigAction::Schema,
+        } => println!("{}", serde_json::to_string_pretty(&dx::config::schema()).unwrap()),
+        Command::Prune { delete } => prune(&mut single_scanner(scanners), delete),
+        Command::Why { class, regex } => why(&mut single_scanner(scanners), &class, regex),
+        Command::Repair => {
+            for scanner in &mut scanners {
+                if multiple {
+                    print_package(scanner);
+                }
+                repair(scanner);
+            }
+        }
+        Command::Undo { last, file } => undo(&single_scanner(scanners), last, file),
+        Command::Codemod { map, dry_run } => codemod(&mut single_scanner(scanners), &map, dry_run),
+        Command::ExtractComponent { file, line, id, name } => {
+            let element = match (line, id) {
+                (Some(line), _) => ElementSelector::Line(line),
+                (None, Some(id)) => ElementSelector::Id(id),
+                (None, None) => unreachable!("clap requires --line or --id"),
+            };
+            extract_component(&mut single_scanner(scanners), &file, &element, &name)
+        }
+        Command::Lsp => {
+            if let Err(e) = dx::lsp::run(single_scanner(scanners)) {
+                eprintln!("{} Language server failed: {}", "✗".bright_red(), e);
+            }
+        }
     }
 }
@@ -0,0 +1,59 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::extract::{ElementExtraction, Extraction};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMeta {
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+    pub generated_ids: Vec<String>,
+    pub elements: Vec<ElementExtraction>,
+}
+
+impl FileMeta {
+    pub fn from_extraction(extraction: &Extraction) -> Self {
+        let mut classnames: Vec<_> = extraction.classnames.iter().cloned().collect();
+        classnames.sort();
+        let mut ids: Vec<_> = extraction.ids.iter().cloned().collect();
+        ids.sort();
+        let generated_ids = extraction
+            .elements
+            .iter()
+            .filter(|el| el.generated_id)
+            .filter_map(|el| el.id.clone())
+            .collect();
+
+        FileMeta {
+            classnames,
+            ids,
+            generated_ids,
+            elements: extraction.elements.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MetaDocument<'a> {
+    version: u32,
+    files: BTreeMap<String, &'a FileMeta>,
+}
+
+/// Writes `files` as JSON, keyed by paths relative to the current directory.
+pub fn write_meta(output_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) -> std::io::Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let document = MetaDocument {
+        version: 1,
+        files: files
+            .iter()
+            .map(|(path, meta)| {
+                let relative = path.strip_prefix(&current_dir).unwrap_or(path);
+                (relative.to_string_lossy().replace('\\', "/"), meta)
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&document)?;
+    std::fs::write(output_path, json)
+}
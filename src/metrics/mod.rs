@@ -0,0 +1,144 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Histogram {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter().zip(BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+        for (bucket, bound) in self.buckets.iter().zip(BUCKETS) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, bucket.load(Ordering::Relaxed));
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// Process-wide counters, recorded by the scanner and watcher whether or not they are exported.
+pub struct Metrics {
+    pub files_processed: AtomicU64,
+    pub parse_failures: AtomicU64,
+    pub css_writes: AtomicU64,
+    pub debounce_queue_depth: AtomicU64,
+    pub watches: AtomicU64,
+    pub scan_duration: Histogram,
+    pub change_duration: Histogram,
+    pub css_write_duration: Histogram,
+}
+
+pub static METRICS: Metrics = Metrics {
+    files_processed: AtomicU64::new(0),
+    parse_failures: AtomicU64::new(0),
+    css_writes: AtomicU64::new(0),
+    debounce_queue_depth: AtomicU64::new(0),
+    watches: AtomicU64::new(0),
+    scan_duration: Histogram::new(),
+    change_duration: Histogram::new(),
+    css_write_duration: Histogram::new(),
+};
+
+fn counter(name: &str, kind: &str, help: &str, value: &AtomicU64, out: &mut String) {
+    let _ = writeln!(
+        out,
+        "# HELP {} {}\n# TYPE {} {}\n{} {}",
+        name,
+        help,
+        name,
+        kind,
+        name,
+        value.load(Ordering::Relaxed)
+    );
+}
+
+impl Metrics {
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        counter(
+            "dx_files_processed_total",
+            "counter",
+            "Source files extracted.",
+            &self.files_processed,
+            &mut out,
+        );
+        counter(
+            "dx_parse_failures_total",
+            "counter",
+            "Source files that failed to read or parse.",
+            &self.parse_failures,
+            &mut out,
+        );
+        counter("dx_css_writes_total", "counter", "Stylesheet writes.", &self.css_writes, &mut out);
+        counter(
+            "dx_debounce_queue_depth",
+            "gauge",
+            "Changed files waiting for the debounce window.",
+            &self.debounce_queue_depth,
+            &mut out,
+        );
+        counter(
+            "dx_watches",
+            "gauge",
+            "Directories the file watcher holds an OS watch on.",
+            &self.watches,
+            &mut out,
+        );
+        self.scan_duration
+            .render("dx_scan_duration_seconds", "Full project scans.", &mut out);
+        self.change_duration
+            .render("dx_change_duration_seconds", "Single-file change processing.", &mut out);
+        self.css_write_duration
+            .render("dx_css_write_duration_seconds", "Rendering and writing the stylesheet.", &mut out);
+        out
+    }
+}
+
+/// Serves [`METRICS`] on every path of `addr` from a background thread.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn serve_metrics(addr: impl std::net::ToSocketAddrs) -> std::io::Result<()> {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind(addr)?;
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut head = [0u8; 4096];
+            let _ = stream.read(&mut head);
+            let body = METRICS.render();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    Ok(())
+}
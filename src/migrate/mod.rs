@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use swc_common::{FileName, SourceMap};
+use swc_ecma_ast::{
+    AssignTarget, Decl, Expr, Lit, MemberProp, Module, ModuleDecl, ModuleItem, ObjectLit, Pat, Prop, PropName,
+    PropOrSpread, SimpleAssignTarget, Stmt,
+};
+
+use crate::config::{CssConfig, SourceSyntax};
+use crate::css::render_css;
+use crate::extract::parse_module;
+use crate::scanner::Scanner;
+use crate::stylesheet::{parse_rules, Rule};
+
+/// Config files `dx migrate` looks for, in order.
+pub const MIGRATABLE_CONFIGS: &[&str] = &[
+    "tailwind.config.js",
+    "tailwind.config.cjs",
+    "tailwind.config.mjs",
+    "tailwind.config.ts",
+    "uno.config.ts",
+    "uno.config.js",
+    "unocss.config.ts",
+    "unocss.config.js",
+];
+
+/// The settings dx can take over from a Tailwind or UnoCSS config.
+#[derive(Debug, Default)]
+pub struct Migration {
+    pub content: Vec<String>,
+    /// Tailwind's `prefix`, e.g. `tw-`.
+    pub class_prefix: Option<String>,
+    /// Tailwind's `important` when it is a selector such as `#app`.
+    pub selector_prefix: Option<String>,
+    /// Breakpoints from `theme.screens`, replacing dx's defaults.
+    pub screens: BTreeMap<String, String>,
+    /// Breakpoints from `theme.extend.screens`, added to dx's defaults.
+    pub extend_screens: BTreeMap<String, String>,
+    /// UnoCSS `shortcuts`, which dx expresses as groups.
+    pub groups: BTreeMap<String, String>,
+    /// Settings with no dx equivalent, as dotted key paths with a reason.
+    pub unsupported: Vec<String>,
+}
+
+pub fn find_config(root: &Path) -> Option<PathBuf> {
+    MIGRATABLE_CONFIGS.iter().map(|name| root.join(name)).find(|path| path.is_file())
+}
+
+/// Reads the config object exported by `path` without evaluating it; only literal values are
+/// understood, anything computed is reported as unsupported.
+pub fn read_config(path: &Path) -> Result<Migration, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let cm: Arc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(Arc::new(FileName::Real(path.to_path_buf())), source);
+    let module = parse_module(&fm, SourceSyntax::Tsx, None).map_err(|e| e.message)?.0;
+    let object = exported_object(&module).ok_or("no exported config object found")?;
+
+    let mut migration = Migration::default();
+    for (key, value) in properties(object) {
+        match key.as_str() {
+            "content" => migration.content = content_globs(value),
+            "prefix" => match string(value) {
+                Some(prefix) => migration.class_prefix = Some(prefix),
+                None => migration.unsupported.push("prefix: not a string literal".to_string()),
+            },
+            "important" => match value {
+                Expr::Lit(Lit::Str(s)) => migration.selector_prefix = Some(s.value.to_string()),
+                Expr::Lit(Lit::Bool(b)) if !b.value => {}
+                _ => migration
+                    .unsupported
+                    .push("important: use the `!` modifier on individual classes instead".to_string()),
+            },
+            "theme" => {
+                let Some(theme) = object_lit(value) else {
+                    migration.unsupported.push("theme: not an object literal".to_string());
+                    continue;
+                };
+                for (key, value) in properties(theme) {
+                    match key.as_str() {
+                        "screens" => migration.screens = screens(value, "theme.screens", &mut migration.unsupported),
+                        "extend" => {
+                            for (key, value) in object_lit(value).map(properties).unwrap_or_default() {
+                                if key == "screens" {
+                                    migration.extend_screens =
+                                        screens(value, "theme.extend.screens", &mut migration.unsupported);
+                                } else {
+                                    migration.unsupported.push(format!("theme.extend.{}: custom theme values", key));
+                                }
+                            }
+                        }
+                        _ => migration.unsupported.push(format!("theme.{}: custom theme values", key)),
+                    }
+                }
+            }
+            "shortcuts" => match object_lit(value) {
+                Some(shortcuts) => {
+                    for (name, value) in properties(shortcuts) {
+                        match string(value) {
+                            Some(classes) => {
+                                migration.groups.insert(name, classes);
+                            }
+                            None => migration.unsupported.push(format!("shortcuts.{}: not a string literal", name)),
+                        }
+                    }
+                }
+                None => migration.unsupported.push("shortcuts: dynamic shortcuts".to_string()),
+            },
+            "plugins" | "presets" if is_empty_array(value) => {}
+            "separator" if string(value).as_deref() == Some(":") => {}
+            _ => migration.unsupported.push(format!("{}: no dx equivalent", key)),
+        }
+    }
+    Ok(migration)
+}
+
+impl Migration {
+    /// Content globs dx will not see, as it only scans `src/`.
+    pub fn outside_src(&self) -> Vec<&str> {
+        self.content
+            .iter()
+            .map(String::as_str)
+            .filter(|glob| !glob.trim_start_matches("./").starts_with("src/"))
+            .collect()
+    }
+
+    fn syntax(&self) -> SourceSyntax {
+        let mentions = |ext: &str| self.content.iter().any(|glob| glob.contains(ext));
+        if mentions("jsx") && !mentions("tsx") {
+            SourceSyntax::Jsx
+        } else {
+            SourceSyntax::Tsx
+        }
+    }
+
+    /// The equivalent `dx.config.toml`.
+    pub fn config_toml(&self, source: &Path) -> String {
+        let mut out = format!(
+            "# Migrated from {} by `dx migrate`.\n",
+            source.file_name().unwrap_or_default().to_string_lossy()
+        );
+        if self.syntax() == SourceSyntax::Jsx {
+            out.push_str("syntax = \"jsx\"\n");
+        }
+        if !self.groups.is_empty() {
+            out.push_str("\n[groups]\n");
+            for (name, classes) in &self.groups {
+                out.push_str(&format!("{} = {}\n", toml_key(name), toml_string(classes)));
+            }
+        }
+
+        let mut css = String::new();
+        if let Some(prefix) = &self.class_prefix {
+            css.push_str(&format!("class_prefix = {}\n", toml_string(prefix)));
+        }
+        if let Some(selector) = &self.selector_prefix {
+            css.push_str(&format!("selector_prefix = {}\n", toml_string(selector)));
+        }
+        if !css.is_empty() {
+            out.push_str("\n[css]\n");
+            out.push_str(&css);
+        }
+
+        let mut screens = if self.screens.is_empty() && !self.extend_screens.is_empty() {
+            CssConfig::default().screens
+        } else {
+            self.screens.clone()
+        };
+        screens.extend(self.extend_screens.clone());
+        if !screens.is_empty() {
+            out.push_str("\n[css.screens]\n");
+            for (name, width) in &screens {
+                out.push_str(&format!("{} = {}\n", toml_key(name), toml_string(width)));
+            }
+        }
+        out
+    }
+}
+
+/// Classes found by scanning that neither dx's generated CSS nor a handwritten stylesheet
+/// gives any declarations, sorted.
+pub fn uncovered_classes(scanner: &Scanner) -> Vec<String> {
+    let handwritten: HashSet<String> = scanner
+        .stylesheets()
+        .iter()
+        .flat_map(|(_, rules)| rules.iter().filter(|rule| rule.has_declarations()).flat_map(Rule::classes))
+        .collect();
+    let groups: HashSet<&str> = scanner.config.groups.keys().map(String::as_str).collect();
+    let mut uncovered: Vec<String> = scanner
+        .classnames
+        .iter()
+        .filter(|class| **class != scanner.options.id_trigger && !groups.contains(class.as_str()))
+        .filter(|class| !handwritten.contains(*class))
+        .filter(|class| {
+            let classnames = HashSet::from([class.to_string()]);
+            !parse_rules(&render_css(&classnames, &HashSet::new(), &scanner.css_options))
+                .iter()
+                .any(Rule::has_declarations)
+        })
+        .cloned()
+        .collect();
+    uncovered.sort();
+    uncovered
+}
+
+fn exported_object(module: &Module) -> Option<&ObjectLit> {
+    module.body.iter().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => config_object(module, &export.expr),
+        ModuleItem::Stmt(Stmt::Expr(stmt)) => match &*stmt.expr {
+            Expr::Assign(assign) if is_module_exports(&assign.left) => config_object(module, &assign.right),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn is_module_exports(target: &AssignTarget) -> bool {
+    let AssignTarget::Simple(SimpleAssignTarget::Member(member)) = target else {
+        return false;
+    };
+    matches!(&*member.obj, Expr::Ident(obj) if &*obj.sym == "module")
+        && matches!(&member.prop, MemberProp::Ident(prop) if &*prop.sym == "exports")
+}
+
+/// Sees through `defineConfig({...})`, `satisfies Config`, `as Config`, parentheses and a
+/// top-level `const config = {...}` binding.
+fn config_object<'a>(module: &'a Module, expr: &'a Expr) -> Option<&'a ObjectLit> {
+    match expr {
+        Expr::Object(object) => Some(object),
+        Expr::Paren(paren) => config_object(module, &paren.expr),
+        Expr::TsSatisfies(satisfies) => config_object(module, &satisfies.expr),
+        Expr::TsAs(as_expr) => config_object(module, &as_expr.expr),
+        Expr::Call(call) => config_object(module, &call.args.first()?.expr),
+        Expr::Ident(ident) => module.body.iter().find_map(|item| {
+            let ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) = item else {
+                return None;
+            };
+            var.decls.iter().find_map(|decl| match &decl.name {
+                Pat::Ident(binding) if binding.id.sym == ident.sym => config_object(module, decl.init.as_deref()?),
+                _ => None,
+            })
+        }),
+        _ => None,
+    }
+}
+
+fn properties(object: &ObjectLit) -> Vec<(String, &Expr)> {
+    object
+        .props
+        .iter()
+        .filter_map(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(kv) => {
+                    let key = match &kv.key {
+                        PropName::Ident(ident) => ident.sym.to_string(),
+                        PropName::Str(s) => s.value.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, &*kv.value))
+                }
+                _ => None,
+            },
+            PropOrSpread::Spread(_) => None,
+        })
+        .collect()
+}
+
+fn object_lit(expr: &Expr) -> Option<&ObjectLit> {
+    match expr {
+        Expr::Object(object) => Some(object),
+        Expr::Paren(paren) => object_lit(&paren.expr),
+        _ => None,
+    }
+}
+
+fn string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+        Expr::Tpl(tpl) if tpl.exprs.is_empty() => Some(tpl.quasis.iter().map(|q| q.raw.to_string()).collect()),
+        _ => None,
+    }
+}
+
+fn strings(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::Array(array) => array.elems.iter().flatten().filter_map(|elem| string(&elem.expr)).collect(),
+        _ => string(expr).into_iter().collect(),
+    }
+}
+
+fn is_empty_array(expr: &Expr) -> bool {
+    matches!(expr, Expr::Array(array) if array.elems.is_empty())
+}
+
+/// Tailwind's `content: [...]` or `content: { files: [...] }`, or UnoCSS's
+/// `content: { filesystem: [...] }`.
+fn content_globs(expr: &Expr) -> Vec<String> {
+    match object_lit(expr) {
+        Some(object) => properties(object)
+            .into_iter()
+            .filter(|(key, _)| key == "files" || key == "filesystem")
+            .flat_map(|(_, value)| strings(value))
+            .collect(),
+        None => strings(expr),
+    }
+}
+
+fn screens(expr: &Expr, path: &str, unsupported: &mut Vec<String>) -> BTreeMap<String, String> {
+    let Some(object) = object_lit(expr) else {
+        unsupported.push(format!("{}: not an object literal", path));
+        return BTreeMap::new();
+    };
+    properties(object)
+        .into_iter()
+        .filter_map(|(name, value)| match string(value) {
+            Some(width) => Some((name, width)),
+            None => {
+                unsupported.push(format!("{}.{}: only min-width breakpoints are supported", path, name));
+                None
+            }
+        })
+        .collect()
+}
+
+fn toml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
@@ -0,0 +1,15 @@
+use notify_rust::Notification;
+
+/// Shows a desktop notification. Failures (no notification daemon, headless CI) are ignored.
+pub fn notify_error(summary: &str, body: &str) {
+    let _ = Notification::new().appname("dx").summary(summary).body(body).show();
+}
+
+/// Notifies before the default panic output, so a crashed watcher (e.g. a failed write) is noticed.
+pub fn notify_on_panic() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        notify_error("dx watch stopped", &info.to_string());
+        previous(info);
+    }));
+}
@@ -0,0 +1,217 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use swc_ecma_ast::{
+    Callee, Expr, JSXAttr, JSXAttrName, JSXAttrValue, JSXExpr, Lit, PropName, Str, TplElement,
+};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+use crate::cache::content_hash;
+use crate::utility::parse_class;
+
+pub const CLASS_MAP_FILE: &str = "dx-classmap.json";
+
+const TOKEN_LENGTH: usize = 7;
+
+/// The production name of `classname`: `d` followed by base36 digits of its content hash.
+///
+/// The name only depends on the class itself, so files transformed independently (e.g. by a
+/// bundler plugin) agree with the stylesheet. Classes already no longer than a token are kept.
+pub fn obfuscated_name(classname: &str) -> Cow<'_, str> {
+    if classname.len() <= TOKEN_LENGTH + 1 || classname == "id" {
+        return Cow::Borrowed(classname);
+    }
+    let mut hash = content_hash(classname.as_bytes());
+    let mut token = String::with_capacity(TOKEN_LENGTH + 1);
+    token.push('d');
+    for _ in 0..TOKEN_LENGTH {
+        token.push(std::char::from_digit((hash % 36) as u32, 36).unwrap_or('0'));
+        hash /= 36;
+    }
+    Cow::Owned(token)
+}
+
+/// The replacement for a deprecated class from the config's `rename` table. A rename of `btn-old`
+/// also applies under variants, so `hover:btn-old` becomes `hover:btn`.
+pub fn renamed_class<'a>(classname: &'a str, renames: &HashMap<String, String>) -> Cow<'a, str> {
+    if let Some(renamed) = renames.get(classname) {
+        return Cow::Owned(renamed.clone());
+    }
+    let utility = parse_class(classname).utility;
+    match renames.get(utility) {
+        Some(renamed) => Cow::Owned(format!("{}{}", &classname[..classname.len() - utility.len()], renamed)),
+        None => Cow::Borrowed(classname),
+    }
+}
+
+/// Renames every whitespace-separated class in `value`, keeping the whitespace and group
+/// references (`name(...)`) as they are.
+pub fn obfuscate_class_list(value: &str) -> String {
+    rename_class_list(value, &|classname| obfuscated_name(classname).into_owned())
+}
+
+/// Like [`obfuscate_class_list`], with `rename` deciding each class's new name. A class renamed
+/// to nothing is dropped along with the whitespace after it.
+pub fn rename_class_list(value: &str, rename: &dyn Fn(&str) -> String) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut dropped_last = false;
+    while !rest.is_empty() {
+        let token_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (token, tail) = rest.split_at(token_end);
+        let space_end = tail.find(|c: char| !c.is_whitespace()).unwrap_or(tail.len());
+        rest = &tail[space_end..];
+        if token.contains('(') || token.contains(')') {
+            output.push_str(token);
+        } else {
+            let renamed = rename(token);
+            dropped_last = renamed.is_empty() && !token.is_empty();
+            if dropped_last {
+                continue;
+            }
+            output.push_str(&renamed);
+        }
+        output.push_str(&tail[..space_end]);
+    }
+    if dropped_last && !value.ends_with(char::is_whitespace) {
+        output.truncate(output.trim_end().len());
+    }
+    output
+}
+
+/// Original class to production name, for every class that is renamed.
+pub fn class_map(classnames: &HashSet<String>) -> BTreeMap<String, String> {
+    classnames
+        .iter()
+        .filter_map(|classname| match obfuscated_name(classname) {
+            Cow::Owned(token) => Some((classname.clone(), token)),
+            Cow::Borrowed(_) => None,
+        })
+        .collect()
+}
+
+/// Production names shared by more than one class.
+pub fn collisions(map: &BTreeMap<String, String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut collisions: Vec<String> = map.values().filter(|token| !seen.insert(*token)).cloned().collect();
+    collisions.sort();
+    collisions.dedup();
+    collisions
+}
+
+pub fn write_class_map(output_path: &Path, map: &BTreeMap<String, String>) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(map)?;
+    std::fs::write(output_path, json)
+}
+
+fn renamed_str(s: &Str, rename: &dyn Fn(&str) -> String) -> Str {
+    Str {
+        span: s.span,
+        value: rename_class_list(&s.value, rename).into(),
+        raw: None,
+    }
+}
+
+/// Renames classes in configured attributes and helper calls, mirroring where
+/// [`crate::id::InfoCollector`] finds them: to production names for `features.obfuscate`, or to
+/// their replacements from the config's `rename` table.
+pub struct ClassRenamer<'a> {
+    pub attributes: &'a [String],
+    pub helpers: &'a [String],
+    pub rename: &'a dyn Fn(&str) -> String,
+}
+
+struct HelperClassRenamer<'a> {
+    rename: &'a dyn Fn(&str) -> String,
+}
+
+impl VisitMut for HelperClassRenamer<'_> {
+    fn visit_mut_str(&mut self, s: &mut Str) {
+        *s = renamed_str(s, self.rename);
+    }
+
+    fn visit_mut_tpl_element(&mut self, el: &mut TplElement) {
+        let renamed = rename_class_list(&el.raw, self.rename);
+        el.cooked = Some(renamed.clone().into());
+        el.raw = renamed.into();
+    }
+
+    fn visit_mut_prop_name(&mut self, name: &mut PropName) {
+        if let PropName::Ident(ident) = name {
+            let renamed = (self.rename)(&ident.sym);
+            if renamed != *ident.sym {
+                *name = PropName::Str(Str {
+                    span: ident.span,
+                    value: renamed.into(),
+                    raw: None,
+                });
+            }
+            return;
+        }
+        name.visit_mut_children_with(self);
+    }
+}
+
+impl ClassRenamer<'_> {
+    fn rename_expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Lit(Lit::Str(s)) => *s = renamed_str(s, self.rename),
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+                for quasi in &mut tpl.quasis {
+                    HelperClassRenamer { rename: self.rename }.visit_mut_tpl_element(quasi);
+                }
+            }
+            Expr::Paren(paren) => self.rename_expr(&mut paren.expr),
+            Expr::Call(call) => {
+                let is_helper = matches!(
+                    &call.callee,
+                    Callee::Expr(callee) if matches!(&**callee, Expr::Ident(ident) if self.helpers.iter().any(|h| h.as_str() == &*ident.sym))
+                );
+                if is_helper {
+                    call.args.visit_mut_with(&mut HelperClassRenamer { rename: self.rename });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl VisitMut for ClassRenamer<'_> {
+    fn visit_mut_jsx_attr(&mut self, attr: &mut JSXAttr) {
+        let is_class_attr = matches!(&attr.name, JSXAttrName::Ident(ident) if self.attributes.iter().any(|a| a.as_str() == &*ident.sym));
+        if is_class_attr {
+            match &mut attr.value {
+                Some(JSXAttrValue::Lit(Lit::Str(s))) => *s = renamed_str(s, self.rename),
+                Some(JSXAttrValue::JSXExprContainer(container)) => {
+                    if let JSXExpr::Expr(expr) = &mut container.expr {
+                        self.rename_expr(expr);
+                    }
+                }
+                _ => {}
+            }
+        }
+        attr.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_apply_under_variants() {
+        let renames = HashMap::from([("btn-old".to_string(), "btn".to_string())]);
+        assert_eq!(renamed_class("btn-old", &renames), "btn");
+        assert_eq!(renamed_class("hover:btn-old", &renames), "hover:btn");
+        assert_eq!(renamed_class("btn-older", &renames), "btn-older");
+    }
+
+    #[test]
+    fn obfuscated_names_are_short_and_stable() {
+        assert_eq!(obfuscated_name("p-4"), "p-4");
+        assert_eq!(obfuscated_name("id"), "id");
+        let name = obfuscated_name("hover:bg-blue-500");
+        assert!(name.starts_with('d') && name.len() == TOKEN_LENGTH + 1);
+        assert_eq!(name, obfuscated_name("hover:bg-blue-500"));
+    }
+}
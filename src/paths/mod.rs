@@ -0,0 +1,109 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `std::fs::canonicalize` without the `\\?\` verbatim prefix Windows adds, so canonical paths
+/// compare equal to the plain paths reported by the watcher and globbing.
+pub fn canonicalize(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    dunce::canonicalize(path)
+}
+
+/// The identity used for `file_map` keys: the canonical path with the on-disk spelling of its
+/// name, or for a file that no longer exists (e.g. a delete event), its canonical parent joined
+/// with its name.
+pub fn normalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = canonicalize(path) {
+        return on_disk_case(canonical);
+    }
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => canonicalize(parent).map(|parent| parent.join(name)).unwrap_or(absolute),
+        _ => absolute,
+    }
+}
+
+/// Whether the default filesystems of this platform (APFS, NTFS) ignore case in file names.
+pub const CASE_INSENSITIVE: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Whether `a` and `b` name the same file, ignoring case where the filesystem does.
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    if !CASE_INSENSITIVE {
+        return a == b;
+    }
+    a == b || a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+/// `path` with its file name spelled as on disk, so an event for `button.tsx` maps to the
+/// `Button.tsx` it refers to. Case-sensitive platforms get `path` back unchanged.
+fn on_disk_case(path: PathBuf) -> PathBuf {
+    if !CASE_INSENSITIVE {
+        return path;
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return path;
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return path;
+    };
+    let names: Vec<_> = entries.filter_map(Result::ok).map(|entry| entry.file_name()).collect();
+    if names.iter().any(|entry| entry == name) {
+        return path;
+    }
+    let folded = name.to_string_lossy().to_lowercase();
+    match names.into_iter().find(|entry| entry.to_string_lossy().to_lowercase() == folded) {
+        Some(entry) => parent.join(entry),
+        None => path,
+    }
+}
+
+/// Every symlink below `dir` with its canonical target, without descending into linked directories.
+pub fn symlinks(dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut links = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                if let Ok(target) = canonicalize(entry.path()) {
+                    links.push((entry.path(), target));
+                }
+            } else if file_type.is_dir() {
+                pending.push(entry.path());
+            }
+        }
+    }
+    links.sort();
+    links
+}
+
+/// Directories no source worth watching lives in: `node_modules` and hidden ones like `.git`.
+pub fn is_ignored_dir(dir: &Path) -> bool {
+    dir.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name == "node_modules" || name.starts_with('.'))
+}
+
+/// `root` and every directory below it, without descending into symlinks or directories
+/// `skip` rejects.
+pub fn directories(root: &Path, skip: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            pending.extend(
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+                    .map(|entry| entry.path())
+                    .filter(|path| !skip(path)),
+            );
+        }
+        dirs.push(dir);
+    }
+    dirs.sort();
+    dirs
+}
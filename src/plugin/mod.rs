@@ -0,0 +1,209 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::codes::{INVALID_PATTERN, UNKNOWN_PLUGIN};
+use crate::config::PluginConfig;
+
+/// Finds class names that the built-in JSX extraction cannot see, e.g. an
+/// in-house styling DSL.
+pub trait Extractor: Send + Sync {
+    fn name(&self) -> &str;
+    fn extract(&self, path: Option<&Path>, source: &str) -> Vec<String>;
+}
+
+/// Post-processes the rendered stylesheet.
+pub trait CssEmitter: Send + Sync {
+    fn name(&self) -> &str;
+    fn emit(&self, classnames: &HashSet<String>, ids: &HashSet<String>, css: &mut String);
+}
+
+/// Derives the base id for an element from its classes (the `id` trigger excluded).
+/// Elements sharing a base id are numbered `base1`, `base2`, ...
+pub trait IdStrategy: Send + Sync {
+    fn name(&self) -> &str;
+    fn base_id(&self, classes: &[String]) -> String;
+}
+
+impl fmt::Debug for dyn Extractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Extractor({})", self.name())
+    }
+}
+
+impl fmt::Debug for dyn CssEmitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CssEmitter({})", self.name())
+    }
+}
+
+impl fmt::Debug for dyn IdStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IdStrategy({})", self.name())
+    }
+}
+
+/// The original dx scheme: sorted, de-duplicated initials of (a sample of) the classes.
+pub struct InitialsIdStrategy;
+
+impl IdStrategy for InitialsIdStrategy {
+    fn name(&self) -> &str {
+        "initials"
+    }
+
+    fn base_id(&self, classes: &[String]) -> String {
+        if classes.is_empty() {
+            return "G".to_string();
+        }
+
+        let classes_to_sample = if classes.len() > 5 {
+            vec![
+                &classes[0],
+                &classes[1],
+                &classes[classes.len() / 2],
+                &classes[classes.len() - 2],
+                &classes[classes.len() - 1],
+            ]
+        } else {
+            classes.iter().collect()
+        };
+
+        let mut id_chars: Vec<char> = classes_to_sample
+            .iter()
+            .filter_map(|s| s.trim_start_matches(['-', '!']).chars().next())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        id_chars.sort_unstable();
+        id_chars.dedup();
+        id_chars.into_iter().collect()
+    }
+}
+
+/// A short, stable FNV-1a hash of the sorted class list.
+pub struct HashIdStrategy;
+
+impl IdStrategy for HashIdStrategy {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn base_id(&self, classes: &[String]) -> String {
+        let mut sorted: Vec<_> = classes.iter().collect();
+        sorted.sort();
+        let mut hash: u32 = 0x811c9dc5;
+        for byte in sorted.iter().flat_map(|c| c.bytes().chain(std::iter::once(b' '))) {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+        format!("x{:06x}", hash & 0xffffff)
+    }
+}
+
+/// Extracts the first capture group of every match of a configured pattern.
+pub struct PatternExtractor {
+    name: String,
+    pattern: Regex,
+}
+
+impl PatternExtractor {
+    pub fn new(name: &str, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(PatternExtractor {
+            name: name.to_string(),
+            pattern: Regex::new(pattern)?,
+        })
+    }
+}
+
+impl Extractor for PatternExtractor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extract(&self, _path: Option<&Path>, source: &str) -> Vec<String> {
+        self.pattern
+            .captures_iter(source)
+            .filter_map(|caps| caps.get(1).or(caps.get(0)))
+            .flat_map(|m| m.as_str().split_whitespace().map(String::from).collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+/// Named plugins available for `[plugins]` in the config to enable.
+#[derive(Default)]
+pub struct PluginRegistry {
+    extractors: HashMap<String, Arc<dyn Extractor>>,
+    emitters: HashMap<String, Arc<dyn CssEmitter>>,
+    id_strategies: HashMap<String, Arc<dyn IdStrategy>>,
+}
+
+impl PluginRegistry {
+    pub fn with_builtins() -> Self {
+        let mut registry = PluginRegistry::default();
+        registry.register_id_strategy(Arc::new(InitialsIdStrategy));
+        registry.register_id_strategy(Arc::new(HashIdStrategy));
+        registry
+    }
+
+    pub fn register_extractor(&mut self, extractor: Arc<dyn Extractor>) {
+        self.extractors.insert(extractor.name().to_string(), extractor);
+    }
+
+    pub fn register_emitter(&mut self, emitter: Arc<dyn CssEmitter>) {
+        self.emitters.insert(emitter.name().to_string(), emitter);
+    }
+
+    pub fn register_id_strategy(&mut self, strategy: Arc<dyn IdStrategy>) {
+        self.id_strategies.insert(strategy.name().to_string(), strategy);
+    }
+
+    /// Registers the config's pattern extractors, then resolves every enabled
+    /// plugin by name. Unknown names are reported and skipped.
+    pub fn resolve(&mut self, config: &PluginConfig) -> ResolvedPlugins {
+        let mut extractors = Vec::new();
+        for (name, pattern) in &config.patterns {
+            match PatternExtractor::new(name, pattern) {
+                Ok(extractor) => {
+                    let extractor: Arc<dyn Extractor> = Arc::new(extractor);
+                    self.register_extractor(extractor.clone());
+                    extractors.push(extractor);
+                }
+                Err(e) => eprintln!("[{}] Invalid pattern for extractor {}: {}", INVALID_PATTERN, name, e),
+            }
+        }
+        extractors.extend(lookup(&self.extractors, &config.extractors, "extractor"));
+
+        let emitters = lookup(&self.emitters, &config.emitters, "emitter");
+
+        let id_strategy = lookup(&self.id_strategies, std::slice::from_ref(&config.id_strategy), "id strategy")
+            .pop()
+            .unwrap_or_else(|| Arc::new(InitialsIdStrategy));
+
+        ResolvedPlugins {
+            extractors,
+            emitters,
+            id_strategy,
+        }
+    }
+}
+
+fn lookup<T: ?Sized>(available: &HashMap<String, Arc<T>>, names: &[String], kind: &str) -> Vec<Arc<T>> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let plugin = available.get(name).cloned();
+            if plugin.is_none() {
+                eprintln!("[{}] Unknown {} plugin: {}", UNKNOWN_PLUGIN, kind, name);
+            }
+            plugin
+        })
+        .collect()
+}
+
+pub struct ResolvedPlugins {
+    pub extractors: Vec<Arc<dyn Extractor>>,
+    pub emitters: Vec<Arc<dyn CssEmitter>>,
+    pub id_strategy: Arc<dyn IdStrategy>,
+}
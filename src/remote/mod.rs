@@ -0,0 +1,245 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ureq::Agent;
+
+use crate::cache::{CacheEntry, RemoteCache};
+use crate::config::CacheConfig;
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(5)))
+        .build()
+        .into()
+}
+
+/// Stores entries as `{base}/{key}.json` with plain GET and PUT, optionally with a bearer token.
+pub struct HttpCache {
+    base_url: String,
+    token: Option<String>,
+    upload: bool,
+    agent: Agent,
+}
+
+impl HttpCache {
+    pub fn new(base_url: &str, token: Option<String>, upload: bool) -> Self {
+        HttpCache {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            upload,
+            agent: agent(),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}.json", self.base_url, key)
+    }
+}
+
+impl RemoteCache for HttpCache {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut request = self.agent.get(&self.url(key));
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        let mut response = request.call().ok()?;
+        serde_json::from_str(&response.body_mut().read_to_string().ok()?).ok()
+    }
+
+    fn put(&self, key: &str, entry: &CacheEntry) {
+        if !self.upload {
+            return;
+        }
+        let Ok(body) = serde_json::to_string(entry) else {
+            return;
+        };
+        let mut request = self.agent.put(&self.url(key)).header("Content-Type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        let _ = request.send(body);
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `(YYYYMMDD, YYYYMMDDTHHMMSSZ)` for a Unix timestamp, as SigV4 expects.
+fn amz_dates(secs: u64) -> (String, String) {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil-from-days, Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let datetime = format!("{}T{:02}{:02}{:02}Z", date, rem / 3_600, rem % 3_600 / 60, rem % 60);
+    (date, datetime)
+}
+
+fn uri_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Stores entries in an S3 (or S3-compatible) bucket, signed with AWS Signature Version 4 using
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and optionally `AWS_SESSION_TOKEN`.
+pub struct S3Cache {
+    endpoint: String,
+    host: String,
+    bucket: String,
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    upload: bool,
+    agent: Agent,
+}
+
+impl S3Cache {
+    /// Returns `None` when credentials are missing from the environment.
+    pub fn from_env(bucket: &str, prefix: &str, region: &str, endpoint: Option<&str>, upload: bool) -> Option<Self> {
+        let endpoint = endpoint
+            .map(|e| e.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let host = endpoint.split("://").nth(1).unwrap_or(&endpoint).to_string();
+        Some(S3Cache {
+            host,
+            endpoint,
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            region: region.to_string(),
+            access_key: std::env::var("AWS_ACCESS_KEY_ID").ok()?,
+            secret_key: std::env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            upload,
+            agent: agent(),
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        let object = if self.prefix.is_empty() {
+            format!("{}.json", key)
+        } else {
+            format!("{}/{}.json", self.prefix, key)
+        };
+        uri_encode_path(&format!("/{}/{}", self.bucket, object))
+    }
+
+    /// The headers of a signed request with an unsigned payload.
+    fn signed_headers(&self, method: &str, path: &str) -> Vec<(&'static str, String)> {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let (date, datetime) = amz_dates(secs);
+
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", "UNSIGNED-PAYLOAD".to_string()),
+            ("x-amz-date", datetime.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+        let signed_names = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+
+        let canonical_request =
+            format!("{}\n{}\n\n{}\n{}\nUNSIGNED-PAYLOAD", method, path, canonical_headers, signed_names);
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            datetime,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let k_date = hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        let k_region = hmac(&k_date, &self.region);
+        let k_service = hmac(&k_region, "s3");
+        let k_signing = hmac(&k_service, "aws4_request");
+        let signature = hex(&hmac(&k_signing, &string_to_sign));
+
+        headers.retain(|(name, _)| *name != "host");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key, scope, signed_names, signature
+            ),
+        ));
+        headers
+    }
+}
+
+impl RemoteCache for S3Cache {
+    fn name(&self) -> &str {
+        "s3"
+    }
+
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let path = self.object_path(key);
+        let mut request = self.agent.get(&format!("{}{}", self.endpoint, path));
+        for (name, value) in self.signed_headers("GET", &path) {
+            request = request.header(name, value);
+        }
+        let mut response = request.call().ok()?;
+        serde_json::from_str(&response.body_mut().read_to_string().ok()?).ok()
+    }
+
+    fn put(&self, key: &str, entry: &CacheEntry) {
+        if !self.upload {
+            return;
+        }
+        let Ok(body) = serde_json::to_string(entry) else {
+            return;
+        };
+        let path = self.object_path(key);
+        let mut request = self
+            .agent
+            .put(&format!("{}{}", self.endpoint, path))
+            .header("Content-Type", "application/json");
+        for (name, value) in self.signed_headers("PUT", &path) {
+            request = request.header(name, value);
+        }
+        let _ = request.send(body);
+    }
+}
+
+/// The remote cache configured in `[cache]`, if any.
+pub fn from_config(config: &CacheConfig) -> Option<Arc<dyn RemoteCache>> {
+    let remote = config.remote.as_deref()?;
+    if let Some(location) = remote.strip_prefix("s3://") {
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        let cache = S3Cache::from_env(bucket, prefix, &config.region, config.endpoint.as_deref(), config.upload);
+        if cache.is_none() {
+            eprintln!("Remote cache {} disabled: AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY are not set", remote);
+        }
+        return cache.map(|cache| Arc::new(cache) as Arc<dyn RemoteCache>);
+    }
+    let token = std::env::var(&config.token_env).ok();
+    Some(Arc::new(HttpCache::new(remote, token, config.upload)))
+}
@@ -0,0 +1,293 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::codes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// One problem found by `dx check`, `dx fmt-check`, `dx lint` or `dx duplicates`, in a form CI
+/// tools can show next to the code.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub code: &'static str,
+    pub level: Level,
+    pub path: PathBuf,
+    /// 1-based; 0 when the finding is about the whole file.
+    pub line: usize,
+    /// 1-based; 0 when the finding is about the whole file or line.
+    pub column: usize,
+    pub message: String,
+}
+
+/// The 1-based line and column of byte `offset` in `source`.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+fn relative<'a>(path: &'a Path, base: &Path) -> &'a Path {
+    path.strip_prefix(base).unwrap_or(path)
+}
+
+fn location(finding: &Finding, base: &Path) -> String {
+    let path = relative(&finding.path, base).display();
+    match (finding.line, finding.column) {
+        (0, _) => path.to_string(),
+        (line, 0) => format!("{}:{}", path, line),
+        (line, column) => format!("{}:{}:{}", path, line, column),
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A JUnit XML report with one test case per file, failing on its errors; warnings are attached
+/// as output so they show up without failing the test tab. `suite` names the pass, e.g. `dx check`.
+pub fn junit(suite: &str, findings: &[Finding], base: &Path) -> String {
+    let mut files: BTreeMap<&Path, Vec<&Finding>> = BTreeMap::new();
+    for finding in findings {
+        files.entry(relative(&finding.path, base)).or_default().push(finding);
+    }
+    let failures = files.values().filter(|findings| findings.iter().any(|f| f.level == Level::Error)).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let counts = format!(
+        "name=\"{}\" tests=\"{}\" failures=\"{}\"",
+        escape_xml(suite),
+        files.len().max(1),
+        failures
+    );
+    xml.push_str(&format!("<testsuites {0}>\n  <testsuite {0}>\n", counts));
+    if files.is_empty() {
+        xml.push_str(&format!("    <testcase name=\"{0}\" classname=\"{0}\"/>\n", escape_xml(suite)));
+    }
+    for (path, findings) in files {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\">\n",
+            escape_xml(&path.display().to_string()),
+            escape_xml(suite)
+        ));
+        let (errors, warnings): (Vec<&Finding>, Vec<&Finding>) =
+            findings.into_iter().partition(|finding| finding.level == Level::Error);
+        let lines = |findings: &[&Finding]| {
+            findings
+                .iter()
+                .map(|f| escape_xml(&format!("[{}] {}: {}", f.code, location(f, base), f.message)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        if let Some(first) = errors.first() {
+            xml.push_str(&format!(
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                first.code,
+                escape_xml(&first.message),
+                lines(&errors)
+            ));
+        }
+        if !warnings.is_empty() {
+            xml.push_str(&format!("      <system-out>{}</system-out>\n", lines(&warnings)));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// A SARIF 2.1.0 log for code-scanning UIs, with a rule per diagnostic code that occurs.
+pub fn sarif(findings: &[Finding], base: &Path) -> String {
+    let mut rule_codes: Vec<&str> = findings.iter().map(|finding| finding.code).collect();
+    rule_codes.sort_unstable();
+    rule_codes.dedup();
+    let rules: Vec<_> = rule_codes
+        .iter()
+        .map(|code| {
+            let entry = codes::explain(code);
+            json!({
+                "id": code,
+                "shortDescription": { "text": entry.map_or(*code, |entry| entry.title) },
+                "fullDescription": { "text": entry.map_or("", |entry| entry.explanation) },
+                "help": { "text": format!("Run `dx explain {}` for the cause and fix.", code) },
+            })
+        })
+        .collect();
+    let results: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            let uri = relative(&finding.path, base).to_string_lossy().replace('\\', "/");
+            let mut location = json!({ "artifactLocation": { "uri": uri } });
+            if finding.line > 0 {
+                location["region"] = json!({ "startLine": finding.line, "startColumn": finding.column.max(1) });
+            }
+            json!({
+                "ruleId": finding.code,
+                "ruleIndex": rule_codes.binary_search(&finding.code).unwrap_or_default(),
+                "level": finding.level,
+                "message": { "text": finding.message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "dx",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+/// The findings as a JSON array, with paths relative to `base`.
+pub fn json(findings: &[Finding], base: &Path) -> String {
+    let findings: Vec<Finding> = findings
+        .iter()
+        .map(|finding| Finding { path: relative(&finding.path, base).to_path_buf(), ..finding.clone() })
+        .collect();
+    serde_json::to_string_pretty(&findings).unwrap_or_default()
+}
+
+fn escape_workflow(text: &str, property: bool) -> String {
+    let escaped = text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    if property { escaped.replace(':', "%3A").replace(',', "%2C") } else { escaped }
+}
+
+/// GitHub Actions workflow commands (`::error file=...,line=...::message`) that annotate the
+/// findings on pull request diffs, one per line.
+pub fn github(findings: &[Finding], base: &Path) -> String {
+    let mut output = String::new();
+    for finding in findings {
+        let command = match finding.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        let path = relative(&finding.path, base).to_string_lossy().replace('\\', "/");
+        let mut properties = vec![format!("file={}", escape_workflow(&path, true))];
+        if finding.line > 0 {
+            properties.push(format!("line={}", finding.line));
+        }
+        if finding.column > 0 {
+            properties.push(format!("col={}", finding.column));
+        }
+        properties.push(format!("title={}", finding.code));
+        output.push_str(&format!(
+            "::{} {}::{}\n",
+            command,
+            properties.join(","),
+            escape_workflow(&finding.message, false)
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn findings() -> Vec<Finding> {
+        vec![
+            Finding {
+                code: codes::PARSE_FAILURE,
+                level: Level::Error,
+                path: PathBuf::from("/project/src/a.tsx"),
+                line: 3,
+                column: 5,
+                message: "expected <div>, found end".to_string(),
+            },
+            Finding {
+                code: codes::STALE_SOURCE,
+                level: Level::Warning,
+                path: PathBuf::from("/project/src/b.tsx"),
+                line: 0,
+                column: 0,
+                message: "would be rewritten".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn line_column_is_one_based() {
+        assert_eq!(line_column("ab\ncd", 0), (1, 1));
+        assert_eq!(line_column("ab\ncd", 4), (2, 2));
+        assert_eq!(line_column("é\n", 1), (1, 1));
+    }
+
+    #[test]
+    fn github_annotations() {
+        let output = github(&findings(), Path::new("/project"));
+        assert_eq!(
+            output,
+            "::error file=src/a.tsx,line=3,col=5,title=DX0001::expected <div>, found end\n\
+             ::warning file=src/b.tsx,title=DX0007::would be rewritten\n"
+        );
+    }
+
+    #[test]
+    fn junit_fails_files_with_errors() {
+        let xml = junit("dx check", &findings(), Path::new("/project"));
+        assert!(xml.contains("<testsuites name=\"dx check\" tests=\"2\" failures=\"1\">"));
+        assert!(xml.contains(
+            "<failure type=\"DX0001\" message=\"expected &lt;div&gt;, found end\">\
+             [DX0001] src/a.tsx:3:5: expected &lt;div&gt;, found end</failure>"
+        ));
+        assert!(xml.contains("<system-out>[DX0007] src/b.tsx: would be rewritten</system-out>"));
+    }
+
+    #[test]
+    fn junit_without_findings_has_one_passing_case() {
+        let xml = junit("dx check", &[], Path::new("/project"));
+        assert!(xml.contains("tests=\"1\" failures=\"0\""));
+        assert!(xml.contains("<testcase name=\"dx check\" classname=\"dx check\"/>"));
+    }
+
+    #[test]
+    fn sarif_locations() {
+        let log: Value = serde_json::from_str(&sarif(&findings(), Path::new("/project"))).unwrap();
+        let results = &log["runs"][0]["results"];
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/a.tsx");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["startColumn"], 5);
+        assert!(results[1]["locations"][0]["physicalLocation"].get("region").is_none());
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn json_paths_are_relative() {
+        let findings: Value = serde_json::from_str(&json(&findings(), Path::new("/project"))).unwrap();
+        assert_eq!(findings[0]["path"], "src/a.tsx");
+        assert_eq!(findings[1]["level"], "warning");
+    }
+}
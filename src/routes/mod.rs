@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use crate::graph::{reachable, ImportGraph};
+use crate::paths;
+
+pub const MANIFEST_FILE: &str = "dx-routes.json";
+
+/// A Next.js App Router route: its page, the layouts wrapping it and everything they import.
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// The URL pattern, e.g. `/blog/[slug]`. Route groups and parallel-route slots are dropped.
+    pub path: String,
+    pub page: PathBuf,
+    /// Layouts from the root of the app directory down to the page's own directory.
+    pub layouts: Vec<PathBuf>,
+    pub files: Vec<PathBuf>,
+}
+
+impl Route {
+    /// The stylesheet name for this route, e.g. `blog/[slug]` becomes `blog__slug_.css`.
+    pub fn css_file_name(&self) -> String {
+        let trimmed = self.path.trim_matches('/');
+        if trimmed.is_empty() {
+            return "index.css".to_string();
+        }
+        let name: String = trimmed
+            .chars()
+            .map(|c| match c {
+                '/' => '_',
+                c if c.is_ascii_alphanumeric() || c == '-' || c == '.' => c,
+                _ => '_',
+            })
+            .collect();
+        format!("{}.css", name)
+    }
+}
+
+/// The `app` directory of a project whose sources live under `<root>/src`.
+pub fn app_dir(root: &Path) -> Option<PathBuf> {
+    paths::canonicalize(root.join("src").join("app")).ok()
+}
+
+fn is_convention(path: &Path, stem: &str) -> bool {
+    path.file_stem().is_some_and(|s| s == stem)
+}
+
+fn route_path(app_dir: &Path, page_dir: &Path) -> String {
+    let segments: Vec<_> = page_dir
+        .strip_prefix(app_dir)
+        .unwrap_or(page_dir)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(segment) => segment.to_str(),
+            _ => None,
+        })
+        .filter(|segment| !segment.starts_with('(') && !segment.starts_with('@'))
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Every `page` under `app_dir`, with its layout chain and the files reachable from both.
+pub fn routes(app_dir: &Path, files: &[PathBuf], graph: &ImportGraph) -> Vec<Route> {
+    let layouts: HashMap<&Path, &PathBuf> = files
+        .iter()
+        .filter(|path| path.starts_with(app_dir) && is_convention(path, "layout"))
+        .filter_map(|path| Some((path.parent()?, path)))
+        .collect();
+
+    let mut routes: Vec<Route> = files
+        .iter()
+        .filter(|path| path.starts_with(app_dir) && is_convention(path, "page"))
+        .filter_map(|page| {
+            let page_dir = page.parent()?;
+            let mut chain: Vec<PathBuf> = page_dir
+                .ancestors()
+                .take_while(|dir| dir.starts_with(app_dir))
+                .filter_map(|dir| layouts.get(dir).map(|layout| (*layout).clone()))
+                .collect();
+            chain.reverse();
+
+            let mut entries = chain.clone();
+            entries.push(page.clone());
+            Some(Route {
+                path: route_path(app_dir, page_dir),
+                page: page.clone(),
+                files: reachable(graph, &entries),
+                layouts: chain,
+            })
+        })
+        .collect();
+    routes.sort_by(|a, b| a.path.cmp(&b.path));
+    routes
+}
+
+/// Stylesheets in `css_dir` left over from routes that no longer exist.
+pub fn stale_route_styles(css_dir: &Path, routes: &[Route]) -> Vec<PathBuf> {
+    let current: Vec<String> = routes.iter().map(Route::css_file_name).collect();
+    let Ok(entries) = std::fs::read_dir(css_dir) else {
+        return Vec::new();
+    };
+    let mut stale: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "css"))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            !current.iter().any(|file| file == name)
+        })
+        .collect();
+    stale.sort();
+    stale
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    css: String,
+    files: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    version: u32,
+    routes: BTreeMap<String, ManifestEntry>,
+}
+
+fn relative(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+/// Writes the route manifest: each route's stylesheet (relative to `css_dir`) and source files
+/// (relative to `root`), for a layout or plugin to pick the stylesheet per route.
+pub fn write_manifest(output_path: &Path, routes: &[Route], root: &Path, css_dir: &Path) -> std::io::Result<()> {
+    let root = paths::canonicalize(root)?;
+    let manifest = Manifest {
+        version: 1,
+        routes: routes
+            .iter()
+            .map(|route| {
+                let entry = ManifestEntry {
+                    css: relative(&css_dir.join(route.css_file_name()), &root),
+                    files: route.files.iter().map(|file| relative(file, &root)).collect(),
+                };
+                (route.path.clone(), entry)
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(output_path, json)
+}
@@ -0,0 +1,1889 @@
+use glob::{glob, Pattern};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use swc_common::SourceMap;
+
+use crate::assets::{
+    asset_name, hashed_path, read_asset_manifest, write_asset_manifest, AssetManifest, Compression,
+    ASSET_MANIFEST_FILE,
+};
+use crate::cache::{
+    config_fingerprint, content_hash, remote_key, Cache, CacheEntry, RemoteCache, Snapshot, CACHE_FILE, SNAPSHOT_FILE,
+};
+use crate::cascade::Cascade;
+use crate::codes::{CSS_TRANSFORM_FAILED, ID_CONFLICT, WRITE_FAILURE};
+use crate::config::{Config, IdReferences, IdRules, SymlinkPolicy, WatchStrategy};
+use crate::css::{
+    finish_css, merge_duplicate_rules, render_css, render_css_parts, render_css_with_usages, render_scoped_css_parts,
+    scope_hash, transform_stylesheet, CssOptions, CssParts, UsageSites,
+};
+use crate::extract::{
+    collect_from_file, extract_file_source, extract_from_file, group_usages_from_file, read_source,
+    rewrite_id_references, Diagnostic, Extraction, Options,
+};
+use crate::graph::{file_imports, import_graph, reachable, ImportGraph};
+use crate::group::{merge_group_usages, GroupUsage};
+use crate::id::source_id_references;
+use crate::plugin::PluginRegistry;
+use crate::intent::{IntentLog, PlannedWrite};
+use crate::io::{parse_existing_css, read_existing_css, write_file, write_files};
+use crate::lint::{find_conflicts, ConflictFinding};
+use crate::meta::FileMeta;
+use crate::metrics::METRICS;
+use crate::obfuscate::renamed_class;
+use crate::paths;
+use crate::routes::{app_dir, routes, write_manifest, Route, MANIFEST_FILE};
+use crate::stylesheet::{parse_rules, Rule};
+use crate::symbols::SymbolIndex;
+use crate::undo::Journal;
+use crate::utility::sorted_screens;
+
+pub type FileMap = HashMap<PathBuf, (HashSet<String>, HashSet<String>)>;
+
+#[derive(Debug, Clone)]
+pub struct ScanSummary {
+    pub files: usize,
+    pub classnames: usize,
+    pub ids: usize,
+    /// Files that could not be read or parsed.
+    pub parse_failures: usize,
+    /// Files skipped for their size or as binary.
+    pub skipped: usize,
+    /// Sources rewritten with generated ids or expanded groups.
+    pub rewritten: usize,
+    /// Files whose content matched the persistent cache and were not re-parsed.
+    pub cache_hits: usize,
+    /// The part of `cache_hits` served by the remote cache.
+    pub remote_hits: usize,
+    pub up_to_date: bool,
+    /// Restored from the snapshot of the last `dx watch` without extracting anything.
+    pub warm: bool,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangeSummary {
+    pub path: PathBuf,
+    pub source_added: usize,
+    pub source_removed: usize,
+    pub output_added: usize,
+    pub output_removed: usize,
+    /// Selectors added to and removed from the stylesheet, e.g. `.p-4` or `#card`.
+    pub css_added: Vec<String>,
+    pub css_removed: Vec<String>,
+    pub duration: Duration,
+}
+
+/// A source whose rewrite is not idempotent: rewriting the rewritten code changes it again.
+#[derive(Debug, Clone)]
+pub struct UnstableRewrite {
+    pub path: PathBuf,
+    /// One-based line of the first difference between the two passes.
+    pub line: usize,
+    pub first: String,
+    /// The second pass's version of the line, or why the rewritten code could not be extracted.
+    pub second: String,
+}
+
+/// What a build would change, computed without writing anything.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    /// Sources that would be rewritten with generated ids or expanded groups.
+    pub rewrites: Vec<PathBuf>,
+    pub parse_errors: Vec<(PathBuf, Diagnostic)>,
+    /// Ids used by more than one element of a file; they do not make the report unclean.
+    pub id_conflicts: Vec<(PathBuf, Diagnostic)>,
+    /// Selectors the stylesheet is missing or has in excess.
+    pub css_added: Vec<String>,
+    pub css_removed: Vec<String>,
+}
+
+impl CheckReport {
+    pub fn is_clean(&self) -> bool {
+        self.rewrites.is_empty() && self.parse_errors.is_empty() && self.css_added.is_empty() && self.css_removed.is_empty()
+    }
+}
+
+/// One occurrence of a class on an element.
+#[derive(Debug, Clone)]
+pub struct ClassUsage {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub classname: String,
+    /// Byte offsets of the class token, or of the opening tag when the class is not written
+    /// literally (e.g. it comes from a group).
+    pub start: u32,
+    pub end: u32,
+}
+
+/// An element carrying an id.
+#[derive(Debug, Clone)]
+pub struct IdUsage {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// Byte offsets of the element's opening tag.
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Source files no entry point reaches, and the classes only they use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeadCode {
+    pub files: Vec<PathBuf>,
+    pub classnames: Vec<String>,
+}
+
+/// File stems that are entry points by convention: app roots, and Next.js route files under `app/`.
+const ROOT_ENTRY_STEMS: &[&str] = &["main", "index", "App", "_app", "_document"];
+const ROUTE_ENTRY_STEMS: &[&str] = &["page", "layout", "template", "loading", "error", "not-found"];
+
+/// Decides whether a source rewrite is written, given the path, the current and the rewritten code.
+pub type ReviewRewrite = Box<dyn Fn(&Path, &str, &str) -> bool + Send + Sync>;
+
+/// Owns the per-file extraction state of a project and keeps its stylesheet in sync.
+pub struct Scanner {
+    pub root: PathBuf,
+    pub config: Config,
+    pub options: Options,
+    pub css_options: CssOptions,
+    pub output_path: PathBuf,
+    pub file_map: FileMap,
+    pub classnames: HashSet<String>,
+    pub ids: HashSet<String>,
+    pub cache: Cache,
+    pub cache_path: PathBuf,
+    pub remote_cache: Option<Arc<dyn RemoteCache>>,
+    /// Files that currently fail to read or parse, with the first error.
+    pub parse_errors: HashMap<PathBuf, Diagnostic>,
+    /// Content hashes of files in `parse_errors` as of their last attempt, so retries skip
+    /// files that have not changed since.
+    quarantine: HashMap<PathBuf, u64>,
+    /// Sources left out for their size or as binary; see [`Diagnostic::is_skip`].
+    pub skipped: HashMap<PathBuf, Diagnostic>,
+    /// Sources and stylesheets that could not be written, retried on the next change.
+    pub write_errors: HashMap<PathBuf, Diagnostic>,
+    /// Files reachable from the configured `entries`, or `None` when every file counts.
+    pub reachable: Option<HashSet<PathBuf>>,
+    graph: Option<ImportGraph>,
+    /// Overrides from `dx.config.toml` files nested below `src/`.
+    pub cascade: Cascade,
+    /// Symlinks below `src/` with their canonical targets.
+    links: Vec<(PathBuf, PathBuf)>,
+    /// Change events whose file still hashed to its cached content, skipped without parsing.
+    pub unchanged_events: u64,
+    /// Files referencing each id through `htmlFor`, `aria-*` or `href`, built on the first rename.
+    id_references: OnceLock<HashMap<String, HashSet<PathBuf>>>,
+    /// Sees every source rewrite before it is written, e.g. to print a diff; a rejected file is
+    /// left as it is and extracted again on the next scan.
+    pub review_rewrite: Option<ReviewRewrite>,
+    /// Where each class and id is used, kept current on every change once opened with
+    /// [`Scanner::open_symbols`].
+    pub symbols: Option<SymbolIndex>,
+    /// Render every output from the sources alone: the caches and the stylesheet on disk are
+    /// ignored and usage comments leave out files outside the project.
+    pub deterministic: bool,
+    /// Formats every stylesheet is also written in next to it, e.g. `styles.css.br`.
+    pub compression: Vec<Compression>,
+}
+
+enum ScanResult {
+    Cached((HashSet<String>, HashSet<String>)),
+    Remote { entities: (HashSet<String>, HashSet<String>), hash: u64 },
+    Extracted {
+        entities: (HashSet<String>, HashSet<String>),
+        hash: u64,
+        rewrite: Option<String>,
+        renames: HashMap<String, String>,
+    },
+    Failed(Diagnostic),
+    Skipped(Diagnostic),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remote_cache(config: &Config) -> Option<Arc<dyn RemoteCache>> {
+    crate::remote::from_config(&config.cache)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remote_cache(_config: &Config) -> Option<Arc<dyn RemoteCache>> {
+    None
+}
+
+fn unwritable(error: std::io::Error) -> Diagnostic {
+    Diagnostic {
+        code: WRITE_FAILURE,
+        message: format!("could not write file: {}", error),
+        start: 0,
+        end: 0,
+    }
+}
+
+
+impl Scanner {
+    pub fn new(config: Config) -> Self {
+        Scanner::with_registry(config, PluginRegistry::with_builtins())
+    }
+
+    /// Creates a scanner whose config may enable plugins registered on `registry`.
+    pub fn with_registry(config: Config, registry: PluginRegistry) -> Self {
+        Scanner::with_root(config, registry, Path::new("."))
+    }
+
+    /// Creates a scanner for the project at `root`, which holds `src/`, `styles.css` and `.dx/`.
+    pub fn with_root(config: Config, mut registry: PluginRegistry, root: &Path) -> Self {
+        let plugins = registry.resolve(&config.plugins);
+        if !cfg!(feature = "lightningcss") && (!config.css.targets.is_empty() || config.css.minify) {
+            eprintln!(
+                "[{}] css.targets and css.minify need dx built with the `lightningcss` feature; ignoring them",
+                CSS_TRANSFORM_FAILED
+            );
+        }
+        let cache_path = root.join(CACHE_FILE);
+        // Sources are rewritten in place and keep their class names; only the stylesheet (and
+        // bundler transforms, which use `Options` directly) switch to production names.
+        let mut options = Options::with_plugins(&config, &plugins);
+        options.obfuscate = false;
+        Scanner {
+            root: root.to_path_buf(),
+            cache: Cache::load(&cache_path, config_fingerprint(&config)),
+            cache_path,
+            remote_cache: remote_cache(&config),
+            parse_errors: HashMap::new(),
+            quarantine: HashMap::new(),
+            skipped: HashMap::new(),
+            write_errors: HashMap::new(),
+            reachable: None,
+            graph: None,
+            cascade: Cascade::discover(root),
+            links: paths::canonicalize(root.join("src")).map(|src| paths::symlinks(&src)).unwrap_or_default(),
+            unchanged_events: 0,
+            id_references: OnceLock::new(),
+            review_rewrite: None,
+            symbols: None,
+            deterministic: false,
+            compression: Vec::new(),
+            options,
+            css_options: CssOptions {
+                emitters: plugins.emitters,
+                obfuscate: config.features.obfuscate,
+                merge_rules: config.features.merge_rules,
+                order: config.css.order,
+                important_modifier: config.css.important_modifier.clone(),
+                screens: sorted_screens(&config.css.screens),
+                selector_prefix: config.css.selector_prefix.clone(),
+                class_prefix: config.css.class_prefix.clone(),
+                zero_specificity: config.css.zero_specificity,
+                usage_comments: config.css.usage_comments,
+                preflight: config.css.preflight,
+                id_rules: config.css.id_rules,
+                targets: config.css.targets.clone(),
+                minify: config.css.minify,
+            },
+            output_path: root.join("styles.css"),
+            file_map: HashMap::new(),
+            classnames: HashSet::new(),
+            ids: HashSet::new(),
+            config,
+        }
+    }
+
+    /// Every source file the scanner is responsible for, canonicalized, or for files reached
+    /// through a symlink under the `follow` policy, the path through the link.
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let current_dir = env::current_dir().expect("Failed to get current directory");
+        let src = self.root.join("src");
+        let canonical_src = paths::canonicalize(&src).unwrap_or_else(|_| current_dir.join(&src));
+        let pattern = Pattern::escape(&src.to_string_lossy());
+        let mut sources: Vec<PathBuf> = glob(&format!("{}/**/*.{}", pattern, self.options.syntax.extension()))
+            .expect("Failed to read glob pattern")
+            .filter_map(Result::ok)
+            .filter_map(|path| {
+                let linked = canonical_src.join(path.strip_prefix(&src).unwrap_or(&path));
+                let via_link = self.links.iter().any(|(link, _)| linked.starts_with(link));
+                match self.config.symlinks {
+                    SymlinkPolicy::Ignore if via_link => None,
+                    SymlinkPolicy::Follow if via_link => Some(linked),
+                    _ => Some(paths::canonicalize(&path).unwrap_or_else(|_| current_dir.join(path))),
+                }
+            })
+            .filter(|path| self.cascade.includes(path))
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// The directories to watch: `src/` and, unless symlinks are ignored, link targets outside it.
+    pub fn watch_roots(&self) -> Vec<PathBuf> {
+        let src = self.root.join("src");
+        let src = paths::canonicalize(&src).unwrap_or(src);
+        let mut roots = vec![src.clone()];
+        if self.config.symlinks != SymlinkPolicy::Ignore {
+            roots.extend(
+                self.links
+                    .iter()
+                    .filter(|(_, target)| target.is_dir() && !target.starts_with(&src))
+                    .map(|(_, target)| target.clone()),
+            );
+        }
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
+    /// The directories to watch one by one under [`WatchStrategy::Directories`], or `None` to
+    /// watch [`Scanner::watch_roots`] recursively.
+    pub fn watch_dirs(&self) -> Option<Vec<PathBuf>> {
+        if self.config.watch != WatchStrategy::Directories {
+            return None;
+        }
+        let skip = |dir: &Path| paths::is_ignored_dir(dir) || self.cascade.excludes_dir(dir);
+        Some(
+            self.watch_roots()
+                .iter()
+                .flat_map(|root| paths::directories(root, skip))
+                .collect(),
+        )
+    }
+
+    /// The `file_map` keys a changed canonical path stands for: itself, or under the `follow`
+    /// policy, its path through every link pointing at it.
+    pub fn source_aliases(&self, path: &Path) -> Vec<PathBuf> {
+        if self.config.symlinks != SymlinkPolicy::Follow {
+            return vec![path.to_path_buf()];
+        }
+        let src = self.root.join("src");
+        let src = paths::canonicalize(&src).unwrap_or(src);
+        let mut aliases: Vec<PathBuf> = self
+            .links
+            .iter()
+            .filter_map(|(link, target)| Some(link.join(path.strip_prefix(target).ok()?)))
+            .collect();
+        if path.starts_with(&src) {
+            aliases.push(path.to_path_buf());
+        }
+        aliases
+    }
+
+    /// The extraction options for `path`, with any nested config's id trigger applied.
+    pub fn options_for(&self, path: &Path) -> Cow<'_, Options> {
+        let mut options = match self.cascade.id_trigger(path) {
+            Some(trigger) if trigger != self.options.id_trigger => {
+                let mut options = self.options.clone();
+                options.id_trigger = trigger.to_string();
+                Cow::Owned(options)
+            }
+            _ => Cow::Borrowed(&self.options),
+        };
+        if self.config.features.scoped {
+            options.to_mut().scope = Some(self.scope_of(path));
+        }
+        options
+    }
+
+    /// The `data-dx-scope` of the component at `path` under `features.scoped`.
+    pub fn scope_of(&self, path: &Path) -> String {
+        let root = paths::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        let relative = path.strip_prefix(&root).or_else(|_| path.strip_prefix(&self.root)).unwrap_or(path);
+        scope_hash(&relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Each counted file's scope and classes, in path order, for `features.scoped`.
+    fn scoped_parts(&self, ids: &HashSet<String>) -> CssParts {
+        let mut files: Vec<_> = self
+            .file_map
+            .iter()
+            .filter(|(path, _)| self.reachable.as_ref().is_none_or(|scope| scope.contains(*path)))
+            .filter(|(path, _)| self.cascade.output(path).is_none())
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        let scopes: Vec<_> = files.into_iter().map(|(path, (classnames, _))| (self.scope_of(path), classnames)).collect();
+        render_scoped_css_parts(&scopes, ids, &self.css_options)
+    }
+
+    /// Scans every source file, rewriting sources and the stylesheet only if they are stale.
+    pub fn initial_scan(&mut self) -> ScanSummary {
+        self.initial_scan_with_progress(|_, _| {})
+    }
+
+    /// Like [`Scanner::initial_scan`], calling `on_progress(done, total)` as each file is processed.
+    pub fn initial_scan_with_progress(&mut self, on_progress: impl Fn(usize, usize) + Sync) -> ScanSummary {
+        self.scan(None, on_progress)
+    }
+
+    /// Re-extracts only `changed` files and takes every other file from the persistent cache
+    /// without reading it, then patches the stylesheet if needed. Files missing from the cache
+    /// are extracted as usual.
+    pub fn scan_changed(&mut self, changed: &[PathBuf], on_progress: impl Fn(usize, usize) + Sync) -> ScanSummary {
+        let changed: HashSet<PathBuf> = changed.iter().map(|path| paths::normalize(path)).collect();
+        self.scan(Some(&changed), on_progress)
+    }
+
+    fn scan(&mut self, changed: Option<&HashSet<PathBuf>>, on_progress: impl Fn(usize, usize) + Sync) -> ScanSummary {
+        let start = Instant::now();
+        let cm: Arc<SourceMap> = Default::default();
+        self.id_references = OnceLock::new();
+
+        let (mut existing_classnames, mut existing_ids) = (HashSet::new(), HashSet::new());
+        for output in self.stylesheet_outputs() {
+            let (classnames, ids) = read_existing_css(&output);
+            existing_classnames.extend(classnames);
+            existing_ids.extend(ids);
+        }
+        let paths = self.source_paths();
+        let total = paths.len();
+        let done = AtomicUsize::new(0);
+
+        let results: Vec<_> = paths
+            .par_iter()
+            .map(|path| {
+                let trusted = changed
+                    .filter(|changed| !changed.contains(path))
+                    .and_then(|_| self.cache.get_unchecked(path));
+                let result = match trusted {
+                    Some(entities) => ScanResult::Cached(entities),
+                    None => self.scan_file(path, &cm),
+                };
+                on_progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+                (path.clone(), result)
+            })
+            .collect();
+
+        let mut cache_hits = 0;
+        let mut remote_hits = 0;
+        let mut parse_failures = 0;
+        let mut skipped = 0;
+        let mut pending_rewrites = Vec::new();
+        let mut id_renames = Vec::new();
+        let mut file_map = HashMap::new();
+        self.parse_errors.clear();
+        self.skipped.clear();
+        for (path, result) in results {
+            match result {
+                ScanResult::Cached(entities) => {
+                    cache_hits += 1;
+                    file_map.insert(path, entities);
+                }
+                ScanResult::Remote { entities, hash } => {
+                    cache_hits += 1;
+                    remote_hits += 1;
+                    self.cache.insert(path.clone(), hash, &entities.0, &entities.1);
+                    file_map.insert(path, entities);
+                }
+                ScanResult::Extracted { entities, hash, rewrite, renames } => {
+                    if !renames.is_empty() {
+                        id_renames.push((path.clone(), renames));
+                    }
+                    match rewrite {
+                        Some(code) => pending_rewrites.push((path.clone(), code)),
+                        None => self.cache.insert(path.clone(), hash, &entities.0, &entities.1),
+                    }
+                    file_map.insert(path, entities);
+                }
+                ScanResult::Failed(diagnostic) => {
+                    parse_failures += 1;
+                    METRICS.parse_failures.fetch_add(1, Ordering::Relaxed);
+                    // Keep the last good extraction until the file parses again or is deleted,
+                    // so a half-typed edit does not shrink the stylesheet.
+                    let last_good = self.file_map.get(&path).cloned().or_else(|| self.cache.get_unchecked(&path));
+                    if let Some(entities) = last_good {
+                        file_map.insert(path.clone(), entities);
+                    }
+                    self.parse_errors.insert(path, diagnostic);
+                }
+                ScanResult::Skipped(diagnostic) => {
+                    skipped += 1;
+                    self.skipped.insert(path, diagnostic);
+                }
+            }
+        }
+
+        self.file_map = file_map;
+        let reference_rewrites = self.reference_rewrites(&id_renames, &pending_rewrites);
+        for (path, code) in &reference_rewrites {
+            match pending_rewrites.iter_mut().find(|(pending, _)| pending == path) {
+                Some(pending) => pending.1 = code.clone(),
+                None => pending_rewrites.push((path.clone(), code.clone())),
+            }
+        }
+        self.cache.retain(|path| self.file_map.contains_key(path));
+        self.refresh_reachability();
+        let (classnames, ids) = self.globals(&self.file_map);
+        let up_to_date = !self.deterministic
+            && self.rendered_classnames(&classnames) == existing_classnames
+            && self.rendered_ids(&ids) == existing_ids;
+
+        if !up_to_date && self.review_rewrite.is_some() {
+            if reference_rewrites.is_empty() {
+                pending_rewrites.retain(|file| self.approved(std::slice::from_ref(file)));
+            } else if !self.approved(&pending_rewrites) {
+                pending_rewrites.clear();
+            }
+        }
+        let rewritten = if up_to_date { 0 } else { pending_rewrites.len() };
+        if !up_to_date {
+            self.journal(&pending_rewrites);
+            self.begin_intent(&pending_rewrites);
+            // Renamed ids and the references to them in other files land together or not at all.
+            let batch = !reference_rewrites.is_empty() && self.write_batch(&pending_rewrites);
+            for (path, code) in pending_rewrites {
+                let written = if reference_rewrites.is_empty() { self.write(&path, &code) } else { batch };
+                if !written {
+                    continue;
+                }
+                let (file_classnames, file_ids) = &self.file_map[&path];
+                let hash = content_hash(code.as_bytes());
+                self.upload(hash, file_classnames, file_ids);
+                self.cache.insert(path.clone(), hash, file_classnames, file_ids);
+            }
+            self.write_stylesheet(&classnames, &ids);
+            self.end_intent();
+        } else {
+            // Copies are still missing when `css.hashed_filenames` or compression was just turned on.
+            self.write_stylesheet_copies();
+        }
+        self.classnames = classnames;
+        self.ids = ids;
+        self.id_references = OnceLock::new();
+        self.write_cascade_stylesheets();
+        self.save_cache();
+        METRICS.scan_duration.observe(start.elapsed());
+
+        ScanSummary {
+            files: total,
+            classnames: self.classnames.len(),
+            ids: self.ids.len(),
+            parse_failures,
+            skipped,
+            rewritten,
+            cache_hits,
+            remote_hits,
+            up_to_date,
+            warm: false,
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Checks `sources` (path and content, e.g. as staged in git) against `existing_css`, using the
+    /// index for every other file. Call [`Scanner::index`] first.
+    pub fn check(&self, sources: Vec<(PathBuf, String)>, existing_css: &str) -> CheckReport {
+        let cm: Arc<SourceMap> = Default::default();
+        let mut file_map = self.file_map.clone();
+        let mut report = CheckReport::default();
+
+        let results: Vec<_> = sources
+            .into_par_iter()
+            .map(|(path, source)| {
+                let result = self.extract_source(&path, source, &cm);
+                (path, result)
+            })
+            .collect();
+        for (path, result) in results {
+            match result {
+                Ok(extraction) => {
+                    if extraction.is_modified() {
+                        report.rewrites.push(path.clone());
+                    }
+                    for diagnostic in &extraction.diagnostics {
+                        if diagnostic.code == ID_CONFLICT {
+                            report.id_conflicts.push((path.clone(), diagnostic.clone()));
+                        }
+                    }
+                    file_map.insert(path, (extraction.classnames, extraction.ids));
+                }
+                Err(diagnostic) => report.parse_errors.push((path, diagnostic)),
+            }
+        }
+        report.rewrites.sort();
+        report.parse_errors.sort_by(|a, b| a.0.cmp(&b.0));
+        report.id_conflicts.sort_by(|a, b| (&a.0, a.1.start).cmp(&(&b.0, b.1.start)));
+
+        let (classnames, ids) = self.globals(&file_map);
+        let classnames = self.rendered_classnames(&classnames);
+        let ids = self.rendered_ids(&ids);
+        let (existing_classnames, existing_ids) = parse_existing_css(existing_css);
+        report.css_added = selector_diff(&classnames, &existing_classnames, &ids, &existing_ids);
+        report.css_removed = selector_diff(&existing_classnames, &classnames, &existing_ids, &ids);
+        report
+    }
+
+    /// Runs the rewrite pipeline twice over every source without writing anything and returns
+    /// the files whose second pass still changes them, sorted by path.
+    pub fn unstable_rewrites(&self) -> Vec<UnstableRewrite> {
+        let cm: Arc<SourceMap> = Default::default();
+        let mut unstable: Vec<_> = self
+            .source_paths()
+            .par_iter()
+            .filter_map(|path| {
+                let options = self.options_for(path);
+                let source = read_source(path, options.max_file_size).ok()?;
+                let first = extract_file_source(path, source, &cm, &options).ok()?;
+                if !first.is_modified() {
+                    return None;
+                }
+                let (line, second) = match extract_file_source(path, first.code.clone(), &cm, &options) {
+                    Ok(second) if !second.is_modified() => return None,
+                    Ok(second) => {
+                        let line = first.code.lines().zip(second.code.lines()).take_while(|(a, b)| a == b).count();
+                        (line, second.code.lines().nth(line).unwrap_or_default().to_string())
+                    }
+                    Err(diagnostic) => (0, format!("rewritten code does not parse: {}", diagnostic.message)),
+                };
+                Some(UnstableRewrite {
+                    path: path.clone(),
+                    line: line + 1,
+                    first: first.code.lines().nth(line).unwrap_or_default().to_string(),
+                    second,
+                })
+            })
+            .collect();
+        unstable.sort_by(|a, b| a.path.cmp(&b.path));
+        unstable
+    }
+
+    /// `classnames` as they appear in the stylesheet; markers such as `group` (and classes
+    /// without the class prefix) get no rule.
+    fn rendered_classnames(&self, classnames: &HashSet<String>) -> HashSet<String> {
+        classnames
+            .iter()
+            .filter(|c| self.css_options.utility_class(c).is_some())
+            .map(|c| self.css_options.class_name(c).into_owned())
+            .collect()
+    }
+
+    /// `ids` as they appear in the stylesheet: none when `css.id_rules` is off.
+    fn rendered_ids(&self, ids: &HashSet<String>) -> HashSet<String> {
+        if self.css_options.id_rules == IdRules::None {
+            return HashSet::new();
+        }
+        ids.clone()
+    }
+
+    fn scan_file(&self, path: &Path, cm: &Arc<SourceMap>) -> ScanResult {
+        let source = match read_source(path, self.options.max_file_size) {
+            Ok(source) => source,
+            Err(diagnostic) if diagnostic.is_skip() => return ScanResult::Skipped(diagnostic),
+            Err(diagnostic) => return ScanResult::Failed(diagnostic),
+        };
+        let hash = content_hash(source.as_bytes());
+        if let Some(entities) = self.cache.get(path, hash).filter(|_| !self.deterministic) {
+            return ScanResult::Cached(entities);
+        }
+        let key = remote_key(self.cache.fingerprint(), hash);
+        let remote_cache = self.remote_cache.as_ref().filter(|_| !self.deterministic);
+        if let Some(entry) = remote_cache.and_then(|remote| remote.get(&key)) {
+            return ScanResult::Remote {
+                entities: entry.entities(),
+                hash,
+            };
+        }
+        METRICS.files_processed.fetch_add(1, Ordering::Relaxed);
+        match self.extract_source(path, source, cm) {
+            Ok(extraction) => {
+                let renames = extraction.id_renames();
+                let rewrite = extraction.is_modified().then_some(extraction.code);
+                if rewrite.is_none() {
+                    self.upload(hash, &extraction.classnames, &extraction.ids);
+                }
+                ScanResult::Extracted {
+                    rewrite,
+                    renames,
+                    entities: (extraction.classnames, extraction.ids),
+                    hash,
+                }
+            }
+            Err(diagnostic) => ScanResult::Failed(diagnostic),
+        }
+    }
+
+    /// Extracts `source`, keeping any id that another file still references instead of renaming it.
+    fn extract_source(&self, path: &Path, source: String, cm: &Arc<SourceMap>) -> Result<Extraction, Diagnostic> {
+        let options = self.options_for(path);
+        let extraction = extract_file_source(path, source.clone(), cm, &options)?;
+        if options.id_references == IdReferences::Project {
+            return Ok(extraction);
+        }
+        let pinned: HashSet<String> =
+            extraction.id_renames().into_keys().filter(|previous| self.referenced_elsewhere(previous, path)).collect();
+        if pinned.is_empty() {
+            return Ok(extraction);
+        }
+        let mut options = options.into_owned();
+        options.pinned_ids = Arc::new(pinned);
+        extract_file_source(path, source, cm, &options)
+    }
+
+    /// Extracts `source` as unsaved content of `path` the way a scan would, without indexing it.
+    pub fn preview(&self, path: &Path, source: &str) -> Result<Extraction, Diagnostic> {
+        let cm: Arc<SourceMap> = Default::default();
+        self.extract_source(path, source.to_string(), &cm)
+    }
+
+    /// Whether a source other than `path` references `id`.
+    fn referenced_elsewhere(&self, id: &str, path: &Path) -> bool {
+        self.files_referencing(id).any(|file| file != path)
+    }
+
+    fn files_referencing(&self, id: &str) -> impl Iterator<Item = &PathBuf> {
+        let index = self.id_references.get_or_init(|| {
+            let references: Vec<_> = self
+                .source_paths()
+                .into_par_iter()
+                .filter_map(|path| Some((source_id_references(&std::fs::read_to_string(&path).ok()?), path)))
+                .collect();
+            let mut index: HashMap<String, HashSet<PathBuf>> = HashMap::new();
+            for (ids, path) in references {
+                for id in ids {
+                    index.entry(id).or_default().insert(path.clone());
+                }
+            }
+            index
+        });
+        index.get(id).into_iter().flatten()
+    }
+
+    /// Records the ids `source` references, if the reference index has been built.
+    fn reindex_references(&mut self, path: &Path, source: &str) {
+        let Some(index) = self.id_references.get_mut() else {
+            return;
+        };
+        for files in index.values_mut() {
+            files.remove(path);
+        }
+        for id in source_id_references(source) {
+            index.entry(id).or_default().insert(path.to_path_buf());
+        }
+    }
+
+    /// With `id_references = "project"`, every other indexed source that references an id renamed
+    /// in `id_renames`, rewritten to the new ids. A source about to be written is rewritten from
+    /// its code in `pending` rather than from disk.
+    fn reference_rewrites(
+        &self,
+        id_renames: &[(PathBuf, HashMap<String, String>)],
+        pending: &[(PathBuf, String)],
+    ) -> Vec<(PathBuf, String)> {
+        if self.options.id_references != IdReferences::Project {
+            return Vec::new();
+        }
+        let mut targets: BTreeMap<PathBuf, HashMap<String, String>> = BTreeMap::new();
+        for (renamed_in, renames) in id_renames {
+            for (previous, id) in renames {
+                for file in self.files_referencing(previous).filter(|file| *file != renamed_in) {
+                    targets.entry(file.clone()).or_default().insert(previous.clone(), id.clone());
+                }
+            }
+        }
+        let cm: Arc<SourceMap> = Default::default();
+        targets
+            .into_par_iter()
+            .filter(|(path, _)| self.file_map.contains_key(path))
+            .filter_map(|(path, renames)| {
+                let source = match pending.iter().find(|(pending, _)| *pending == path) {
+                    Some((_, code)) => code.clone(),
+                    None => std::fs::read_to_string(&path).ok()?,
+                };
+                let code = rewrite_id_references(&path, source, &cm, &self.options_for(&path), &renames).ok()??;
+                Some((path, code))
+            })
+            .collect()
+    }
+
+    /// Writes `files` with [`write_files`], recording a failure against each of them.
+    fn write_batch(&mut self, files: &[(PathBuf, String)]) -> bool {
+        match write_files(files) {
+            Ok(()) => {
+                for (path, _) in files {
+                    self.write_errors.remove(path);
+                }
+                true
+            }
+            Err(error) => {
+                let diagnostic = unwritable(error);
+                for (path, _) in files {
+                    self.write_errors.insert(path.clone(), diagnostic.clone());
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether `review_rewrite`, if any, lets every file in `files` be rewritten.
+    fn approved(&self, files: &[(PathBuf, String)]) -> bool {
+        let Some(review) = &self.review_rewrite else {
+            return true;
+        };
+        files.iter().all(|(path, code)| {
+            let current = std::fs::read_to_string(path).unwrap_or_default();
+            review(path, &current, code)
+        })
+    }
+
+    /// Saves the current content of the sources in `files` to the undo journal before they are
+    /// rewritten. A failure to save does not hold the rewrite back.
+    fn journal(&self, files: &[(PathBuf, String)]) {
+        let paths: Vec<&Path> = files.iter().map(|(path, _)| path.as_path()).collect();
+        let _ = Journal::new(&self.root).record(&paths);
+    }
+
+    /// Records the sources in `files` and the stylesheets in the intent log before writing them.
+    fn begin_intent(&self, files: &[(PathBuf, String)]) {
+        let sources = files.iter().map(|(path, code)| PlannedWrite {
+            path: path.clone(),
+            hash: Some(content_hash(code.as_bytes())),
+        });
+        let stylesheets = self.stylesheet_outputs().into_iter().map(|path| PlannedWrite { path, hash: None });
+        let _ = IntentLog::new(&self.root).begin(sources.chain(stylesheets).collect());
+    }
+
+    /// Clears the intent log once a batch was written; after a failed write it stays, so the
+    /// next start reports the files that may disagree.
+    fn end_intent(&self) {
+        if self.write_errors.is_empty() {
+            let _ = IntentLog::new(&self.root).commit();
+        }
+    }
+
+    /// Shares a stable (not about to be rewritten) file's results with the remote cache.
+    fn upload(&self, hash: u64, classnames: &HashSet<String>, ids: &HashSet<String>) {
+        if let Some(remote) = &self.remote_cache {
+            remote.put(&remote_key(self.cache.fingerprint(), hash), &CacheEntry::new(hash, classnames, ids));
+        }
+    }
+
+    /// Writes `content` to `path`, recording a failure in `write_errors` instead of aborting.
+    fn write(&mut self, path: &Path, content: &str) -> bool {
+        match write_file(path, content) {
+            Ok(()) => {
+                self.write_errors.remove(path);
+                true
+            }
+            Err(error) => {
+                self.write_errors.insert(path.to_path_buf(), unwritable(error));
+                false
+            }
+        }
+    }
+
+    /// Renders `classnames` and `ids`, with usage comments when `css.usage_comments` is set.
+    fn render(&self, classnames: &HashSet<String>, ids: &HashSet<String>) -> String {
+        if self.css_options.usage_comments == 0 {
+            return render_css(classnames, ids, &self.css_options);
+        }
+        render_css_with_usages(classnames, ids, &self.css_options, &self.usage_sites())
+    }
+
+    /// The main stylesheet: [`Scanner::render`], or each component's classes under its scope with
+    /// `features.scoped`.
+    fn render_stylesheet(&self, classnames: &HashSet<String>, ids: &HashSet<String>) -> String {
+        if !self.config.features.scoped {
+            return self.render(classnames, ids);
+        }
+        let parts = self.scoped_parts(ids);
+        finish_css([parts.base, parts.utilities, parts.ids].concat(), classnames, ids, &self.css_options, false)
+    }
+
+    /// Every class's usage sites as `path:line`, relative to the project root.
+    pub fn usage_sites(&self) -> UsageSites {
+        let root = paths::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        let mut sites = UsageSites::new();
+        for usage in self.class_usages(|_| true) {
+            let path = match usage.path.strip_prefix(&root) {
+                Ok(path) => path,
+                // E.g. a symlink target; its absolute path differs from machine to machine.
+                Err(_) if self.deterministic => continue,
+                Err(_) => &usage.path,
+            };
+            sites
+                .entry(usage.classname)
+                .or_default()
+                .push(format!("{}:{}", path.to_string_lossy().replace('\\', "/"), usage.line));
+        }
+        sites
+    }
+
+    /// The main stylesheet followed by the distinct files of `css.split`.
+    pub fn stylesheet_outputs(&self) -> Vec<PathBuf> {
+        let split = &self.config.css.split;
+        let mut outputs = vec![self.output_path.clone()];
+        for route in [&split.base, &split.utilities, &split.ids].into_iter().flatten() {
+            let path = self.root.join(route);
+            if !outputs.contains(&path) {
+                outputs.push(path);
+            }
+        }
+        outputs
+    }
+
+    fn write_stylesheet(&mut self, classnames: &HashSet<String>, ids: &HashSet<String>) {
+        let start = Instant::now();
+        if self.stylesheet_outputs().len() > 1 {
+            self.write_split_stylesheets(classnames, ids);
+            METRICS.css_write_duration.observe(start.elapsed());
+        } else {
+            let css = self.render_stylesheet(classnames, ids);
+            let output_path = self.output_path.clone();
+            if self.write(&output_path, &css) {
+                METRICS.css_writes.fetch_add(1, Ordering::Relaxed);
+                METRICS.css_write_duration.observe(start.elapsed());
+            }
+        }
+        self.write_stylesheet_copies();
+    }
+
+    /// The content-addressed and compressed copies of the stylesheet outputs, as configured.
+    fn write_stylesheet_copies(&mut self) {
+        self.write_hashed_stylesheets();
+        let mut stylesheets = self.stylesheet_outputs();
+        if self.config.css.hashed_filenames {
+            let manifest = read_asset_manifest(&self.root.join(ASSET_MANIFEST_FILE));
+            stylesheets.extend(manifest.into_values().map(|name| self.root.join(name)));
+        }
+        self.write_compressed(&stylesheets);
+    }
+
+    /// Writes a copy of each of `stylesheets` per `compression` format next to it, unless the copy
+    /// is already newer than the stylesheet.
+    fn write_compressed(&mut self, stylesheets: &[PathBuf]) {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        for compression in self.compression.clone() {
+            for path in stylesheets {
+                let compressed_path = compression.path(path);
+                if modified(&compressed_path).is_some_and(|compressed| Some(compressed) > modified(path)) {
+                    continue;
+                }
+                let result = std::fs::read(path)
+                    .and_then(|css| compression.compress(&css))
+                    .and_then(|compressed| std::fs::write(&compressed_path, compressed));
+                match result {
+                    Ok(()) => {
+                        self.write_errors.remove(&compressed_path);
+                    }
+                    Err(error) => {
+                        self.write_errors.insert(compressed_path, unwritable(error));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Under `css.hashed_filenames`, copies every stylesheet output to its content-addressed name
+    /// and lists the copies in `dx-assets.json`, removing the copies they replace.
+    fn write_hashed_stylesheets(&mut self) {
+        if !self.config.css.hashed_filenames {
+            return;
+        }
+        let manifest_path = self.root.join(ASSET_MANIFEST_FILE);
+        let previous = read_asset_manifest(&manifest_path);
+        let mut manifest = AssetManifest::new();
+        for output in self.stylesheet_outputs() {
+            let Ok(css) = std::fs::read_to_string(&output) else {
+                continue;
+            };
+            let hashed = hashed_path(&output, &css);
+            if hashed.exists() || self.write(&hashed, &css) {
+                manifest.insert(asset_name(&output, &self.root), asset_name(&hashed, &self.root));
+            }
+        }
+        if manifest == previous {
+            return;
+        }
+        for stale in previous.values().filter(|name| !manifest.values().any(|current| current == *name)) {
+            let stale = self.root.join(stale);
+            for compression in Compression::ALL {
+                let _ = std::fs::remove_file(compression.path(&stale));
+            }
+            let _ = std::fs::remove_file(stale);
+        }
+        if let Err(error) = write_asset_manifest(&manifest_path, &manifest) {
+            self.write_errors.insert(manifest_path, unwritable(error));
+        }
+    }
+
+    /// Writes each category of rules to its `css.split` file, the rest to the main stylesheet.
+    /// Emitters run over the file that receives the utilities; unchanged files are not rewritten.
+    fn write_split_stylesheets(&mut self, classnames: &HashSet<String>, ids: &HashSet<String>) {
+        let usages = if self.css_options.usage_comments > 0 { self.usage_sites() } else { UsageSites::new() };
+        let parts = match self.config.features.scoped {
+            true => self.scoped_parts(ids),
+            false => render_css_parts(classnames, ids, &self.css_options, &usages),
+        };
+        let split = &self.config.css.split;
+        let route = |route: &Option<String>| route.as_ref().map_or(self.output_path.clone(), |r| self.root.join(r));
+        let categories = [
+            (route(&split.base), parts.base),
+            (route(&split.utilities), parts.utilities),
+            (route(&split.ids), parts.ids),
+        ];
+        let utilities_output = categories[1].0.clone();
+
+        for output in self.stylesheet_outputs() {
+            let css: String =
+                categories.iter().filter(|(path, _)| *path == output).map(|(_, css)| css.as_str()).collect();
+            let css = if output == utilities_output {
+                finish_css(css, classnames, ids, &self.css_options, !usages.is_empty())
+            } else if self.css_options.merge_rules {
+                transform_stylesheet(merge_duplicate_rules(&css), &self.css_options)
+            } else {
+                transform_stylesheet(css, &self.css_options)
+            };
+            let unchanged =
+                output != self.output_path && std::fs::read_to_string(&output).ok().as_deref() == Some(css.as_str());
+            if !unchanged && self.write(&output, &css) {
+                METRICS.css_writes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Rewrites the stylesheets of nested configs that set `output`, skipping unchanged ones.
+    fn write_cascade_stylesheets(&mut self) {
+        for output in self.cascade.outputs() {
+            let (classnames, ids) = self.globals_where(&self.file_map, |path| self.cascade.output(path).as_ref() == Some(&output));
+            let css = self.render(&classnames, &ids);
+            if std::fs::read_to_string(&output).ok().as_deref() != Some(css.as_str()) && self.write(&output, &css) {
+                METRICS.css_writes.fetch_add(1, Ordering::Relaxed);
+                self.write_compressed(std::slice::from_ref(&output));
+            }
+        }
+    }
+
+    /// Retries every write that failed before; sources are re-extracted and rewritten, stylesheets
+    /// re-rendered from the current sets.
+    pub fn retry_failed_writes(&mut self) {
+        let failed = std::mem::take(&mut self.write_errors);
+        let mut stylesheets = false;
+        for path in failed.into_keys() {
+            if self.file_map.contains_key(&path) {
+                self.process_change(&path);
+            } else {
+                stylesheets = true;
+            }
+        }
+        if stylesheets {
+            let (classnames, ids) = (self.classnames.clone(), self.ids.clone());
+            self.write_stylesheet(&classnames, &ids);
+            self.write_cascade_stylesheets();
+        }
+    }
+
+    /// Moves the persistent cache to `cache_path`, e.g. a directory shared by several packages.
+    pub fn set_cache_path(&mut self, cache_path: PathBuf) {
+        self.cache = Cache::load(&cache_path, config_fingerprint(&self.config));
+        self.cache_path = cache_path;
+    }
+
+    /// Persists the extraction cache; failures only cost a cold start next time.
+    pub fn save_cache(&self) {
+        let _ = self.cache.save(&self.cache_path);
+    }
+
+    /// Persists the file map and global sets for [`Scanner::warm_start`]. Files that fail to parse
+    /// are left out, so the next start re-reads them and reports the failure again.
+    pub fn save_snapshot(&self) -> std::io::Result<()> {
+        let stylesheet_hash = content_hash(&std::fs::read(&self.output_path)?);
+        let files = self
+            .file_map
+            .iter()
+            .filter(|(path, _)| !self.parse_errors.contains_key(*path))
+            .filter_map(|(path, (classnames, ids))| {
+                Some((path.clone(), CacheEntry::new(self.cache.hash(path)?, classnames, ids)))
+            })
+            .collect();
+        let snapshot = Snapshot::new(self.cache.fingerprint(), stylesheet_hash, files, &self.classnames, &self.ids);
+        snapshot.save(&self.root.join(SNAPSHOT_FILE))
+    }
+
+    /// Restores the state saved by [`Scanner::save_snapshot`] when every source still hashes to
+    /// its snapshot entry and the stylesheet is untouched, skipping extraction and the stylesheet
+    /// rebuild. Otherwise only the sources that changed are re-extracted, or everything without a
+    /// usable snapshot.
+    pub fn warm_start(&mut self, on_progress: impl Fn(usize, usize) + Sync) -> ScanSummary {
+        let start = Instant::now();
+        let snapshot = Snapshot::load(&self.root.join(SNAPSHOT_FILE), self.cache.fingerprint())
+            .filter(|snapshot| {
+                std::fs::read(&self.output_path).is_ok_and(|css| content_hash(&css) == snapshot.stylesheet_hash)
+            });
+        let Some(snapshot) = snapshot else {
+            return self.initial_scan_with_progress(on_progress);
+        };
+
+        let sources = self.source_paths();
+        let total = sources.len();
+        let done = AtomicUsize::new(0);
+        let stale: Vec<PathBuf> = sources
+            .par_iter()
+            .filter(|path| {
+                let hash = std::fs::read(path).ok().map(|bytes| content_hash(&bytes));
+                on_progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+                snapshot.files.get(*path).map(|entry| entry.hash) != hash || hash.is_none()
+            })
+            .cloned()
+            .collect();
+        let removed = snapshot.files.len() + stale.len() != total;
+        if !stale.is_empty() || removed {
+            return self.scan_changed(&stale, on_progress);
+        }
+
+        self.file_map = snapshot.files.iter().map(|(path, entry)| (path.clone(), entry.entities())).collect();
+        self.parse_errors.clear();
+        self.skipped.clear();
+        self.refresh_reachability();
+        self.classnames = snapshot.classnames.into_iter().collect();
+        self.ids = snapshot.ids.into_iter().collect();
+        METRICS.scan_duration.observe(start.elapsed());
+
+        ScanSummary {
+            files: total,
+            classnames: self.classnames.len(),
+            ids: self.ids.len(),
+            parse_failures: 0,
+            skipped: 0,
+            rewritten: 0,
+            cache_hits: total,
+            remote_hits: 0,
+            up_to_date: true,
+            warm: true,
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Whether `source` is what `path` held when it was last extracted, e.g. after a touch or an
+    /// event for dx's own rewrite, so there is nothing to redo.
+    fn is_unchanged(&self, path: &Path, source: &str) -> bool {
+        self.file_map.contains_key(path)
+            && !self.parse_errors.contains_key(path)
+            && !self.skipped.contains_key(path)
+            && self.cache.hash(path) == Some(content_hash(source.as_bytes()))
+    }
+
+    /// Re-extracts a single changed (or deleted) file and patches the stylesheet.
+    ///
+    /// Returns a summary only when the stylesheet was rewritten because of an edit.
+    pub fn process_change(&mut self, path: &Path) -> Option<ChangeSummary> {
+        let start = Instant::now();
+        let cm: Arc<SourceMap> = Default::default();
+        let path = &self.known_path(path);
+        if !self.write_errors.is_empty() && !self.write_errors.contains_key(path) {
+            self.retry_failed_writes();
+        }
+
+        let (old_file_classnames, old_file_ids) = self.file_map.get(path).cloned().unwrap_or_default();
+
+        if !path.exists() {
+            self.skipped.remove(path);
+            self.forget(path);
+            return None;
+        }
+
+        let source = read_source(path, self.options.max_file_size);
+        if source.as_ref().is_ok_and(|source| self.is_unchanged(path, source)) {
+            self.unchanged_events += 1;
+            return None;
+        }
+
+        METRICS.files_processed.fetch_add(1, Ordering::Relaxed);
+        if let Ok(source) = &source {
+            self.reindex_references(path, source);
+        }
+        let extraction = match source.and_then(|source| self.extract_source(path, source, &cm)) {
+            Ok(extraction) => {
+                self.parse_errors.remove(path);
+                self.skipped.remove(path);
+                extraction
+            }
+            Err(diagnostic) if diagnostic.is_skip() => {
+                self.skipped.insert(path.to_path_buf(), diagnostic);
+                self.forget(path);
+                return None;
+            }
+            // The file's last good extraction stays in `file_map` until it parses again.
+            Err(diagnostic) => {
+                METRICS.parse_failures.fetch_add(1, Ordering::Relaxed);
+                self.parse_errors.insert(path.to_path_buf(), diagnostic);
+                return None;
+            }
+        };
+
+        let code_was_modified = extraction.is_modified();
+        let data_was_modified =
+            extraction.classnames != old_file_classnames || extraction.ids != old_file_ids;
+        let new_file_classnames = extraction.classnames.clone();
+        self.file_map.insert(path.to_path_buf(), (extraction.classnames.clone(), extraction.ids.clone()));
+        // Imports may have changed even when the file's classes did not.
+        let reachability_changed = self.update_reachability(path);
+
+        if !code_was_modified && !data_was_modified && !reachability_changed {
+            // The content still changed (say, a comment), so the next identical event short-circuits.
+            let hash = content_hash(extraction.source.as_bytes());
+            self.cache.insert(path.to_path_buf(), hash, &extraction.classnames, &extraction.ids);
+            self.save_cache();
+            return None;
+        }
+
+        let reference_rewrites = self.reference_rewrites(&[(path.to_path_buf(), extraction.id_renames())], &[]);
+        let mut batch = reference_rewrites.clone();
+        batch.push((path.to_path_buf(), extraction.code.clone()));
+        let approved = code_was_modified && self.approved(&batch);
+        if approved {
+            self.journal(&batch);
+            self.begin_intent(&batch);
+        }
+        let written = match code_was_modified {
+            false => true,
+            true if !approved => false,
+            true if reference_rewrites.is_empty() => self.write(path, &extraction.code),
+            true => self.write_batch(&batch),
+        };
+        // A source that could not be rewritten stays out of the cache, so it is extracted again.
+        if written {
+            let written = if code_was_modified { &extraction.code } else { &extraction.source };
+            self.cache
+                .insert(path.to_path_buf(), content_hash(written.as_bytes()), &extraction.classnames, &extraction.ids);
+            for (file, code) in &reference_rewrites {
+                if let Some((classnames, ids)) = self.file_map.get(file) {
+                    self.cache.insert(file.clone(), content_hash(code.as_bytes()), classnames, ids);
+                }
+            }
+            for (file, code) in &batch {
+                self.reindex_references(file, code);
+            }
+            self.save_cache();
+        }
+        if self.cascade.output(path).is_some() {
+            self.write_cascade_stylesheets();
+        }
+
+        let (new_global_classnames, new_global_ids) = self.globals(&self.file_map);
+
+        let globals_did_change = new_global_classnames != self.classnames || new_global_ids != self.ids;
+
+        if !globals_did_change {
+            if approved {
+                self.end_intent();
+            }
+            return None;
+        }
+
+        let source_added = new_file_classnames.difference(&old_file_classnames).count();
+        let source_removed = old_file_classnames.difference(&new_file_classnames).count();
+
+        let css_added = selector_diff(&new_global_classnames, &self.classnames, &new_global_ids, &self.ids);
+        let css_removed = selector_diff(&self.classnames, &new_global_classnames, &self.ids, &new_global_ids);
+        let output_added = css_added.len();
+        let output_removed = css_removed.len();
+
+        self.write_stylesheet(&new_global_classnames, &new_global_ids);
+        if approved {
+            self.end_intent();
+        }
+        self.classnames = new_global_classnames;
+        self.ids = new_global_ids;
+
+        Some(ChangeSummary {
+            path: path.to_path_buf(),
+            source_added,
+            source_removed,
+            output_added,
+            output_removed,
+            css_added,
+            css_removed,
+            duration: start.elapsed(),
+        })
+    }
+
+    /// The `file_map` key for `path` on case-insensitive filesystems, where an event may spell a
+    /// file differently from its key. A key left behind by a case-only rename (`button.tsx` to
+    /// `Button.tsx`) is dropped so its classes do not linger.
+    fn known_path(&mut self, path: &Path) -> PathBuf {
+        if !paths::CASE_INSENSITIVE || self.file_map.contains_key(path) {
+            return path.to_path_buf();
+        }
+        let Some(known) = self.file_map.keys().find(|known| paths::same_path(known, path)).cloned() else {
+            return path.to_path_buf();
+        };
+        if !path.exists() {
+            return known;
+        }
+        self.file_map.remove(&known);
+        self.parse_errors.remove(&known);
+        self.cache.remove(&known);
+        path.to_path_buf()
+    }
+
+    /// Files that failed to parse and changed (or disappeared) since they were last tried, e.g.
+    /// because the fixing edit's event was lost. Each is due for another [`Scanner::process_change`].
+    pub fn stale_quarantine(&mut self) -> Vec<PathBuf> {
+        self.quarantine.retain(|path, _| self.parse_errors.contains_key(path));
+        let mut stale = Vec::new();
+        for path in self.parse_errors.keys() {
+            let hash = std::fs::read(path).ok().map(|source| content_hash(&source));
+            let Some(hash) = hash else {
+                stale.push(path.clone());
+                continue;
+            };
+            if self.quarantine.insert(path.clone(), hash) != Some(hash) {
+                stale.push(path.clone());
+            }
+        }
+        stale.sort();
+        stale
+    }
+
+    /// Drops a deleted or skipped file and patches the stylesheets without it.
+    fn forget(&mut self, path: &Path) {
+        self.file_map.remove(path);
+        self.parse_errors.remove(path);
+        self.cache.remove(path);
+        self.save_cache();
+        self.update_reachability(path);
+        self.write_cascade_stylesheets();
+        let (new_global_classnames, new_global_ids) = self.globals(&self.file_map);
+        if new_global_classnames != self.classnames || new_global_ids != self.ids {
+            self.write_stylesheet(&new_global_classnames, &new_global_ids);
+        }
+        self.classnames = new_global_classnames;
+        self.ids = new_global_ids;
+    }
+
+    /// Populates the file map and global sets without writing anything to disk.
+    pub fn index(&mut self) {
+        let cm: Arc<SourceMap> = Default::default();
+        self.file_map = self
+            .source_paths()
+            .par_iter()
+            .filter_map(|path| collect_from_file(path, &cm, &self.options_for(path)).map(|entities| (path.clone(), entities)))
+            .collect();
+        self.refresh_reachability();
+        let (classnames, ids) = self.globals(&self.file_map);
+        self.classnames = classnames;
+        self.ids = ids;
+    }
+
+    /// Refreshes a single file in the index without writing anything to disk.
+    pub fn index_file(&mut self, path: &Path) {
+        let cm: Arc<SourceMap> = Default::default();
+        match collect_from_file(path, &cm, &self.options_for(path)) {
+            Some(entities) => {
+                self.file_map.insert(path.to_path_buf(), entities);
+            }
+            None if !path.exists() => {
+                self.file_map.remove(path);
+            }
+            None => return,
+        }
+        self.update_reachability(path);
+        let (classnames, ids) = self.globals(&self.file_map);
+        self.classnames = classnames;
+        self.ids = ids;
+    }
+
+    /// Loads the symbol index saved in `.dx/symbols.json` and re-indexes the files that changed
+    /// since; from then on the watcher keeps it current through [`Scanner::update_symbols`].
+    pub fn open_symbols(&mut self) {
+        let mut symbols = SymbolIndex::load(&self.root);
+        symbols.refresh(self);
+        self.symbols = Some(symbols);
+    }
+
+    /// Re-indexes `path` in the symbol index, if one is open, when its content changed.
+    pub fn update_symbols(&mut self, path: &Path) {
+        if let Some(mut symbols) = self.symbols.take() {
+            symbols.refresh_file(self, path);
+            self.symbols = Some(symbols);
+        }
+    }
+
+    /// Persists the symbol index, if one is open; failures only cost a re-index next time.
+    pub fn save_symbols(&self) {
+        if let Some(symbols) = &self.symbols {
+            let _ = symbols.save(&self.root);
+        }
+    }
+
+    /// Parsed rules of every handwritten stylesheet matched by `config.stylesheets`.
+    pub fn stylesheets(&self) -> Vec<(PathBuf, Vec<Rule>)> {
+        let manifest = read_asset_manifest(&self.root.join(ASSET_MANIFEST_FILE));
+        let hashed = manifest.into_values().map(|name| self.root.join(name));
+        let outputs: Vec<_> = self
+            .stylesheet_outputs()
+            .into_iter()
+            .chain(hashed)
+            .filter_map(|path| paths::canonicalize(&path).ok())
+            .collect();
+        self.config
+            .stylesheets
+            .iter()
+            .filter_map(|pattern| {
+                let root = Pattern::escape(&self.root.to_string_lossy());
+                glob(&format!("{}/{}", root, pattern.trim_start_matches("./"))).ok()
+            })
+            .flat_map(|paths| paths.filter_map(Result::ok))
+            .filter(|path| paths::canonicalize(path).is_ok_and(|path| !outputs.contains(&path)))
+            .filter_map(|path| {
+                let css = std::fs::read_to_string(&path).ok()?;
+                Some((path, parse_rules(&css)))
+            })
+            .collect()
+    }
+
+    /// Per-file metadata for every indexed file, re-extracted without writing.
+    pub fn metadata(&self) -> BTreeMap<PathBuf, FileMeta> {
+        let cm: Arc<SourceMap> = Default::default();
+        self.file_map
+            .par_iter()
+            .filter_map(|(path, _)| {
+                let extraction = extract_from_file(path, &cm, &self.options_for(path))?;
+                Some((path.clone(), FileMeta::from_extraction(&extraction)))
+            })
+            .collect()
+    }
+
+    /// Metadata for a single file, or `None` if it no longer parses.
+    pub fn file_metadata(&self, path: &Path) -> Option<FileMeta> {
+        let cm: Arc<SourceMap> = Default::default();
+        extract_from_file(path, &cm, &self.options_for(path)).map(|extraction| FileMeta::from_extraction(&extraction))
+    }
+
+    /// Every element using a class accepted by `matches`, ordered by path and line.
+    ///
+    /// Only files whose indexed classes match are re-parsed.
+    pub fn class_usages(&self, matches: impl Fn(&str) -> bool + Sync) -> Vec<ClassUsage> {
+        let cm: Arc<SourceMap> = Default::default();
+        let mut usages: Vec<ClassUsage> = self
+            .file_map
+            .par_iter()
+            .filter(|(_, (classnames, _))| classnames.iter().any(|c| matches(c)))
+            .filter_map(|(path, _)| Some((path, extract_from_file(path, &cm, &self.options_for(path))?)))
+            .flat_map_iter(|(path, extraction)| {
+                let matches = &matches;
+                extraction.elements.into_iter().flat_map(move |el| {
+                    el.classnames
+                        .iter()
+                        .filter(|c| matches(c))
+                        .map(|classname| {
+                            let (start, end) = el
+                                .class_spans
+                                .iter()
+                                .find(|span| span.name == *classname)
+                                .map_or((el.start, el.end), |span| (span.start, span.end));
+                            ClassUsage {
+                                path: path.clone(),
+                                line: el.line,
+                                column: el.column,
+                                classname: classname.clone(),
+                                start,
+                                end,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        usages.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+        usages
+    }
+
+    /// Every element whose id is `id`, ordered by path and line.
+    pub fn id_usages(&self, id: &str) -> Vec<IdUsage> {
+        let cm: Arc<SourceMap> = Default::default();
+        let mut usages: Vec<IdUsage> = self
+            .file_map
+            .par_iter()
+            .filter(|(_, (_, ids))| ids.contains(id))
+            .filter_map(|(path, _)| Some((path, extract_from_file(path, &cm, &self.options_for(path))?)))
+            .flat_map_iter(|(path, extraction)| {
+                extraction.elements.into_iter().filter(move |el| el.id.as_deref() == Some(id)).map(move |el| IdUsage {
+                    path: path.clone(),
+                    line: el.line,
+                    column: el.column,
+                    start: el.start,
+                    end: el.end,
+                })
+            })
+            .collect();
+        usages.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+        usages
+    }
+
+    /// Contradictory utilities on any element of an indexed file, ordered by path and line.
+    pub fn conflicts(&self) -> Vec<ConflictFinding> {
+        let mut findings: Vec<_> = self
+            .file_map
+            .par_iter()
+            .flat_map_iter(|(path, _)| self.file_conflicts(path))
+            .collect();
+        findings.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+        findings
+    }
+
+    /// Contradictory utilities on the elements of a single file.
+    pub fn file_conflicts(&self, path: &Path) -> Vec<ConflictFinding> {
+        let cm: Arc<SourceMap> = Default::default();
+        let Some(extraction) = extract_from_file(path, &cm, &self.options_for(path)) else {
+            return Vec::new();
+        };
+        extraction
+            .elements
+            .iter()
+            .flat_map(|el| {
+                find_conflicts(&el.classnames).into_iter().map(|conflict| ConflictFinding {
+                    path: path.to_path_buf(),
+                    line: el.line,
+                    column: el.column,
+                    conflict,
+                })
+            })
+            .collect()
+    }
+
+    /// The resolved local imports of every indexed file.
+    pub fn import_graph(&self) -> ImportGraph {
+        let files: Vec<PathBuf> = self.file_map.keys().cloned().collect();
+        import_graph(&files, &self.root, |path| self.options_for(path))
+    }
+
+    /// Re-resolves the imports of a changed (or deleted) file in `graph`.
+    pub fn update_import_graph(&self, graph: &mut ImportGraph, path: &Path) {
+        if self.file_map.contains_key(path) && !graph.contains_key(path) {
+            // A new file may satisfy imports that previously failed to resolve.
+            *graph = self.import_graph();
+        } else if self.file_map.contains_key(path) {
+            let imports = file_imports(path, &self.root, &self.options_for(path), |p| self.file_map.contains_key(p));
+            graph.insert(path.to_path_buf(), imports);
+        } else {
+            graph.remove(path);
+        }
+    }
+
+    /// Files matching the configured `entries`, canonicalized.
+    pub fn entry_paths(&self) -> Vec<PathBuf> {
+        self.config
+            .entries
+            .iter()
+            .filter_map(|pattern| glob(&self.root.join(pattern).to_string_lossy()).ok())
+            .flat_map(|paths| paths.filter_map(Result::ok))
+            .filter_map(|path| paths::canonicalize(path).ok())
+            .collect()
+    }
+
+    /// The configured `entries`, or else conventional ones: `src/main.*`, `src/index.*`,
+    /// `src/App.*`, everything under `src/pages/` and route files under `src/app/`.
+    pub fn analysis_entry_paths(&self) -> Vec<PathBuf> {
+        if !self.config.entries.is_empty() {
+            return self.entry_paths();
+        }
+        let Ok(src) = paths::canonicalize(self.root.join("src")) else {
+            return Vec::new();
+        };
+        let stem_in = |path: &Path, stems: &[&str]| {
+            path.file_stem().and_then(|s| s.to_str()).is_some_and(|s| stems.contains(&s))
+        };
+        let mut entries: Vec<PathBuf> = self
+            .file_map
+            .keys()
+            .filter(|path| {
+                (path.parent() == Some(src.as_path()) && stem_in(path, ROOT_ENTRY_STEMS))
+                    || path.starts_with(src.join("pages"))
+                    || (path.starts_with(src.join("app")) && stem_in(path, ROUTE_ENTRY_STEMS))
+            })
+            .cloned()
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// Files unreachable from [`Scanner::analysis_entry_paths`] and the classes used only there,
+    /// or `None` if the project has no recognizable entry points.
+    pub fn dead_code(&self) -> Option<DeadCode> {
+        let entries = self.analysis_entry_paths();
+        if entries.is_empty() {
+            return None;
+        }
+        let scope: HashSet<PathBuf> = reachable(&self.import_graph(), &entries).into_iter().collect();
+        Some(self.dead_code_outside(&scope))
+    }
+
+    fn dead_code_outside(&self, scope: &HashSet<PathBuf>) -> DeadCode {
+        let live: HashSet<&String> = scope
+            .iter()
+            .filter_map(|path| self.file_map.get(path))
+            .flat_map(|(classnames, _)| classnames)
+            .collect();
+        let mut files: Vec<PathBuf> = self.file_map.keys().filter(|path| !scope.contains(*path)).cloned().collect();
+        files.sort();
+        let mut classnames: Vec<String> = files
+            .iter()
+            .flat_map(|path| &self.file_map[path].0)
+            .filter(|classname| !live.contains(classname))
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        classnames.sort();
+        DeadCode { files, classnames }
+    }
+
+    /// Drops unreachable files from the stylesheet and keeps them out on later changes, as if
+    /// the analysis entry points were configured as `entries`.
+    pub fn prune(&mut self) -> Option<DeadCode> {
+        let entries = self.analysis_entry_paths();
+        if entries.is_empty() {
+            return None;
+        }
+        let graph = self.import_graph();
+        let scope: HashSet<PathBuf> = reachable(&graph, &entries).into_iter().collect();
+        let dead = self.dead_code_outside(&scope);
+        self.reachable = Some(scope);
+        self.graph = Some(graph);
+
+        let (classnames, ids) = self.globals(&self.file_map);
+        self.write_stylesheet(&classnames, &ids);
+        self.classnames = classnames;
+        self.ids = ids;
+        Some(dead)
+    }
+
+    /// Rebuilds the import graph and reachable set; a no-op without `entries`.
+    fn refresh_reachability(&mut self) {
+        if self.config.entries.is_empty() {
+            return;
+        }
+        let graph = self.import_graph();
+        self.reachable = Some(reachable(&graph, &self.entry_paths()).into_iter().collect());
+        self.graph = Some(graph);
+    }
+
+    /// Re-resolves the imports of a changed file; returns whether the reachable set changed.
+    fn update_reachability(&mut self, path: &Path) -> bool {
+        let Some(mut graph) = self.graph.take() else {
+            return false;
+        };
+        self.update_import_graph(&mut graph, path);
+        let scope: HashSet<PathBuf> = reachable(&graph, &self.analysis_entry_paths()).into_iter().collect();
+        self.graph = Some(graph);
+        let changed = self.reachable.as_ref() != Some(&scope);
+        self.reachable = Some(scope);
+        changed
+    }
+
+    /// The global class and id sets, counting only reachable files when `entries` is configured
+    /// and leaving out files whose nested config sends them to another stylesheet.
+    fn globals(&self, file_map: &FileMap) -> (HashSet<String>, HashSet<String>) {
+        if self.reachable.is_none() && self.cascade.is_empty() {
+            return calculate_global_classnames_and_ids(file_map);
+        }
+        self.globals_where(file_map, |path| self.cascade.output(path).is_none())
+    }
+
+    fn globals_where(&self, file_map: &FileMap, keep: impl Fn(&Path) -> bool) -> (HashSet<String>, HashSet<String>) {
+        let files: FileMap = file_map
+            .iter()
+            .filter(|(path, _)| self.reachable.as_ref().is_none_or(|scope| scope.contains(*path)) && keep(path))
+            .map(|(path, entities)| (path.clone(), entities.clone()))
+            .collect();
+        calculate_global_classnames_and_ids(&files)
+    }
+
+    /// The App Router routes of the project, empty without `src/app`.
+    pub fn routes(&self) -> Vec<Route> {
+        let Some(app_dir) = app_dir(&self.root) else {
+            return Vec::new();
+        };
+        let files: Vec<PathBuf> = self.file_map.keys().cloned().collect();
+        routes(&app_dir, &files, &self.import_graph())
+    }
+
+    /// Writes one stylesheet per Next.js App Router route into `css_dir`, holding only the classes
+    /// of the route's page, layouts and their imports, plus the route manifest at the root.
+    pub fn write_route_styles(&self, css_dir: &Path) -> std::io::Result<Vec<Route>> {
+        let routes = self.routes();
+        if routes.is_empty() {
+            return Ok(routes);
+        }
+
+        std::fs::create_dir_all(css_dir)?;
+        let css_dir = paths::canonicalize(css_dir)?;
+        for route in &routes {
+            let route_map: FileMap = route
+                .files
+                .iter()
+                .filter_map(|path| Some((path.clone(), self.file_map.get(path)?.clone())))
+                .collect();
+            let (classnames, ids) = calculate_global_classnames_and_ids(&route_map);
+            std::fs::write(
+                css_dir.join(route.css_file_name()),
+                render_css(&classnames, &ids, &self.css_options),
+            )?;
+        }
+        write_manifest(&self.root.join(MANIFEST_FILE), &routes, &self.root, &css_dir)?;
+        Ok(routes)
+    }
+
+    /// The stylesheet for the current global class and id sets.
+    pub fn css(&self) -> String {
+        self.render_stylesheet(&self.classnames, &self.ids)
+    }
+
+    /// The stylesheet as it would be once every use of the classes in `renames` is renamed.
+    pub fn css_with_renames(&self, renames: &HashMap<String, String>) -> String {
+        let classnames = self.classnames.iter().map(|c| renamed_class(c, renames).into_owned()).collect();
+        self.render_stylesheet(&classnames, &self.ids)
+    }
+
+    /// Every named group in the project and config, with its expansion and usage count.
+    pub fn group_usages(&self) -> BTreeMap<String, GroupUsage> {
+        let cm: Arc<SourceMap> = Default::default();
+        let file_usages: Vec<_> = self
+            .source_paths()
+            .par_iter()
+            .filter_map(|path| group_usages_from_file(path, &cm, &self.options_for(path)))
+            .collect();
+        merge_group_usages(file_usages, &self.config.groups)
+    }
+}
+
+/// Sorted `.class` and `#id` selectors present in the first sets but not the second.
+fn selector_diff(
+    classnames: &HashSet<String>,
+    other_classnames: &HashSet<String>,
+    ids: &HashSet<String>,
+    other_ids: &HashSet<String>,
+) -> Vec<String> {
+    let mut selectors: Vec<_> = classnames
+        .difference(other_classnames)
+        .map(|c| format!(".{}", c))
+        .chain(ids.difference(other_ids).map(|id| format!("#{}", id)))
+        .collect();
+    selectors.sort();
+    selectors
+}
+
+pub fn calculate_global_classnames_and_ids(file_map: &FileMap) -> (HashSet<String>, HashSet<String>) {
+    let classnames = file_map
+        .par_iter()
+        .flat_map(|(_, (classes, _))| classes.clone())
+        .collect();
+    let ids = file_map
+        .par_iter()
+        .flat_map(|(_, (_, ids))| ids.clone())
+        .collect();
+    (classnames, ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARD: &str = "export const Card = () => <div className=\"p-4 flex\" />;\n";
+    const BROKEN_CARD: &str = "export const Card = () => <div className=\"p-4 grid\"\n";
+
+    /// A project in a fresh temporary directory with `files` written relative to its root.
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("dx-scanner-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        paths::canonicalize(&dir).unwrap()
+    }
+
+    fn scanner_for(root: &Path) -> Scanner {
+        Scanner::with_root(Config::default(), PluginRegistry::with_builtins(), root)
+    }
+
+    #[test]
+    fn edits_that_keep_the_classes_refresh_the_cached_hash() {
+        let root = project("refresh", &[("src/card.tsx", CARD)]);
+        let path = root.join("src/card.tsx");
+        let mut scanner = scanner_for(&root);
+        scanner.initial_scan();
+
+        let edited = format!("// A card.\n{}", CARD);
+        std::fs::write(&path, &edited).unwrap();
+        assert!(scanner.process_change(&path).is_none());
+        let hash = Some(content_hash(edited.as_bytes()));
+        assert_eq!(scanner.cache.hash(&path), hash);
+        // The next event for the same content short-circuits before extraction.
+        let unchanged_events = scanner.unchanged_events;
+        assert!(scanner.process_change(&path).is_none());
+        assert_eq!(scanner.unchanged_events, unchanged_events + 1);
+        // The refreshed hash was saved too.
+        assert_eq!(scanner_for(&root).cache.hash(&path), hash);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn nested_configs_apply_to_their_subtree() {
+        let panel = "export const Panel = () => <section className=\"anchor card\" />;\n";
+        let root = project(
+            "cascade",
+            &[
+                ("src/panel.tsx", panel),
+                ("src/admin/panel.tsx", panel),
+                ("src/admin/dx.config.toml", "id_trigger = \"anchor\"\n"),
+                ("src/vendor/widget.tsx", "export const Widget = () => <div className=\"vendor-only\" />;\n"),
+                ("src/vendor/dx.config.toml", "exclude = [\"*.tsx\"]\n"),
+            ],
+        );
+        let mut scanner = scanner_for(&root);
+        scanner.initial_scan();
+
+        // `anchor` only asks for an id below src/admin/.
+        assert!(scanner.file_map[&root.join("src/panel.tsx")].1.is_empty());
+        assert_eq!(std::fs::read_to_string(root.join("src/panel.tsx")).unwrap(), panel);
+        assert_eq!(scanner.file_map[&root.join("src/admin/panel.tsx")].1.len(), 1);
+        assert!(!scanner.file_map.contains_key(&root.join("src/vendor/widget.tsx")));
+        assert!(!scanner.classnames.contains("vendor-only"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn quarantined_files_are_retried_once_they_change() {
+        let root = project("quarantine", &[("src/card.tsx", BROKEN_CARD)]);
+        let path = root.join("src/card.tsx");
+        let mut scanner = scanner_for(&root);
+        scanner.initial_scan();
+        assert!(scanner.parse_errors.contains_key(&path));
+
+        // The first check records the failed content; it is not retried until that changes.
+        scanner.stale_quarantine();
+        assert!(scanner.stale_quarantine().is_empty());
+        std::fs::write(&path, CARD).unwrap();
+        assert_eq!(scanner.stale_quarantine(), [path.clone()]);
+
+        scanner.process_change(&path);
+        assert!(scanner.parse_errors.is_empty());
+        assert!(scanner.classnames.contains("flex"));
+        assert!(scanner.stale_quarantine().is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn files_that_stop_parsing_keep_their_last_good_classes() {
+        let root = project("last-good", &[("src/card.tsx", CARD)]);
+        let path = root.join("src/card.tsx");
+        let mut scanner = scanner_for(&root);
+        scanner.initial_scan();
+
+        std::fs::write(&path, BROKEN_CARD).unwrap();
+        assert!(scanner.process_change(&path).is_none());
+        assert!(scanner.parse_errors.contains_key(&path));
+        assert!(scanner.classnames.contains("flex") && !scanner.classnames.contains("grid"));
+
+        std::fs::write(&path, CARD.replace("flex", "grid")).unwrap();
+        assert!(scanner.process_change(&path).is_some());
+        assert!(scanner.parse_errors.is_empty());
+        assert!(scanner.classnames.contains("grid") && !scanner.classnames.contains("flex"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+// dx-styles live reload client.
+// Usage: <script src="http://127.0.0.1:4545/client.js"></script>
+(function () {
+  var origin = new URL(document.currentScript.src).origin;
+  var link = document.querySelector("link[data-dx-styles]");
+  if (!link) {
+    link = document.createElement("link");
+    link.rel = "stylesheet";
+    link.href = origin + "/styles.css";
+    link.setAttribute("data-dx-styles", "");
+    document.head.appendChild(link);
+  }
+
+  function swap() {
+    var next = link.cloneNode();
+    next.href = origin + "/styles.css?t=" + Date.now();
+    next.onload = function () {
+      link.remove();
+      link = next;
+    };
+    link.after(next);
+  }
+
+  function connect() {
+    var socket = new WebSocket(origin.replace(/^http/, "ws") + "/ws");
+    socket.onmessage = function (event) {
+      var message = JSON.parse(event.data);
+      if (message.type === "css-updated") {
+        swap();
+      }
+    };
+    socket.onclose = function () {
+      setTimeout(connect, 1000);
+    };
+  }
+
+  connect();
+})();
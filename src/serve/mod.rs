@@ -0,0 +1,97 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tungstenite::{Message, WebSocket};
+
+const CLIENT_JS: &str = include_str!("client.js");
+
+/// Serves the generated stylesheet over HTTP and pushes `css-updated`
+/// messages to WebSocket clients connected on `/ws`.
+#[derive(Clone)]
+pub struct DevServer {
+    output_path: PathBuf,
+    clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+}
+
+/// Turns `:4545` into `127.0.0.1:4545`; full addresses are used as-is.
+pub fn normalize_addr(addr: &str) -> String {
+    if addr.starts_with(':') {
+        format!("127.0.0.1{}", addr)
+    } else {
+        addr.to_string()
+    }
+}
+
+impl DevServer {
+    pub fn start(addr: impl ToSocketAddrs, output_path: PathBuf) -> std::io::Result<DevServer> {
+        let listener = TcpListener::bind(addr)?;
+        let server = DevServer {
+            output_path,
+            clients: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let accept_server = server.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = accept_server.clone();
+                thread::spawn(move || server.handle(stream));
+            }
+        });
+
+        Ok(server)
+    }
+
+    /// Tells every connected browser to reload the stylesheet.
+    pub fn notify_css_updated(&self) {
+        let message = Message::text(r#"{"type":"css-updated","href":"/styles.css"}"#);
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|client| client.send(message.clone()).is_ok());
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let mut head = [0u8; 4096];
+        let Ok(len) = stream.peek(&mut head) else {
+            return;
+        };
+        let request = String::from_utf8_lossy(&head[..len]).to_string();
+        let path = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/")
+            .split('?')
+            .next()
+            .unwrap_or("/")
+            .to_string();
+
+        if request.to_ascii_lowercase().contains("upgrade: websocket") {
+            if let Ok(client) = tungstenite::accept(stream) {
+                self.clients.lock().unwrap().push(client);
+            }
+            return;
+        }
+
+        let _ = stream.read(&mut head);
+        let (status, content_type, body) = match path.as_str() {
+            "/styles.css" => match std::fs::read_to_string(&self.output_path) {
+                Ok(css) => ("200 OK", "text/css", css),
+                Err(_) => ("404 Not Found", "text/plain", "styles.css not generated yet".to_string()),
+            },
+            "/client.js" => ("200 OK", "application/javascript", CLIENT_JS.to_string()),
+            _ => ("404 Not Found", "text/plain", "Not found".to_string()),
+        };
+
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use swc_ecma_ast::{Expr, JSXAttr, JSXAttrName, JSXAttrValue, JSXExpr, Lit, Str};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+use crate::utility::{
+    category, declarations, parse_class, strip_class_prefix, strip_important, variant_rank, Category,
+};
+
+/// Where a utility goes in the canonical order, like `css.order = "layers"`: by variant rank,
+/// variant count, category and utility. `None` for classes dx has no rule for.
+fn sort_key(classname: &str, prefix: &str, important_modifier: &str) -> Option<(usize, usize, Category, String)> {
+    let stripped = strip_class_prefix(classname, prefix, important_modifier)?;
+    let parsed = parse_class(&stripped);
+    let utility = strip_important(parsed.utility, important_modifier).1;
+    declarations(utility)?;
+    let rank = parsed.variants.iter().map(|v| variant_rank(v)).max().unwrap_or(0);
+    Some((rank, parsed.variants.len(), category(utility), utility.to_string()))
+}
+
+/// `value`'s classes in their original order, separated by single spaces and without duplicates.
+pub fn normalize_classes(value: &str) -> String {
+    unique_classes(value).join(" ")
+}
+
+fn unique_classes(value: &str) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    value.split_whitespace().filter(|classname| seen.insert(*classname)).collect()
+}
+
+/// `value`'s classes in canonical order, separated by single spaces and without duplicates.
+/// Classes dx has no rule for (components, markers, third-party classes) keep their relative
+/// order at the front, so the result reads like prettier-plugin-tailwindcss output.
+pub fn sort_classes(value: &str, prefix: &str, important_modifier: &str) -> String {
+    let mut classes = unique_classes(value);
+    classes.sort_by_cached_key(|classname| {
+        sort_key(classname, prefix, important_modifier).map(|key| (key, classname.to_string()))
+    });
+    classes.join(" ")
+}
+
+/// Sorts the literal class attributes (`className="p-4 flex"`, `className={"p-4 flex"}`) with
+/// [`sort_classes`], or only cleans them up with [`normalize_classes`] when `sort` is off.
+/// Leading and trailing whitespace is dropped either way.
+pub struct ClassSorter<'a> {
+    pub sort: bool,
+    pub attributes: &'a [String],
+    pub class_prefix: &'a str,
+    pub important_modifier: &'a str,
+    /// How many attributes changed.
+    pub sorted: usize,
+}
+
+impl ClassSorter<'_> {
+    fn sort(&mut self, s: &mut Str) {
+        let sorted = if self.sort {
+            sort_classes(&s.value, self.class_prefix, self.important_modifier)
+        } else {
+            normalize_classes(&s.value)
+        };
+        if *s.value == *sorted {
+            return;
+        }
+        *s = Str {
+            value: sorted.into(),
+            span: s.span,
+            raw: None,
+        };
+        self.sorted += 1;
+    }
+}
+
+impl VisitMut for ClassSorter<'_> {
+    fn visit_mut_jsx_attr(&mut self, attr: &mut JSXAttr) {
+        attr.visit_mut_children_with(self);
+        let JSXAttrName::Ident(ident) = &attr.name else {
+            return;
+        };
+        if !self.attributes.iter().any(|a| a.as_str() == &*ident.sym) {
+            return;
+        }
+        match &mut attr.value {
+            Some(JSXAttrValue::Lit(Lit::Str(s))) => self.sort(s),
+            Some(JSXAttrValue::JSXExprContainer(container)) => {
+                if let JSXExpr::Expr(expr) = &mut container.expr {
+                    if let Expr::Lit(Lit::Str(s)) = &mut **expr {
+                        self.sort(s);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_drops_duplicates_and_extra_whitespace() {
+        assert_eq!(normalize_classes("  p-4  flex\tp-4 "), "p-4 flex");
+    }
+
+    #[test]
+    fn sort_keeps_unknown_classes_first() {
+        assert_eq!(sort_classes("card hover:p-2 p-4 flex card", "", "!"), "card flex p-4 hover:p-2");
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::cache::content_hash;
+use crate::css::{render_css, CssOptions};
+use crate::graph::{reachable, ImportGraph};
+use crate::meta::FileMeta;
+
+/// Whether `path` is a Storybook CSF file such as `Button.stories.tsx`.
+pub fn is_story(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(".stories."))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryEntry {
+    /// The story file and every component it (transitively) imports.
+    pub files: Vec<PathBuf>,
+    pub classnames: Vec<String>,
+    pub ids: Vec<String>,
+    pub generated_ids: Vec<String>,
+    /// The stylesheet the story needs on its own.
+    pub css: String,
+    /// Changes whenever the story's CSS does, for addons to flag visual-affecting changes.
+    pub css_hash: String,
+}
+
+/// One entry per story file, built from per-file metadata and the import graph.
+pub fn stories(
+    files: &BTreeMap<PathBuf, FileMeta>,
+    graph: &ImportGraph,
+    css_options: &CssOptions,
+) -> BTreeMap<PathBuf, StoryEntry> {
+    files
+        .keys()
+        .filter(|path| is_story(path))
+        .map(|story| {
+            let reached = reachable(graph, std::slice::from_ref(story));
+            let metas: Vec<&FileMeta> = reached.iter().filter_map(|path| files.get(path)).collect();
+            let classnames: BTreeSet<&String> = metas.iter().flat_map(|meta| &meta.classnames).collect();
+            let ids: BTreeSet<&String> = metas.iter().flat_map(|meta| &meta.ids).collect();
+            let generated_ids: BTreeSet<&String> = metas.iter().flat_map(|meta| &meta.generated_ids).collect();
+
+            let css = render_css(
+                &classnames.iter().map(|c| c.to_string()).collect::<HashSet<_>>(),
+                &ids.iter().map(|id| id.to_string()).collect::<HashSet<_>>(),
+                css_options,
+            );
+            let entry = StoryEntry {
+                files: reached,
+                classnames: classnames.into_iter().cloned().collect(),
+                ids: ids.into_iter().cloned().collect(),
+                generated_ids: generated_ids.into_iter().cloned().collect(),
+                css_hash: format!("{:016x}", content_hash(css.as_bytes())),
+                css,
+            };
+            (story.clone(), entry)
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct StoriesDocument<'a> {
+    version: u32,
+    stories: BTreeMap<String, &'a StoryEntry>,
+}
+
+/// Writes `stories` as JSON, keyed by (and listing files as) paths relative to the current directory.
+pub fn write_stories(output_path: &Path, stories: &BTreeMap<PathBuf, StoryEntry>) -> std::io::Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let relative = |path: &Path| {
+        path.strip_prefix(&current_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    };
+    let relative_entries: Vec<(String, StoryEntry)> = stories
+        .iter()
+        .map(|(path, entry)| {
+            let mut entry = entry.clone();
+            entry.files = entry.files.iter().map(|file| PathBuf::from(relative(file))).collect();
+            (relative(path), entry)
+        })
+        .collect();
+    let document = StoriesDocument {
+        version: 1,
+        stories: relative_entries.iter().map(|(path, entry)| (path.clone(), entry)).collect(),
+    };
+    let json = serde_json::to_string_pretty(&document)?;
+    std::fs::write(output_path, json)
+}
@@ -0,0 +1,191 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+static CLASS_SELECTOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\.((?:\\[0-9a-fA-F]{1,6} ?|\\.|[\w-])+)").unwrap());
+static ID_SELECTOR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#((?:\\[0-9a-fA-F]{1,6} ?|\\.|[\w-])+)").unwrap());
+static ID_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\[id="((?:\\.|[^"\\])*)"\]"#).unwrap());
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub selector: String,
+    pub body: String,
+    /// Zero-based line of the selector in the stylesheet.
+    pub line: usize,
+}
+
+impl Rule {
+    pub fn classes(&self) -> Vec<String> {
+        selector_classes(&self.selector)
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        selector_ids(&self.selector)
+    }
+
+    /// The comma-separated selectors of this rule, trimmed.
+    pub fn selectors(&self) -> Vec<&str> {
+        split_selector_list(&self.selector)
+    }
+
+    pub fn has_declarations(&self) -> bool {
+        !self.body.trim().is_empty()
+    }
+}
+
+pub fn selector_classes(selector: &str) -> Vec<String> {
+    selector_names(&CLASS_SELECTOR, selector)
+}
+
+/// Ids named by `#id` or `[id="id"]`.
+pub fn selector_ids(selector: &str) -> Vec<String> {
+    let mut ids = selector_names(&ID_SELECTOR, selector);
+    ids.extend(selector_names(&ID_ATTRIBUTE, selector));
+    ids
+}
+
+/// Splits a selector list on top-level commas, leaving commas inside `:is(...)` and friends alone.
+pub fn split_selector_list(selector: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(selector[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(selector[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// A class or id named by a selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorName {
+    Class(String),
+    Id(String),
+}
+
+/// The last class or id in `selector`, whichever comes later: the subject of a generated rule,
+/// e.g. `p-4` in `.my-app .p-4`, or `nav` in `.my-app #nav` and `[id="nav"]`.
+pub fn subject_name(selector: &str) -> Option<SelectorName> {
+    let last = |re: &Regex| re.captures_iter(selector).filter_map(|caps| caps.get(1)).last();
+    let id = [last(&ID_SELECTOR), last(&ID_ATTRIBUTE)].into_iter().flatten().max_by_key(|id| id.start());
+    match (last(&CLASS_SELECTOR), id) {
+        (Some(class), Some(id)) if id.start() > class.start() => Some(SelectorName::Id(unescape(id.as_str()))),
+        (Some(class), _) => Some(SelectorName::Class(unescape(class.as_str()))),
+        (None, Some(id)) => Some(SelectorName::Id(unescape(id.as_str()))),
+        (None, None) => None,
+    }
+}
+
+fn selector_names(re: &Regex, selector: &str) -> Vec<String> {
+    re.captures_iter(selector)
+        .filter_map(|caps| caps.get(1))
+        .map(|m| unescape(m.as_str()))
+        .collect()
+}
+
+pub fn unescape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let mut hex = String::new();
+        while hex.len() < 6 && chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            hex.extend(chars.next());
+        }
+        if hex.is_empty() {
+            out.extend(chars.next());
+            continue;
+        }
+        // A hex escape (`\31 `) may be terminated by a single space.
+        if chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        out.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+    }
+    out
+}
+
+pub fn parse_rules(css: &str) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    parse_block(&strip_comments(css), 0, &mut rules);
+    rules
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        let end = rest[start + 2..]
+            .find("*/")
+            .map(|e| start + 2 + e + 2)
+            .unwrap_or(rest.len());
+        out.extend(rest[start..end].chars().map(|c| if c == '\n' { '\n' } else { ' ' }));
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_block(css: &str, base_line: usize, rules: &mut Vec<Rule>) {
+    let bytes = css.as_bytes();
+    let mut i = 0;
+    let mut prelude_start = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => {
+                let mut depth = 1;
+                let mut j = i + 1;
+                while j < bytes.len() && depth > 0 {
+                    match bytes[j] {
+                        b'{' => depth += 1,
+                        b'}' => depth -= 1,
+                        _ => {}
+                    }
+                    j += 1;
+                }
+
+                let prelude = &css[prelude_start..i];
+                let body_end = if depth == 0 { j - 1 } else { j };
+                let body = &css[i + 1..body_end];
+                let leading = prelude.len() - prelude.trim_start().len();
+                let line = base_line + css[..prelude_start + leading].matches('\n').count();
+                let selector = prelude.trim();
+
+                if selector.starts_with('@') {
+                    if body.contains('{') {
+                        let body_line = base_line + css[..=i].matches('\n').count();
+                        parse_block(body, body_line, rules);
+                    }
+                } else if !selector.is_empty() {
+                    rules.push(Rule {
+                        selector: selector.to_string(),
+                        body: body.trim().to_string(),
+                        line,
+                    });
+                }
+
+                i = j;
+                prelude_start = j;
+            }
+            b';' | b'}' => {
+                i += 1;
+                prelude_start = i;
+            }
+            _ => i += 1,
+        }
+    }
+}
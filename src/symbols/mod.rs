@@ -0,0 +1,232 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cache::content_hash;
+use crate::extract::Extraction;
+use crate::scanner::Scanner;
+
+pub const SYMBOLS_FILE: &str = ".dx/symbols.json";
+
+/// One use of a class or id, with zero-based lines and UTF-16 columns the way editors count.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FileSymbols {
+    /// Content hash of the source the symbols were read from.
+    hash: u64,
+    classes: Vec<Symbol>,
+    ids: Vec<Symbol>,
+}
+
+/// Where every class and id of a project is used, kept in `.dx/symbols.json` so lookups read
+/// no sources. Files are re-extracted only when their content hash changed since they were
+/// indexed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    files: BTreeMap<PathBuf, FileSymbols>,
+    #[serde(skip)]
+    class_files: HashMap<String, HashSet<PathBuf>>,
+    #[serde(skip)]
+    id_files: HashMap<String, HashSet<PathBuf>>,
+}
+
+/// Maps byte offsets of `source` to zero-based lines and UTF-16 columns.
+fn positions(source: &str) -> impl Fn(u32) -> (u32, u32) + '_ {
+    let line_starts: Vec<usize> =
+        std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    move |offset| {
+        let offset = (offset as usize).min(source.len());
+        let line = line_starts.partition_point(|&start| start <= offset) - 1;
+        let text = source.get(line_starts[line]..offset).unwrap_or_default();
+        (line as u32, text.encode_utf16().count() as u32)
+    }
+}
+
+fn file_symbols(hash: u64, extraction: &Extraction) -> FileSymbols {
+    let position = positions(&extraction.source);
+    let symbol = |name: &str, start: u32, end: u32| {
+        let ((line, column), (end_line, end_column)) = (position(start), position(end));
+        Symbol { name: name.to_string(), line, column, end_line, end_column }
+    };
+    let mut symbols = FileSymbols { hash, ..Default::default() };
+    for el in &extraction.elements {
+        for classname in &el.classnames {
+            // Classes not written literally, e.g. from a group, point at the opening tag.
+            let (start, end) = el
+                .class_spans
+                .iter()
+                .find(|span| span.name == *classname)
+                .map_or((el.start, el.end), |span| (span.start, span.end));
+            symbols.classes.push(symbol(classname, start, end));
+        }
+        if let Some(id) = &el.id {
+            symbols.ids.push(symbol(id, el.start, el.end));
+        }
+    }
+    symbols
+}
+
+impl SymbolIndex {
+    pub fn load(root: &Path) -> Self {
+        let mut index: SymbolIndex = std::fs::read_to_string(root.join(SYMBOLS_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let paths: Vec<PathBuf> = index.files.keys().cloned().collect();
+        for path in paths {
+            index.link(&path);
+        }
+        index
+    }
+
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let path = root.join(SYMBOLS_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    fn link(&mut self, path: &Path) {
+        let Some(symbols) = self.files.get(path) else {
+            return;
+        };
+        for symbol in &symbols.classes {
+            self.class_files.entry(symbol.name.clone()).or_default().insert(path.to_path_buf());
+        }
+        for symbol in &symbols.ids {
+            self.id_files.entry(symbol.name.clone()).or_default().insert(path.to_path_buf());
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let Some(symbols) = self.files.remove(path) else {
+            return;
+        };
+        for (names, files) in [(symbols.classes, &mut self.class_files), (symbols.ids, &mut self.id_files)] {
+            for symbol in names {
+                if let Some(paths) = files.get_mut(&symbol.name) {
+                    paths.remove(path);
+                    if paths.is_empty() {
+                        files.remove(&symbol.name);
+                    }
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf, symbols: FileSymbols) {
+        self.remove(&path);
+        self.files.insert(path.clone(), symbols);
+        self.link(&path);
+    }
+
+    /// Records the symbols of `extraction`, read from a source hashing to `hash`.
+    pub fn update(&mut self, path: &Path, hash: u64, extraction: &Extraction) {
+        self.insert(path.to_path_buf(), file_symbols(hash, extraction));
+    }
+
+    /// Re-indexes `path` if its content changed, or drops it once it is no longer indexed by
+    /// `scanner`. A file that stops parsing keeps its last symbols. Returns whether the index
+    /// changed.
+    pub fn refresh_file(&mut self, scanner: &Scanner, path: &Path) -> bool {
+        if !scanner.file_map.contains_key(path) {
+            let known = self.files.contains_key(path);
+            self.remove(path);
+            return known;
+        }
+        let Ok(source) = std::fs::read_to_string(path) else {
+            return false;
+        };
+        let hash = content_hash(source.as_bytes());
+        if self.files.get(path).is_some_and(|symbols| symbols.hash == hash) {
+            return false;
+        }
+        match scanner.preview(path, &source) {
+            Ok(extraction) => {
+                self.update(path, hash, &extraction);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Brings the index in line with every file `scanner` indexes, re-extracting in parallel
+    /// only those whose content changed. Returns whether the index changed.
+    pub fn refresh(&mut self, scanner: &Scanner) -> bool {
+        let removed: Vec<PathBuf> =
+            self.files.keys().filter(|path| !scanner.file_map.contains_key(*path)).cloned().collect();
+        let changed: Vec<(PathBuf, FileSymbols)> = scanner
+            .file_map
+            .par_iter()
+            .filter_map(|(path, _)| {
+                let source = std::fs::read_to_string(path).ok()?;
+                let hash = content_hash(source.as_bytes());
+                if self.files.get(path).is_some_and(|symbols| symbols.hash == hash) {
+                    return None;
+                }
+                let extraction = scanner.preview(path, &source).ok()?;
+                Some((path.clone(), file_symbols(hash, &extraction)))
+            })
+            .collect();
+        let modified = !removed.is_empty() || !changed.is_empty();
+        for path in removed {
+            self.remove(&path);
+        }
+        for (path, symbols) in changed {
+            self.insert(path, symbols);
+        }
+        modified
+    }
+
+    fn sites<'a>(
+        &'a self,
+        files: &HashMap<String, HashSet<PathBuf>>,
+        name: &str,
+        symbols: impl Fn(&'a FileSymbols) -> &'a [Symbol],
+    ) -> Vec<(&'a Path, &'a Symbol)> {
+        let mut paths: Vec<&PathBuf> = files.get(name).map(|paths| paths.iter().collect()).unwrap_or_default();
+        paths.sort();
+        paths
+            .into_iter()
+            .filter_map(|path| self.files.get_key_value(path))
+            .flat_map(|(path, file)| {
+                symbols(file).iter().filter(|symbol| symbol.name == name).map(move |symbol| (path.as_path(), symbol))
+            })
+            .collect()
+    }
+
+    /// Every use of class `name`, ordered by path and position.
+    pub fn class_sites(&self, name: &str) -> Vec<(&Path, &Symbol)> {
+        self.sites(&self.class_files, name, |file| file.classes.as_slice())
+    }
+
+    /// Every element carrying id `name`, ordered by path and position, spanning its opening tag.
+    pub fn id_sites(&self, name: &str) -> Vec<(&Path, &Symbol)> {
+        self.sites(&self.id_files, name, |file| file.ids.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_utf16_columns() {
+        let position = positions("ab\n😀x\n");
+        assert_eq!(position(0), (0, 0));
+        assert_eq!(position(3), (1, 0));
+        assert_eq!(position(7), (1, 2));
+        assert_eq!(position(100), (2, 0));
+    }
+}
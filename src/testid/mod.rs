@@ -0,0 +1,32 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::meta::FileMeta;
+
+/// Test ids keyed by file (relative to the current directory), then by test id, with the
+/// `line:column` position of the element carrying each.
+pub type TestIdManifest = BTreeMap<String, BTreeMap<String, String>>;
+
+pub fn test_id_manifest(files: &BTreeMap<PathBuf, FileMeta>) -> std::io::Result<TestIdManifest> {
+    let current_dir = std::env::current_dir()?;
+    Ok(files
+        .iter()
+        .filter_map(|(path, meta)| {
+            let test_ids: BTreeMap<String, String> = meta
+                .elements
+                .iter()
+                .filter_map(|el| Some((el.test_id.clone()?, format!("{}:{}", el.line, el.column))))
+                .collect();
+            if test_ids.is_empty() {
+                return None;
+            }
+            let relative = path.strip_prefix(&current_dir).unwrap_or(path);
+            Some((relative.to_string_lossy().replace('\\', "/"), test_ids))
+        })
+        .collect())
+}
+
+pub fn write_test_id_manifest(output_path: &Path, files: &BTreeMap<PathBuf, FileMeta>) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(&test_id_manifest(files)?)?;
+    std::fs::write(output_path, json)
+}
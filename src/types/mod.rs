@@ -0,0 +1,44 @@
+use std::path::Path;
+
+/// A `.d.ts` module with a `DxClass` union of `classnames` and a `dxClasses` const object
+/// keyed by them, so a typed `cn()` helper can reject unknown classes at compile time.
+pub fn class_declarations<'a>(classnames: impl IntoIterator<Item = &'a String>) -> String {
+    let mut classnames: Vec<&String> = classnames.into_iter().collect();
+    classnames.sort();
+    classnames.dedup();
+    let literals: Vec<String> = classnames
+        .iter()
+        .map(|classname| serde_json::to_string(classname).unwrap())
+        .collect();
+
+    let mut out = String::from("// Generated by dx. Do not edit.\n");
+    if literals.is_empty() {
+        out.push_str("export type DxClass = never;\n");
+    } else {
+        out.push_str("export type DxClass =\n");
+        for literal in &literals {
+            out.push_str(&format!("  | {}\n", literal));
+        }
+        out.pop();
+        out.push_str(";\n");
+    }
+    out.push_str("\nexport declare const dxClasses: {\n");
+    for literal in &literals {
+        out.push_str(&format!("  readonly {}: {};\n", literal, literal));
+    }
+    out.push_str("};\n");
+    out
+}
+
+/// Writes the declarations unless the file already holds them, so type checkers
+/// watching it are not woken by unchanged output.
+pub fn write_class_declarations<'a>(
+    output_path: &Path,
+    classnames: impl IntoIterator<Item = &'a String>,
+) -> std::io::Result<()> {
+    let content = class_declarations(classnames);
+    if std::fs::read_to_string(output_path).is_ok_and(|existing| existing == content) {
+        return Ok(());
+    }
+    std::fs::write(output_path, content)
+}
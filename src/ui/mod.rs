@@ -0,0 +1,249 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::scanner::{ChangeSummary, Scanner};
+use crate::watch::{process_path, watch_changes, WatchEvent};
+
+const MAX_EVENTS: usize = 200;
+const MAX_CSS_LINES: usize = 500;
+
+enum Outcome {
+    Changed(ChangeSummary),
+    Unchanged,
+    Removed,
+    Failed(String),
+}
+
+struct Update {
+    path: PathBuf,
+    duration: Duration,
+    outcome: Outcome,
+    files: usize,
+    classnames: usize,
+    ids: usize,
+    parse_errors: Vec<(PathBuf, String)>,
+}
+
+struct Dashboard {
+    started: Instant,
+    current_dir: PathBuf,
+    events: VecDeque<Line<'static>>,
+    timings: HashMap<PathBuf, Duration>,
+    css_tail: VecDeque<Line<'static>>,
+    files: usize,
+    classnames: usize,
+    ids: usize,
+    parse_errors: Vec<(PathBuf, String)>,
+}
+
+fn parse_errors(scanner: &Scanner) -> Vec<(PathBuf, String)> {
+    let mut errors: Vec<_> = scanner
+        .parse_errors
+        .iter()
+        .chain(&scanner.write_errors)
+        .map(|(path, diagnostic)| (path.clone(), diagnostic.message.clone()))
+        .collect();
+    errors.sort();
+    errors
+}
+
+impl Dashboard {
+    fn new(scanner: &Scanner) -> Self {
+        Dashboard {
+            started: Instant::now(),
+            current_dir: std::env::current_dir().unwrap_or_default(),
+            events: VecDeque::new(),
+            timings: HashMap::new(),
+            css_tail: VecDeque::new(),
+            files: scanner.file_map.len(),
+            classnames: scanner.classnames.len(),
+            ids: scanner.ids.len(),
+            parse_errors: parse_errors(scanner),
+        }
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.current_dir).unwrap_or(path).display().to_string()
+    }
+
+    fn apply(&mut self, update: Update) {
+        let at = format!("{:>7.1}s ", self.started.elapsed().as_secs_f64());
+        let path = self.display_path(&update.path);
+        let (style, detail) = match &update.outcome {
+            Outcome::Changed(summary) => {
+                for selector in &summary.css_added {
+                    self.css_tail.push_front(Line::styled(format!("+ {}", selector), Style::default().fg(Color::Green)));
+                }
+                for selector in &summary.css_removed {
+                    self.css_tail.push_front(Line::styled(format!("- {}", selector), Style::default().fg(Color::Red)));
+                }
+                self.css_tail.truncate(MAX_CSS_LINES);
+                (
+                    Style::default().fg(Color::Green),
+                    format!("+{} -{} in styles.css", summary.output_added, summary.output_removed),
+                )
+            }
+            Outcome::Unchanged => (Style::default().fg(Color::DarkGray), "no stylesheet change".to_string()),
+            Outcome::Removed => (Style::default().fg(Color::Yellow), "removed".to_string()),
+            Outcome::Failed(message) => (Style::default().fg(Color::Red), message.clone()),
+        };
+
+        self.events.push_front(Line::from(vec![
+            Span::styled(at, Style::default().fg(Color::DarkGray)),
+            Span::styled(path, Style::default().fg(Color::Blue)),
+            Span::raw(" "),
+            Span::styled(detail, style),
+        ]));
+        self.events.truncate(MAX_EVENTS);
+
+        if matches!(update.outcome, Outcome::Removed) {
+            self.timings.remove(&update.path);
+        } else {
+            self.timings.insert(update.path, update.duration);
+        }
+        self.files = update.files;
+        self.classnames = update.classnames;
+        self.ids = update.ids;
+        self.parse_errors = update.parse_errors;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, footer] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(8), Constraint::Percentage(35)]).areas(frame.area());
+        let [events, timings] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+        let [errors, css] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(footer);
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let error_style = if self.parse_errors.is_empty() {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+        let stats = Line::from(vec![
+            Span::styled(self.files.to_string(), bold.fg(Color::Yellow)),
+            Span::raw(" files  "),
+            Span::styled(self.classnames.to_string(), bold.fg(Color::Green)),
+            Span::raw(" classes  "),
+            Span::styled(self.ids.to_string(), bold.fg(Color::Green)),
+            Span::raw(" ids  "),
+            Span::styled(self.parse_errors.len().to_string(), error_style.add_modifier(Modifier::BOLD)),
+            Span::raw(" parse errors  "),
+            Span::styled("q to quit", Style::default().fg(Color::DarkGray)),
+        ]);
+        frame.render_widget(
+            Paragraph::new(stats).block(Block::bordered().title(" dx watch ").title_style(bold.fg(Color::Magenta))),
+            header,
+        );
+
+        frame.render_widget(
+            List::new(self.events.iter().cloned().map(ListItem::new)).block(Block::bordered().title(" Recent events ")),
+            events,
+        );
+
+        let mut slowest: Vec<_> = self.timings.iter().collect();
+        slowest.sort_by(|a, b| b.1.cmp(a.1));
+        let timing_items = slowest.into_iter().map(|(path, duration)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>9.2}ms ", duration.as_secs_f64() * 1000.0),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(self.display_path(path)),
+            ]))
+        });
+        frame.render_widget(
+            List::new(timing_items).block(Block::bordered().title(" Per-file timings ")),
+            timings,
+        );
+
+        let error_items = self.parse_errors.iter().map(|(path, message)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(self.display_path(path), Style::default().fg(Color::Blue)),
+                Span::raw(" "),
+                Span::styled(message.clone(), Style::default().fg(Color::Red)),
+            ]))
+        });
+        frame.render_widget(List::new(error_items).block(Block::bordered().title(" Parse errors ")), errors);
+
+        frame.render_widget(
+            List::new(self.css_tail.iter().cloned().map(ListItem::new)).block(Block::bordered().title(" CSS diff ")),
+            css,
+        );
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal, updates: &Receiver<Update>) -> io::Result<()> {
+        loop {
+            while let Ok(update) = updates.try_recv() {
+                self.apply(update);
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Watches ./src behind a full-screen dashboard until the user quits, forwarding every
+/// watch event to `on_event`.
+pub fn run(scanner: &mut Scanner, mut on_event: impl FnMut(&Scanner, WatchEvent) + Send) -> io::Result<()> {
+    let dashboard = Dashboard::new(scanner);
+    let extension = scanner.options.syntax.extension();
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+
+    let mut terminal = ratatui::init();
+    let result = thread::scope(|scope| {
+        scope.spawn(|| {
+            watch_changes(extension, &stop, |mut paths| {
+                for stale in scanner.stale_quarantine() {
+                    if !paths.contains(&stale) {
+                        paths.push(stale);
+                    }
+                }
+                for path in paths {
+                    let start = Instant::now();
+                    let outcome = match process_path(scanner, &path, &mut on_event) {
+                        Some(summary) => Outcome::Changed(summary),
+                        None if !path.exists() => Outcome::Removed,
+                        None => match scanner.parse_errors.get(&path) {
+                            Some(diagnostic) => Outcome::Failed(diagnostic.message.clone()),
+                            None => Outcome::Unchanged,
+                        },
+                    };
+                    let _ = tx.send(Update {
+                        duration: start.elapsed(),
+                        path,
+                        outcome,
+                        files: scanner.file_map.len(),
+                        classnames: scanner.classnames.len(),
+                        ids: scanner.ids.len(),
+                        parse_errors: parse_errors(scanner),
+                    });
+                }
+            });
+        });
+
+        let result = dashboard.run(&mut terminal, &rx);
+        stop.store(true, Ordering::Relaxed);
+        result
+    });
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const UNDO_DIR: &str = ".dx/undo";
+
+/// Saved versions kept before the oldest are dropped.
+const MAX_ENTRIES: usize = 500;
+
+/// A file's content before one rewrite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub path: PathBuf,
+    /// Milliseconds since the Unix epoch when the batch this entry belongs to was written.
+    pub batch: u64,
+    /// `None` when the rewrite created the file.
+    pub content: Option<String>,
+    #[serde(skip)]
+    file: PathBuf,
+}
+
+/// The rolling journal of pre-rewrite contents in `.dx/undo/`, one JSON file per saved version,
+/// named so they sort oldest first.
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(root: &Path) -> Self {
+        Journal { dir: root.join(UNDO_DIR) }
+    }
+
+    /// Saves the current content of `paths` as one batch, before they are rewritten.
+    pub fn record(&self, paths: &[&Path]) -> io::Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let mut batch = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64);
+        // Batches recorded within the same millisecond still get their own number.
+        while self.dir.join(format!("{:020}-{:04}.json", batch, 0)).exists() {
+            batch += 1;
+        }
+        for (i, path) in paths.iter().enumerate() {
+            let entry = UndoEntry {
+                path: path.to_path_buf(),
+                batch,
+                content: std::fs::read_to_string(path).ok(),
+                file: PathBuf::new(),
+            };
+            let name = format!("{:020}-{:04}.json", batch, i);
+            std::fs::write(self.dir.join(name), serde_json::to_string(&entry)?)?;
+        }
+        self.prune()
+    }
+
+    fn prune(&self) -> io::Result<()> {
+        let entries = self.entries();
+        for entry in entries.iter().take(entries.len().saturating_sub(MAX_ENTRIES)) {
+            std::fs::remove_file(&entry.file)?;
+        }
+        Ok(())
+    }
+
+    /// Every saved version, oldest first.
+    pub fn entries(&self) -> Vec<UndoEntry> {
+        let Ok(dir) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        files
+            .into_iter()
+            .filter_map(|file| {
+                let json = std::fs::read_to_string(&file).ok()?;
+                let entry: UndoEntry = serde_json::from_str(&json).ok()?;
+                Some(UndoEntry { file, ..entry })
+            })
+            .collect()
+    }
+
+    /// The entries of the `count` most recent batches, newest first.
+    pub fn last_batches(&self, count: usize) -> Vec<UndoEntry> {
+        let mut batches: BTreeMap<u64, Vec<UndoEntry>> = BTreeMap::new();
+        for entry in self.entries() {
+            batches.entry(entry.batch).or_default().push(entry);
+        }
+        batches.into_values().rev().take(count).flatten().collect()
+    }
+
+    /// The most recent entry for `path`.
+    pub fn last_for(&self, path: &Path) -> Option<UndoEntry> {
+        self.entries().into_iter().rev().find(|entry| entry.path == path)
+    }
+
+    /// Puts `entry`'s content back (deleting a file the rewrite created) and drops it from the
+    /// journal. Restoring entries newest first leaves each file at its oldest restored version.
+    pub fn restore(&self, entry: &UndoEntry) -> io::Result<()> {
+        match &entry.content {
+            Some(content) => std::fs::write(&entry.path, content)?,
+            None if entry.path.exists() => std::fs::remove_file(&entry.path)?,
+            None => {}
+        }
+        std::fs::remove_file(&entry.file)
+    }
+}
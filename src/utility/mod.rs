@@ -0,0 +1,671 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// A class name split into its variants (`hover:`, `md:`, ...) and the utility they apply to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedClass<'a> {
+    pub variants: Vec<&'a str>,
+    pub utility: &'a str,
+}
+
+/// Splits `classname` on the `:` separators outside of `[...]` arbitrary values.
+pub fn parse_class(classname: &str) -> ParsedClass<'_> {
+    let mut variants = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in classname.char_indices() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => {
+                variants.push(&classname[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ParsedClass {
+        variants,
+        utility: &classname[start..],
+    }
+}
+
+/// Escapes `name` for use as a class or id selector, e.g. `hover:p-4` as `hover\:p-4`.
+pub fn escape_class(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        let leading_digit = i == 0 && c.is_ascii_digit();
+        let second_digit_after_dash = i == 1 && c.is_ascii_digit() && name.starts_with('-');
+        if leading_digit || second_digit_after_dash {
+            write!(escaped, "\\{:x} ", c as u32).unwrap();
+        } else if c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+            escaped.push(c);
+        } else {
+            escaped.push('\\');
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Pseudo-classes for state variants, in the order Tailwind emits them.
+const PSEUDO_CLASS_VARIANTS: &[(&str, &str)] = &[
+    ("first", ":first-child"),
+    ("last", ":last-child"),
+    ("only", ":only-child"),
+    ("odd", ":nth-child(odd)"),
+    ("even", ":nth-child(even)"),
+    ("first-of-type", ":first-of-type"),
+    ("last-of-type", ":last-of-type"),
+    ("empty", ":empty"),
+    ("disabled", ":disabled"),
+    ("enabled", ":enabled"),
+    ("checked", ":checked"),
+    ("required", ":required"),
+    ("invalid", ":invalid"),
+    ("read-only", ":read-only"),
+    ("visited", ":visited"),
+    ("focus-within", ":focus-within"),
+    ("hover", ":hover"),
+    ("focus", ":focus"),
+    ("focus-visible", ":focus-visible"),
+    ("active", ":active"),
+];
+
+const ARIA_STATES: &[&str] = &[
+    "busy", "checked", "disabled", "expanded", "hidden", "pressed", "readonly", "required", "selected",
+];
+
+/// The attribute selector for `aria-*` and `data-*` variants: `aria-expanded` is
+/// `[aria-expanded="true"]`, `aria-[sort=ascending]` and `data-[state=open]` name the attribute
+/// and value, and a bare `data-active` only requires the attribute.
+fn attribute_selector(variant: &str) -> Option<String> {
+    let (namespace, rest) = variant.split_once('-')?;
+    if namespace != "aria" && namespace != "data" {
+        return None;
+    }
+    if let Some(arbitrary) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+        return Some(match arbitrary.split_once('=') {
+            Some((name, value)) => {
+                let value = value.trim_matches(['"', '\'']).replace('_', " ");
+                format!("[{}-{}=\"{}\"]", namespace, name, value)
+            }
+            None => format!("[{}-{}]", namespace, arbitrary),
+        });
+    }
+    match namespace {
+        "aria" if ARIA_STATES.contains(&rest) => Some(format!("[aria-{}=\"true\"]", rest)),
+        "data" if !rest.is_empty() => Some(format!("[data-{}]", rest)),
+        _ => None,
+    }
+}
+
+/// The selector suffix for a state: a pseudo-class (`hover`) or attribute (`aria-expanded`).
+fn state_suffix(state: &str) -> Option<String> {
+    pseudo_class(state).map(str::to_string).or_else(|| attribute_selector(state))
+}
+
+/// Pseudo-elements for `before:`-style variants; they always end the selector.
+const PSEUDO_ELEMENT_VARIANTS: &[(&str, &str)] = &[
+    ("placeholder", "::placeholder"),
+    ("file", "::file-selector-button"),
+    ("marker", "::marker"),
+    ("selection", "::selection"),
+    ("first-line", "::first-line"),
+    ("first-letter", "::first-letter"),
+    ("backdrop", "::backdrop"),
+    ("before", "::before"),
+    ("after", "::after"),
+];
+
+pub fn pseudo_element(variant: &str) -> Option<&'static str> {
+    PSEUDO_ELEMENT_VARIANTS
+        .iter()
+        .find(|(name, _)| *name == variant)
+        .map(|(_, pseudo)| *pseudo)
+}
+
+pub fn pseudo_class(variant: &str) -> Option<&'static str> {
+    PSEUDO_CLASS_VARIANTS
+        .iter()
+        .find(|(name, _)| *name == variant)
+        .map(|(_, pseudo)| *pseudo)
+}
+
+/// Where a variant sorts among the others: pseudo-elements, then pseudo-classes; unknown
+/// variants sort last.
+pub fn variant_rank(variant: &str) -> usize {
+    let variant = parent_variant(variant).map_or(variant, |(_, state, _)| state);
+    let elements = PSEUDO_ELEMENT_VARIANTS.iter().map(|(name, _)| name);
+    let classes = PSEUDO_CLASS_VARIANTS.iter().map(|(name, _)| name);
+    elements
+        .chain(classes)
+        .position(|name| *name == variant)
+        .map(|i| i + 1)
+        .unwrap_or(PSEUDO_ELEMENT_VARIANTS.len() + PSEUDO_CLASS_VARIANTS.len() + 1)
+}
+
+/// Marker classes and the combinator joining them to the styled element: `group` on an
+/// ancestor, `peer` on an earlier sibling.
+const MARKERS: &[(&str, &str)] = &[("group", " "), ("peer", " ~ ")];
+
+/// Classes that only exist for parent- or sibling-state variants to refer to, e.g. `group`,
+/// `group/card` or `peer`.
+pub fn is_marker(classname: &str) -> bool {
+    MARKERS.iter().any(|(marker, _)| {
+        classname
+            .strip_prefix(marker)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+/// Splits a state variant such as `group-hover`, `group-focus/card` or `peer-checked` into its
+/// marker class (`group`, `group/card`, `peer`), state and the combinator joining the two.
+fn parent_variant(variant: &str) -> Option<(String, &str, &'static str)> {
+    MARKERS.iter().find_map(|(marker, combinator)| {
+        let rest = variant.strip_prefix(marker)?.strip_prefix('-')?;
+        let (state, marker) = match rest.split_once('/') {
+            Some((state, name)) => (state, format!("{}/{}", marker, name)),
+            None => (rest, marker.to_string()),
+        };
+        Some((marker, state, *combinator))
+    })
+}
+
+/// The selector for `classname`, with class names (its own and markers) passed through
+/// `rename` (e.g. to production names), the pseudo-classes of its state variants and the
+/// ancestor or sibling selectors of its `group-*` and `peer-*` variants.
+///
+/// Attribute variants (`aria-expanded`, `data-[state=open]`) add attribute selectors.
+pub fn class_selector(classname: &str, rename: impl Fn(&str) -> String) -> String {
+    let mut parents = String::new();
+    let mut selector = format!(".{}", escape_class(&rename(classname)));
+    let variants = parse_class(classname).variants;
+    for variant in &variants {
+        if let Some((marker, state, combinator)) = parent_variant(variant) {
+            if let Some(suffix) = state_suffix(state) {
+                write!(parents, ".{}{}{}", escape_class(&rename(&marker)), suffix, combinator).unwrap();
+            }
+        } else if let Some(suffix) = state_suffix(variant) {
+            selector.push_str(&suffix);
+        }
+    }
+    if let Some(pseudo) = variants.iter().rev().find_map(|variant| pseudo_element(variant)) {
+        selector.push_str(pseudo);
+    }
+    parents + selector.as_str()
+}
+
+/// What a single variant adds to a rule, e.g. `:hover`, `@media (width >= 768px)` or
+/// `.group:hover ` for `group-hover`, or `None` if dx does not know it.
+pub fn variant_expansion(variant: &str, screens: &[(String, String)]) -> Option<String> {
+    let (bound, name) = match variant.strip_prefix("max-") {
+        Some(name) => ("<", name),
+        None => (">=", variant),
+    };
+    if let Some((_, width)) = screens.iter().find(|(screen, _)| screen == name) {
+        return Some(format!("@media (width {} {})", bound, width));
+    }
+    if let Some((marker, state, combinator)) = parent_variant(variant) {
+        return state_suffix(state).map(|suffix| format!(".{}{}{}", escape_class(&marker), suffix, combinator));
+    }
+    pseudo_element(variant).map(str::to_string).or_else(|| state_suffix(variant))
+}
+
+/// `classname` with `prefix` removed from its utility (`md:!tw-p-4` becomes `md:!p-4`), or
+/// `None` if the utility does not carry it.
+pub fn strip_class_prefix(classname: &str, prefix: &str, important_modifier: &str) -> Option<String> {
+    if prefix.is_empty() {
+        return Some(classname.to_string());
+    }
+    let utility = parse_class(classname).utility;
+    let variants = &classname[..classname.len() - utility.len()];
+    let (important, rest) = match utility.strip_prefix(important_modifier).filter(|_| !important_modifier.is_empty()) {
+        Some(rest) => (important_modifier, rest),
+        None => ("", utility),
+    };
+    let (negative, rest) = match rest.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", rest),
+    };
+    let rest = rest.strip_prefix(prefix)?;
+    Some(format!("{}{}{}{}", variants, important, negative, rest))
+}
+
+/// Breakpoints ordered by width, e.g. from `[css] screens`.
+pub fn sorted_screens(screens: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    let mut sorted: Vec<_> = screens.iter().map(|(name, width)| (name.clone(), width.clone())).collect();
+    sorted.sort_by(|(_, a), (_, b)| {
+        let leading_number = |width: &str| {
+            let end = width.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(width.len());
+            width[..end].parse::<f64>().unwrap_or(f64::MAX)
+        };
+        leading_number(a).total_cmp(&leading_number(b))
+    });
+    sorted
+}
+
+/// Orders responsive rules: `max-*` from the widest breakpoint down, then plain breakpoints
+/// (and ranges) from the narrowest up, so narrower `min-width` rules never override wider ones.
+pub type MediaOrder = (bool, usize, usize);
+
+/// The media query for a class's responsive variants, e.g. `(width >= 768px)` for `md:` or
+/// `(width >= 768px) and (width < 1024px)` for `md:max-lg:`.
+pub fn media_query(classname: &str, screens: &[(String, String)]) -> Option<(String, MediaOrder)> {
+    let mut min: Option<usize> = None;
+    let mut max: Option<usize> = None;
+    for variant in parse_class(classname).variants {
+        let (bound, name) = match variant.strip_prefix("max-") {
+            Some(name) => (&mut max, name),
+            None => (&mut min, variant),
+        };
+        if let Some(index) = screens.iter().position(|(screen, _)| screen == name) {
+            *bound = Some(index);
+        }
+    }
+
+    let mut conditions = Vec::new();
+    if let Some(index) = min {
+        conditions.push(format!("(width >= {})", screens[index].1));
+    }
+    if let Some(index) = max {
+        conditions.push(format!("(width < {})", screens[index].1));
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    let order = (
+        min.is_some(),
+        min.unwrap_or(0),
+        max.map_or(0, |index| screens.len() - index),
+    );
+    Some((conditions.join(" and "), order))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Layout,
+    FlexGrid,
+    Spacing,
+    Sizing,
+    Typography,
+    Background,
+    Border,
+    Effects,
+    Transforms,
+    Interactivity,
+    Other,
+}
+
+const CATEGORY_PREFIXES: &[(&str, Category)] = &[
+    ("container", Category::Layout),
+    ("block", Category::Layout),
+    ("inline", Category::Layout),
+    ("hidden", Category::Layout),
+    ("static", Category::Layout),
+    ("fixed", Category::Layout),
+    ("absolute", Category::Layout),
+    ("relative", Category::Layout),
+    ("sticky", Category::Layout),
+    ("inset", Category::Layout),
+    ("top", Category::Layout),
+    ("right", Category::Layout),
+    ("bottom", Category::Layout),
+    ("left", Category::Layout),
+    ("z-", Category::Layout),
+    ("overflow", Category::Layout),
+    ("visible", Category::Layout),
+    ("invisible", Category::Layout),
+    ("flex", Category::FlexGrid),
+    ("grid", Category::FlexGrid),
+    ("col-", Category::FlexGrid),
+    ("row-", Category::FlexGrid),
+    ("gap", Category::FlexGrid),
+    ("justify", Category::FlexGrid),
+    ("items", Category::FlexGrid),
+    ("content-", Category::FlexGrid),
+    ("self-", Category::FlexGrid),
+    ("place", Category::FlexGrid),
+    ("order", Category::FlexGrid),
+    ("grow", Category::FlexGrid),
+    ("shrink", Category::FlexGrid),
+    ("basis", Category::FlexGrid),
+    ("space-", Category::Spacing),
+    ("p", Category::Spacing),
+    ("m", Category::Spacing),
+    ("w-", Category::Sizing),
+    ("h-", Category::Sizing),
+    ("min-", Category::Sizing),
+    ("max-", Category::Sizing),
+    ("size-", Category::Sizing),
+    ("font", Category::Typography),
+    ("text", Category::Typography),
+    ("leading", Category::Typography),
+    ("tracking", Category::Typography),
+    ("uppercase", Category::Typography),
+    ("lowercase", Category::Typography),
+    ("capitalize", Category::Typography),
+    ("italic", Category::Typography),
+    ("underline", Category::Typography),
+    ("truncate", Category::Typography),
+    ("whitespace", Category::Typography),
+    ("bg", Category::Background),
+    ("from-", Category::Background),
+    ("via-", Category::Background),
+    ("to-", Category::Background),
+    ("border", Category::Border),
+    ("rounded", Category::Border),
+    ("outline", Category::Border),
+    ("ring", Category::Border),
+    ("divide", Category::Border),
+    ("shadow", Category::Effects),
+    ("opacity", Category::Effects),
+    ("blur", Category::Effects),
+    ("transition", Category::Effects),
+    ("duration", Category::Effects),
+    ("ease", Category::Effects),
+    ("animate", Category::Effects),
+    ("scale", Category::Transforms),
+    ("rotate", Category::Transforms),
+    ("translate", Category::Transforms),
+    ("skew", Category::Transforms),
+    ("origin", Category::Transforms),
+    ("transform", Category::Transforms),
+    ("cursor", Category::Interactivity),
+    ("select", Category::Interactivity),
+    ("pointer-events", Category::Interactivity),
+    ("resize", Category::Interactivity),
+    ("appearance", Category::Interactivity),
+];
+
+/// The category of a utility (without variants), by its longest matching known prefix.
+pub fn category(utility: &str) -> Category {
+    let utility = utility.trim_start_matches('-');
+    CATEGORY_PREFIXES
+        .iter()
+        .filter(|(prefix, _)| {
+            utility.starts_with(prefix)
+                && (prefix.len() > 1 || utility.len() == 1 || {
+                    // Single-letter prefixes (`p`, `m`) only match spacing shorthands like `px-2` or `mt-4`.
+                    // Compared as bytes: the class may continue with any character, e.g. `pé`.
+                    let rest = utility[1..].as_bytes();
+                    rest.first() == Some(&b'-') || rest.len() >= 2 && b"xytrbl".contains(&rest[0]) && rest[1] == b'-'
+                })
+        })
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, category)| *category)
+        .unwrap_or(Category::Other)
+}
+
+/// Removes the `!important` modifier from the front or back of a utility (`!p-4`, `p-4!`).
+pub fn strip_important<'a>(utility: &'a str, modifier: &str) -> (bool, &'a str) {
+    if modifier.is_empty() {
+        return (false, utility);
+    }
+    if let Some(rest) = utility.strip_prefix(modifier) {
+        return (true, rest);
+    }
+    match utility.strip_suffix(modifier) {
+        Some(rest) => (true, rest),
+        None => (false, utility),
+    }
+}
+
+pub type Declarations = Vec<(Cow<'static, str>, String)>;
+
+fn spacing(value: &str) -> Option<String> {
+    if let Some(arbitrary) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Some(arbitrary.replace('_', " "));
+    }
+    match value {
+        "0" => Some("0px".to_string()),
+        "px" => Some("1px".to_string()),
+        _ => {
+            let steps: f64 = value.parse().ok()?;
+            Some(format!("{}rem", steps * 0.25))
+        }
+    }
+}
+
+fn size(value: &str, axis: &str) -> Option<String> {
+    match value {
+        "auto" => Some("auto".to_string()),
+        "full" => Some("100%".to_string()),
+        "screen" => Some(format!("100{}", axis)),
+        "min" => Some("min-content".to_string()),
+        "max" => Some("max-content".to_string()),
+        "fit" => Some("fit-content".to_string()),
+        _ => match value.split_once('/') {
+            Some((numerator, denominator)) => {
+                let ratio = numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?;
+                Some(format!("{}%", (ratio * 1_000_000.0).round() / 10_000.0))
+            }
+            None => spacing(value),
+        },
+    }
+}
+
+const SPACING_PROPERTIES: &[(&str, &[&str])] = &[
+    ("p", &["padding"]),
+    ("px", &["padding-left", "padding-right"]),
+    ("py", &["padding-top", "padding-bottom"]),
+    ("pt", &["padding-top"]),
+    ("pr", &["padding-right"]),
+    ("pb", &["padding-bottom"]),
+    ("pl", &["padding-left"]),
+    ("m", &["margin"]),
+    ("mx", &["margin-left", "margin-right"]),
+    ("my", &["margin-top", "margin-bottom"]),
+    ("mt", &["margin-top"]),
+    ("mr", &["margin-right"]),
+    ("mb", &["margin-bottom"]),
+    ("ml", &["margin-left"]),
+    ("gap", &["gap"]),
+    ("gap-x", &["column-gap"]),
+    ("gap-y", &["row-gap"]),
+    ("inset", &["inset"]),
+    ("inset-x", &["left", "right"]),
+    ("inset-y", &["top", "bottom"]),
+    ("top", &["top"]),
+    ("right", &["right"]),
+    ("bottom", &["bottom"]),
+    ("left", &["left"]),
+];
+
+const STATIC_UTILITIES: &[(&str, &str, &str)] = &[
+    ("block", "display", "block"),
+    ("inline-block", "display", "inline-block"),
+    ("inline", "display", "inline"),
+    ("flex", "display", "flex"),
+    ("inline-flex", "display", "inline-flex"),
+    ("grid", "display", "grid"),
+    ("inline-grid", "display", "inline-grid"),
+    ("contents", "display", "contents"),
+    ("hidden", "display", "none"),
+    ("static", "position", "static"),
+    ("fixed", "position", "fixed"),
+    ("absolute", "position", "absolute"),
+    ("relative", "position", "relative"),
+    ("sticky", "position", "sticky"),
+    ("visible", "visibility", "visible"),
+    ("invisible", "visibility", "hidden"),
+    ("flex-row", "flex-direction", "row"),
+    ("flex-col", "flex-direction", "column"),
+    ("flex-wrap", "flex-wrap", "wrap"),
+    ("flex-1", "flex", "1 1 0%"),
+    ("flex-auto", "flex", "1 1 auto"),
+    ("flex-none", "flex", "none"),
+    ("grow", "flex-grow", "1"),
+    ("grow-0", "flex-grow", "0"),
+    ("shrink", "flex-shrink", "1"),
+    ("shrink-0", "flex-shrink", "0"),
+    ("items-start", "align-items", "flex-start"),
+    ("items-center", "align-items", "center"),
+    ("items-end", "align-items", "flex-end"),
+    ("items-stretch", "align-items", "stretch"),
+    ("justify-start", "justify-content", "flex-start"),
+    ("justify-center", "justify-content", "center"),
+    ("justify-end", "justify-content", "flex-end"),
+    ("justify-between", "justify-content", "space-between"),
+    ("justify-around", "justify-content", "space-around"),
+    ("text-left", "text-align", "left"),
+    ("text-center", "text-align", "center"),
+    ("text-right", "text-align", "right"),
+    ("font-light", "font-weight", "300"),
+    ("font-normal", "font-weight", "400"),
+    ("font-medium", "font-weight", "500"),
+    ("font-semibold", "font-weight", "600"),
+    ("font-bold", "font-weight", "700"),
+    ("italic", "font-style", "italic"),
+    ("uppercase", "text-transform", "uppercase"),
+    ("lowercase", "text-transform", "lowercase"),
+    ("capitalize", "text-transform", "capitalize"),
+    ("underline", "text-decoration-line", "underline"),
+    ("overflow-hidden", "overflow", "hidden"),
+    ("overflow-auto", "overflow", "auto"),
+    ("overflow-scroll", "overflow", "scroll"),
+    ("cursor-pointer", "cursor", "pointer"),
+    ("pointer-events-none", "pointer-events", "none"),
+    ("select-none", "user-select", "none"),
+    ("rounded", "border-radius", "0.25rem"),
+    ("rounded-full", "border-radius", "9999px"),
+    ("border", "border-width", "1px"),
+];
+
+/// Utilities whose values may be negated with a leading `-`, e.g. `-mt-4` or `-z-10`.
+const NEGATABLE_PREFIXES: &[&str] = &[
+    "m", "mx", "my", "mt", "mr", "mb", "ml", "inset", "inset-x", "inset-y", "top", "right", "bottom", "left", "z", "order",
+];
+
+fn negate(value: &str) -> Option<String> {
+    match value {
+        "0" | "0px" => Some(value.to_string()),
+        "auto" => None,
+        _ if value.starts_with(|c: char| c.is_ascii_digit() || c == '.') => Some(format!("-{}", value)),
+        _ if value.starts_with('-') => Some(value[1..].to_string()),
+        _ => Some(format!("calc({} * -1)", value)),
+    }
+}
+
+/// The declarations a utility (without variants or modifiers) stands for, if dx knows it.
+///
+/// A leading `-` negates the value of spacing, inset, `z` and `order` utilities, and an arbitrary
+/// property such as `[color:#333]` or `[grid-template-columns:1fr_2fr]` sets that property as is.
+pub fn declarations(utility: &str) -> Option<Declarations> {
+    if let Some((property, value)) = arbitrary_property(utility) {
+        return Some(vec![(Cow::Owned(property.to_string()), value)]);
+    }
+    let declarations = known_declarations(utility)?;
+    Some(declarations.into_iter().map(|(property, value)| (Cow::Borrowed(property), value)).collect())
+}
+
+/// `[property:value]`, with `_` in the value standing for a space.
+fn arbitrary_property(utility: &str) -> Option<(&str, String)> {
+    let (property, value) = utility.strip_prefix('[')?.strip_suffix(']')?.split_once(':')?;
+    let valid = !property.is_empty() && property.chars().all(|c| c.is_ascii_lowercase() || c == '-');
+    (valid && !value.is_empty()).then(|| (property, value.replace('_', " ")))
+}
+
+fn known_declarations(utility: &str) -> Option<Vec<(&'static str, String)>> {
+    if let Some(positive) = utility.strip_prefix('-').filter(|rest| !rest.starts_with('-')) {
+        let negatable = NEGATABLE_PREFIXES
+            .iter()
+            .any(|prefix| positive.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('-')));
+        if !negatable {
+            return None;
+        }
+        return known_declarations(positive)?
+            .into_iter()
+            .map(|(property, value)| Some((property, negate(&value)?)))
+            .collect();
+    }
+
+    if let Some((_, property, value)) = STATIC_UTILITIES.iter().find(|(name, _, _)| *name == utility) {
+        return Some(vec![(*property, value.to_string())]);
+    }
+
+    let (prefix, value) = SPACING_PROPERTIES
+        .iter()
+        .filter_map(|(prefix, _)| Some((*prefix, utility.strip_prefix(prefix)?.strip_prefix('-')?)))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .unwrap_or(("", utility));
+    if !prefix.is_empty() {
+        let properties = SPACING_PROPERTIES.iter().find(|(p, _)| *p == prefix)?.1;
+        let value = match value {
+            "auto" if !prefix.starts_with(['p', 'g']) => "auto".to_string(),
+            _ if prefix.starts_with(['p', 'm', 'g']) => spacing(value)?,
+            _ => size(value, "%")?,
+        };
+        return Some(properties.iter().map(|property| (*property, value.clone())).collect());
+    }
+
+    let (prefix, value) = utility.split_once('-')?;
+    let declaration = match prefix {
+        "w" => ("width", size(value, "vw")?),
+        "h" => ("height", size(value, "vh")?),
+        "size" => return Some(vec![("width", size(value, "vw")?), ("height", size(value, "vh")?)]),
+        "opacity" => ("opacity", format!("{}", value.parse::<f64>().ok()? / 100.0)),
+        "z" => ("z-index", value.parse::<i32>().ok()?.to_string()),
+        "order" => ("order", value.parse::<i32>().ok()?.to_string()),
+        "content" if value == "none" => ("content", "none".to_string()),
+        "content" => {
+            let arbitrary = value.strip_prefix('[')?.strip_suffix(']')?;
+            ("content", arbitrary.replace('_', " "))
+        }
+        _ => return None,
+    };
+    Some(vec![declaration])
+}
+
+/// The declarations for a full class name and whether they are `!important`.
+///
+/// `before:` and `after:` rules get `content: ""` unless the utility sets `content` itself, so
+/// the pseudo-element renders.
+pub fn class_declarations(classname: &str, important_modifier: &str) -> (Declarations, bool) {
+    let parsed = parse_class(classname);
+    let (important, utility) = strip_important(parsed.utility, important_modifier);
+    let mut declarations = declarations(utility).unwrap_or_default();
+    let generated_content = parsed.variants.iter().any(|v| *v == "before" || *v == "after");
+    if generated_content && !declarations.iter().any(|(property, _)| *property == "content") {
+        declarations.insert(0, (Cow::Borrowed("content"), "\"\"".to_string()));
+    }
+    (declarations, important)
+}
+
+/// The rule body for `declarations`, e.g. `{ padding: 1rem; }`, or `{}` for none.
+pub fn format_body(declarations: &[(Cow<'static, str>, String)], important: bool) -> String {
+    if declarations.is_empty() {
+        return "{}".to_string();
+    }
+    let suffix = if important { " !important" } else { "" };
+    let mut body = String::from("{");
+    for (property, value) in declarations {
+        write!(body, " {}: {}{};", property, value, suffix).unwrap();
+    }
+    body.push_str(" }");
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category_matches_spacing_shorthands() {
+        assert_eq!(category("p-4"), Category::Spacing);
+        assert_eq!(category("px-2"), Category::Spacing);
+        assert_eq!(category("-mt-2"), Category::Spacing);
+        assert_eq!(category("m"), Category::Spacing);
+        assert_eq!(category("place-items-center"), Category::FlexGrid);
+        assert_eq!(category("pointer-events-none"), Category::Interactivity);
+        assert_eq!(category("pb"), Category::Other);
+    }
+
+    #[test]
+    fn category_accepts_non_ascii_classes() {
+        assert_eq!(category("pé"), Category::Other);
+        assert_eq!(category("mé-4"), Category::Other);
+        assert_eq!(category("p-é"), Category::Spacing);
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+use crate::config::SourceSyntax;
+use crate::css::{render_css, CssOptions};
+use crate::extract::{
+    extract_from_source, extract_sources, try_extract_from_source, Diagnostic, EntitySpan, IdUpdate, Options,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TransformOptions {
+    groups: HashMap<String, String>,
+    syntax: Option<SourceSyntax>,
+    attributes: Option<Vec<String>>,
+    helpers: Option<Vec<String>>,
+    group_transform: Option<bool>,
+    id_generation: Option<bool>,
+    obfuscate: bool,
+}
+
+impl From<TransformOptions> for Options {
+    fn from(options: TransformOptions) -> Self {
+        let defaults = Options::default();
+        Options {
+            groups: options.groups,
+            syntax: options.syntax.unwrap_or(defaults.syntax),
+            attributes: options.attributes.unwrap_or(defaults.attributes),
+            helpers: options.helpers.unwrap_or(defaults.helpers),
+            transform_groups: options.group_transform.unwrap_or(defaults.transform_groups),
+            generate_ids: options.id_generation.unwrap_or(defaults.generate_ids),
+            obfuscate: options.obfuscate,
+            ..defaults
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TransformOutput {
+    code: String,
+    classnames: Vec<String>,
+    ids: Vec<String>,
+    id_updates: Vec<IdUpdate>,
+    spans: Vec<EntitySpan>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectFileOutput {
+    path: String,
+    code: Option<String>,
+    classnames: Vec<String>,
+    ids: Vec<String>,
+    id_updates: Vec<IdUpdate>,
+    spans: Vec<EntitySpan>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectOutput {
+    classnames: Vec<String>,
+    ids: Vec<String>,
+    files: Vec<ProjectFileOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CssInput {
+    Classnames(Vec<String>),
+    Entities {
+        #[serde(default)]
+        classnames: Vec<String>,
+        #[serde(default)]
+        ids: Vec<String>,
+    },
+}
+
+fn sorted(set: HashSet<String>) -> Vec<String> {
+    let mut values: Vec<_> = set.into_iter().collect();
+    values.sort();
+    values
+}
+
+fn options_from_js(options: JsValue) -> Result<Options, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(Options::default());
+    }
+    let options: TransformOptions = serde_wasm_bindgen::from_value(options)?;
+    Ok(options.into())
+}
+
+#[wasm_bindgen]
+pub fn process_tsx(input: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    let options = options_from_js(options)?;
+    let classnames = extract_from_source(input, &options)
+        .map(|extraction| sorted(extraction.classnames))
+        .unwrap_or_default();
+    Ok(serde_wasm_bindgen::to_value(&classnames)?)
+}
+
+#[wasm_bindgen]
+pub fn transform_tsx(input: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    let options = options_from_js(options)?;
+    let output = match try_extract_from_source(input, &options) {
+        Ok(extraction) => TransformOutput {
+            spans: extraction.spans(),
+            code: extraction.code,
+            classnames: sorted(extraction.classnames),
+            ids: sorted(extraction.ids),
+            id_updates: extraction.id_updates,
+            diagnostics: extraction.diagnostics,
+        },
+        Err(diagnostic) => TransformOutput {
+            code: input.to_string(),
+            classnames: Vec::new(),
+            ids: Vec::new(),
+            id_updates: Vec::new(),
+            spans: Vec::new(),
+            diagnostics: vec![diagnostic],
+        },
+    };
+    Ok(serde_wasm_bindgen::to_value(&output)?)
+}
+
+/// Processes every `path -> source` entry of a `Map` or plain object in one
+/// call. `code` is only set for files whose source would be rewritten.
+#[wasm_bindgen]
+pub fn process_project(files: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+    let files: BTreeMap<String, String> = serde_wasm_bindgen::from_value(files)?;
+    let options = options_from_js(options)?;
+
+    let mut classnames = BTreeSet::new();
+    let mut ids = BTreeSet::new();
+    let mut outputs = Vec::with_capacity(files.len());
+
+    let inputs = files.iter().map(|(path, source)| (path.as_str(), source.as_str()));
+    for (path, result) in extract_sources(inputs, &options) {
+        let output = match result {
+            Ok(extraction) => {
+                classnames.extend(extraction.classnames.iter().cloned());
+                ids.extend(extraction.ids.iter().cloned());
+                ProjectFileOutput {
+                    code: extraction.is_modified().then(|| extraction.code.clone()),
+                    spans: extraction.spans(),
+                    classnames: sorted(extraction.classnames),
+                    ids: sorted(extraction.ids),
+                    id_updates: extraction.id_updates,
+                    diagnostics: extraction.diagnostics,
+                    path,
+                }
+            }
+            Err(diagnostic) => ProjectFileOutput {
+                path,
+                code: None,
+                classnames: Vec::new(),
+                ids: Vec::new(),
+                id_updates: Vec::new(),
+                spans: Vec::new(),
+                diagnostics: vec![diagnostic],
+            },
+        };
+        outputs.push(output);
+    }
+
+    let output = ProjectOutput {
+        classnames: classnames.into_iter().collect(),
+        ids: ids.into_iter().collect(),
+        files: outputs,
+    };
+    Ok(serde_wasm_bindgen::to_value(&output)?)
+}
+
+/// Accepts either an array of class names or a `transform_tsx` result and
+/// returns the stylesheet the CLI would write for it.
+#[wasm_bindgen]
+pub fn generate_css(classnames: JsValue, options: JsValue) -> Result<String, JsValue> {
+    let (classnames, ids) = match serde_wasm_bindgen::from_value(classnames)? {
+        CssInput::Classnames(classnames) => (classnames, Vec::new()),
+        CssInput::Entities { classnames, ids } => (classnames, ids),
+    };
+    let options: CssOptions = if options.is_undefined() || options.is_null() {
+        CssOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)?
+    };
+
+    let classnames: HashSet<String> = classnames.into_iter().collect();
+    let ids: HashSet<String> = ids.into_iter().collect();
+    Ok(render_css(&classnames, &ids, &options))
+}
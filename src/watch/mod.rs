@@ -0,0 +1,581 @@
+use colored::*;
+use glob::{glob, Pattern};
+use notify::{Config as WatcherConfig, ErrorKind, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::extract::Diagnostic;
+use crate::metrics::METRICS;
+use crate::paths;
+use crate::scanner::{ChangeSummary, ScanSummary, Scanner};
+
+/// Set by `--verbose` to report watcher details such as how many watches were installed.
+pub static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// How long a target's queue may keep growing while events keep arriving (e.g. during a
+/// `git checkout`) before it is processed anyway.
+pub const COALESCE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Batches with more files than this are handled by one consolidated rebuild instead of file by
+/// file, so an event storm writes the stylesheet once.
+pub const MAX_BATCH: usize = 50;
+
+/// Where `--event-log` records go, once opened.
+static EVENT_LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Appends one NDJSON record per raw watcher event, debounce decision and processing outcome
+/// to `path`, for debugging changes that were not picked up.
+pub fn log_events_to(path: &Path) -> io::Result<()> {
+    let file = File::options().create(true).append(true).open(path)?;
+    let _ = EVENT_LOG.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Writes `record` with a millisecond Unix timestamp, if an event log is open.
+fn log_event(kind: &str, mut record: serde_json::Value) {
+    let Some(log) = EVENT_LOG.get() else {
+        return;
+    };
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    record["time"] = time.into();
+    record["kind"] = kind.into();
+    let mut file = log.lock().unwrap();
+    let _ = writeln!(file, "{}", record);
+}
+
+/// How often files that failed to parse are retried when no events arrive.
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Something worth reporting after a debounced change was processed.
+pub enum WatchEvent<'a> {
+    /// The stylesheet was rewritten.
+    Changed(&'a ChangeSummary),
+    /// A file that used to parse no longer does.
+    Failed { path: &'a Path, diagnostic: &'a Diagnostic },
+    /// A previously failing file parses again.
+    Recovered { path: &'a Path },
+    /// A source or stylesheet could not be written; it is retried on the next change.
+    Unwritable { path: &'a Path, diagnostic: &'a Diagnostic },
+    /// A previously unwritable file was written.
+    Written { path: &'a Path },
+    /// A file is now left out for its size or as binary.
+    Skipped { path: &'a Path, diagnostic: &'a Diagnostic },
+    /// A batch larger than [`MAX_BATCH`] was handled by one rebuild of this many changed files.
+    Rebuilt { files: usize, summary: &'a ScanSummary },
+}
+
+/// Watches ./src until `stop` is set, feeding debounced changes through the scanner.
+pub fn watch(
+    scanner: &mut Scanner,
+    stop: &AtomicBool,
+    mut on_change: impl FnMut(&Scanner, &ChangeSummary),
+) {
+    watch_events(scanner, stop, |scanner, event| {
+        if let WatchEvent::Changed(summary) = event {
+            on_change(scanner, summary);
+        }
+    });
+}
+
+/// Like [`watch`], but also reports files that start or stop failing to parse.
+pub fn watch_events(scanner: &mut Scanner, stop: &AtomicBool, mut on_event: impl FnMut(&Scanner, WatchEvent)) {
+    let (targets, dirs, _) = scanner_targets(std::slice::from_ref(scanner));
+    watch_targets(&targets, &dirs, stop, |_, paths| process_batch(scanner, &paths, &mut on_event));
+}
+
+/// Watches the `src/` directory (and followed symlink targets) of every scanner from one
+/// watcher, routing each changed file to the scanner whose root contains it.
+///
+/// Every target is debounced on its own and every scanner processes its batches on its own
+/// thread, so a flood of events in one package does not hold up the others.
+pub fn watch_scanners(
+    scanners: &mut [Scanner],
+    stop: &AtomicBool,
+    on_event: impl FnMut(&Scanner, WatchEvent) + Send,
+) {
+    let (targets, dirs, owners) = scanner_targets(scanners);
+    let on_event = Mutex::new(on_event);
+    thread::scope(|scope| {
+        let queues: Vec<mpsc::Sender<Vec<PathBuf>>> = scanners
+            .iter_mut()
+            .map(|scanner| {
+                let (tx, rx) = mpsc::channel::<Vec<PathBuf>>();
+                let on_event = &on_event;
+                scope.spawn(move || {
+                    let mut report = |scanner: &Scanner, event: WatchEvent| (*on_event.lock().unwrap())(scanner, event);
+                    while let Ok(mut paths) = rx.recv() {
+                        // Merge the batches that queued up while the last one was processed.
+                        paths.extend(rx.try_iter().flatten());
+                        paths.sort();
+                        paths.dedup();
+                        process_batch(scanner, &paths, &mut report);
+                    }
+                });
+                tx
+            })
+            .collect();
+        watch_targets(&targets, &dirs, stop, |target, paths| {
+            let _ = queues[owners[target]].send(paths);
+        });
+        // Closing the queues lets the workers finish.
+        drop(queues);
+    });
+}
+
+/// The watch targets of `scanners`, the directories to watch one by one instead of their
+/// roots recursively, and for each target the index of the scanner owning it.
+fn scanner_targets(scanners: &[Scanner]) -> (Vec<(PathBuf, &'static str)>, Vec<PathBuf>, Vec<usize>) {
+    let mut owners = Vec::new();
+    let mut targets = Vec::new();
+    let mut dirs = Vec::new();
+    for (i, scanner) in scanners.iter().enumerate() {
+        for root in scanner.watch_roots() {
+            owners.push(i);
+            targets.push((root, scanner.options.syntax.extension()));
+        }
+        dirs.extend(scanner.watch_dirs().unwrap_or_default());
+    }
+    (targets, dirs, owners)
+}
+
+/// Processes a debounced batch, or for an empty batch (the retry timer) only the quarantine.
+fn process_batch(scanner: &mut Scanner, paths: &[PathBuf], on_event: &mut impl FnMut(&Scanner, WatchEvent)) {
+    if paths.len() > MAX_BATCH {
+        rebuild(scanner, paths, on_event);
+        return;
+    }
+    for path in paths {
+        for alias in scanner.source_aliases(path) {
+            process_path(scanner, &alias, on_event);
+        }
+    }
+    retry_quarantined(scanner, paths, on_event);
+}
+
+/// Re-processes files that failed to parse and changed since their last attempt, other than
+/// those in `just_processed`, so a fix whose event was missed still brings their classes back.
+pub fn retry_quarantined(scanner: &mut Scanner, just_processed: &[PathBuf], on_event: &mut impl FnMut(&Scanner, WatchEvent)) {
+    for path in scanner.stale_quarantine() {
+        if !just_processed.contains(&path) {
+            process_path(scanner, &path, on_event);
+        }
+    }
+}
+
+/// Re-extracts every changed path (and every failing or skipped file, whose last good state
+/// the cache would otherwise stand in for) in one pass that writes the stylesheet at most once.
+pub fn rebuild(scanner: &mut Scanner, paths: &[PathBuf], on_event: &mut impl FnMut(&Scanner, WatchEvent)) -> ScanSummary {
+    let failing: Vec<PathBuf> = scanner.parse_errors.keys().cloned().collect();
+    let skipped: Vec<PathBuf> = scanner.skipped.keys().cloned().collect();
+    let unwritten: Vec<PathBuf> = scanner.write_errors.keys().cloned().collect();
+    let mut changed: Vec<PathBuf> = paths.iter().flat_map(|path| scanner.source_aliases(path)).collect();
+    changed.extend(failing.iter().chain(&skipped).cloned());
+    log_event("rebuild", serde_json::json!({ "files": paths.len() }));
+
+    let summary = scanner.scan_changed(&changed, |_, _| {});
+    for path in &changed {
+        scanner.update_symbols(path);
+    }
+    for (path, diagnostic) in &scanner.parse_errors {
+        if !failing.contains(path) {
+            on_event(scanner, WatchEvent::Failed { path, diagnostic });
+        }
+    }
+    for path in failing.iter().filter(|path| !scanner.parse_errors.contains_key(*path)) {
+        on_event(scanner, WatchEvent::Recovered { path });
+    }
+    for (path, diagnostic) in &scanner.skipped {
+        if !skipped.contains(path) {
+            on_event(scanner, WatchEvent::Skipped { path, diagnostic });
+        }
+    }
+    for (path, diagnostic) in &scanner.write_errors {
+        if !unwritten.contains(path) {
+            on_event(scanner, WatchEvent::Unwritable { path, diagnostic });
+        }
+    }
+    for written in unwritten.iter().filter(|path| !scanner.write_errors.contains_key(*path)) {
+        on_event(scanner, WatchEvent::Written { path: written });
+    }
+    on_event(scanner, WatchEvent::Rebuilt { files: paths.len(), summary: &summary });
+    summary
+}
+
+/// Runs one changed path through the scanner and reports the resulting events.
+pub fn process_path(
+    scanner: &mut Scanner,
+    path: &Path,
+    on_event: &mut impl FnMut(&Scanner, WatchEvent),
+) -> Option<ChangeSummary> {
+    let was_failing = scanner.parse_errors.contains_key(path);
+    let was_skipped = scanner.skipped.contains_key(path);
+    let unwritten: Vec<PathBuf> = scanner.write_errors.keys().cloned().collect();
+    let unchanged_events = scanner.unchanged_events;
+    let start = Instant::now();
+    let summary = scanner.process_change(path);
+    METRICS.change_duration.observe(start.elapsed());
+    scanner.update_symbols(path);
+    let unchanged = scanner.unchanged_events > unchanged_events;
+    if unchanged && VERBOSE.load(Ordering::Relaxed) {
+        println!("{} {} unchanged, nothing to do", "·".dimmed(), path.display());
+    }
+    if EVENT_LOG.get().is_some() {
+        let outcome = if unchanged {
+            "no-op"
+        } else if scanner.parse_errors.contains_key(path) {
+            "failed"
+        } else if scanner.skipped.contains_key(path) {
+            "skipped"
+        } else if summary.is_some() {
+            "changed"
+        } else {
+            "unchanged"
+        };
+        log_event(
+            "processed",
+            serde_json::json!({
+                "path": path,
+                "outcome": outcome,
+                "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+            }),
+        );
+    }
+    match scanner.parse_errors.get(path) {
+        Some(diagnostic) if !was_failing => on_event(scanner, WatchEvent::Failed { path, diagnostic }),
+        None if was_failing => on_event(scanner, WatchEvent::Recovered { path }),
+        _ => {}
+    }
+    if let Some(diagnostic) = scanner.skipped.get(path).filter(|_| !was_skipped) {
+        on_event(scanner, WatchEvent::Skipped { path, diagnostic });
+    }
+    for (failed, diagnostic) in &scanner.write_errors {
+        if !unwritten.contains(failed) {
+            on_event(scanner, WatchEvent::Unwritable { path: failed, diagnostic });
+        }
+    }
+    for written in unwritten.iter().filter(|path| !scanner.write_errors.contains_key(*path)) {
+        on_event(scanner, WatchEvent::Written { path: written });
+    }
+    if let Some(summary) = &summary {
+        on_event(scanner, WatchEvent::Changed(summary));
+    }
+    summary
+}
+
+/// Watches ./src until `stop` is set and hands every debounced batch of
+/// changed files with the given extension to `on_paths`.
+pub fn watch_changes(extension: &str, stop: &AtomicBool, mut on_paths: impl FnMut(Vec<PathBuf>)) {
+    let src = env::current_dir().unwrap().join("src");
+    watch_targets(&[(src, extension)], &[], stop, |_, paths| on_paths(paths));
+}
+
+/// Watches every target recursively, except roots listed in `dirs`, which are covered by
+/// watching each of `dirs` non-recursively.
+fn start_watcher(
+    targets: &[(PathBuf, &str)],
+    dirs: &[PathBuf],
+    tx: mpsc::Sender<notify::Result<Event>>,
+    poll: bool,
+) -> notify::Result<Box<dyn Watcher>> {
+    let config = WatcherConfig::default().with_poll_interval(Duration::from_millis(200));
+    let mut watcher: Box<dyn Watcher> = if poll {
+        Box::new(PollWatcher::new(tx, config)?)
+    } else {
+        Box::new(RecommendedWatcher::new(tx, config)?)
+    };
+    // A recursive watch still costs the OS one watch per directory below it.
+    let mut recursive = 0;
+    for (watch_path, _) in targets.iter().filter(|(root, _)| !dirs.contains(root)) {
+        watcher.watch(watch_path, RecursiveMode::Recursive)?;
+        recursive += paths::directories(watch_path, |_| false).len();
+    }
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    METRICS.watches.store((recursive + dirs.len()) as u64, Ordering::Relaxed);
+    if VERBOSE.load(Ordering::Relaxed) {
+        println!(
+            "{} Installed {} {} ({} through recursive roots, {} single directories)",
+            "👀".bold(),
+            recursive + dirs.len(),
+            if poll { "polled watches" } else { "watches" },
+            recursive,
+            dirs.len()
+        );
+    }
+    Ok(watcher)
+}
+
+/// Whether `error` means the OS ran out of watches (inotify's `max_user_watches` or
+/// `max_user_instances`) rather than something polling would not fix either.
+fn is_watch_limit(error: &notify::Error) -> bool {
+    match &error.kind {
+        ErrorKind::MaxFilesWatch => true,
+        // ENOSPC and EMFILE from inotify_add_watch / inotify_init.
+        ErrorKind::Io(e) => cfg!(target_os = "linux") && matches!(e.raw_os_error(), Some(28 | 24)),
+        _ => false,
+    }
+}
+
+/// Starts the polling backend after the native one failed with `error`. If polling cannot start
+/// either, no watcher is returned and [`watch_targets`] tries again every [`RETRY_INTERVAL`].
+fn polling_fallback(
+    targets: &[(PathBuf, &str)],
+    dirs: &[PathBuf],
+    tx: mpsc::Sender<notify::Result<Event>>,
+    error: &notify::Error,
+) -> Option<Box<dyn Watcher>> {
+    eprintln!("{} File watcher failed: {}; falling back to polling", "⚠".yellow(), error);
+    if is_watch_limit(error) {
+        eprintln!(
+            "  The inotify watch limit was reached. Raise it to get native events back, e.g.\n  \
+sudo sysctl fs.inotify.max_user_watches=524288 fs.inotify.max_user_instances=512\n  \
+(persist the settings in /etc/sysctl.d/ to keep them after a reboot)"
+        );
+    }
+    match start_watcher(targets, dirs, tx, true) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!(
+                "{} Failed to watch for changes: {}; retrying every {}s",
+                "✗".bright_red(),
+                e,
+                RETRY_INTERVAL.as_secs()
+            );
+            None
+        }
+    }
+}
+
+/// Queues every file of `targets` for a full resync after events may have been lost.
+fn queue_all(targets: &[(PathBuf, &str)], queues: &mut [HashMap<PathBuf, Instant>]) {
+    for path in target_files(targets, None) {
+        let path = paths::normalize(&path);
+        if let Some(target) = target_of(targets, &path) {
+            queues[target].insert(path, Instant::now());
+        }
+    }
+}
+
+/// Every file under `dir` with the extension of one of `targets`, for a full resync after
+/// events may have been lost.
+fn target_files(targets: &[(PathBuf, &str)], dir: Option<&Path>) -> Vec<PathBuf> {
+    targets
+        .iter()
+        .filter_map(|(root, extension)| {
+            let dir = dir.unwrap_or(root);
+            glob(&format!("{}/**/*.{}", Pattern::escape(&dir.to_string_lossy()), extension)).ok()
+        })
+        .flat_map(|paths| paths.filter_map(Result::ok))
+        .collect()
+}
+
+/// The target a changed path belongs to: the deepest one containing it with a matching extension.
+fn target_of(targets: &[(PathBuf, &str)], path: &Path) -> Option<usize> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    targets
+        .iter()
+        .enumerate()
+        .filter(|(_, (dir, target))| path.starts_with(dir) && extension == Some(*target))
+        .max_by_key(|(_, (dir, _))| dir.components().count())
+        .map(|(i, _)| i)
+}
+
+/// Watches each `(directory, extension)` target until `stop` is set and hands every
+/// debounced batch of matching changed files to `on_batch` with the index of its target, plus
+/// an empty batch for a target quiet for [`RETRY_INTERVAL`], for work retried on a timer.
+///
+/// Each target has its own debounce queue, so steady events under one target never delay
+/// another's batches.
+///
+/// Targets whose root is in `dirs` are watched one directory at a time; directories created
+/// below a watched one are added as they appear.
+///
+/// If the native backend cannot start or reports an error (e.g. the inotify watch limit is
+/// exceeded), watching continues with the polling backend after resyncing every file. While
+/// polling cannot start either, no batches are handed over and it is retried every
+/// [`RETRY_INTERVAL`].
+pub fn watch_targets(
+    targets: &[(PathBuf, &str)],
+    dirs: &[PathBuf],
+    stop: &AtomicBool,
+    mut on_batch: impl FnMut(usize, Vec<PathBuf>),
+) {
+    let (tx, rx) = mpsc::channel();
+    let mut dirs = dirs.to_vec();
+    let (mut watcher, mut polling) = match start_watcher(targets, &dirs, tx.clone(), false) {
+        Ok(watcher) => (Some(watcher), false),
+        Err(e) => (polling_fallback(targets, &dirs, tx.clone(), &e), true),
+    };
+
+    let mut queues: Vec<HashMap<PathBuf, Instant>> = vec![HashMap::new(); targets.len()];
+    // When each queue's first pending event arrived, bounding how long a storm is coalesced.
+    let mut first_events: Vec<Option<Instant>> = vec![None; targets.len()];
+    let debounce_duration = Duration::from_millis(100);
+    let mut last_batches = vec![Instant::now(); targets.len()];
+    let mut last_attempt = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        if watcher.is_none() {
+            if last_attempt.elapsed() >= RETRY_INTERVAL {
+                last_attempt = Instant::now();
+                watcher = start_watcher(targets, &dirs, tx.clone(), true).ok();
+                if watcher.is_some() {
+                    eprintln!("{} Watching for changes again (polling)", "✓".bright_green());
+                    queue_all(targets, &mut queues);
+                }
+            }
+            if watcher.is_none() {
+                thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+        }
+        while let Ok(result) = rx.try_recv() {
+            let event = match result {
+                Ok(event) => {
+                    log_event("raw", serde_json::json!({ "event": format!("{:?}", event.kind), "paths": event.paths }));
+                    event
+                }
+                Err(e) if !polling => {
+                    log_event("watcher_error", serde_json::json!({ "error": e.to_string(), "fallback": "poll" }));
+                    // Replacing the watcher drops the failed one; events may have been lost.
+                    watcher = polling_fallback(targets, &dirs, tx.clone(), &e);
+                    last_attempt = Instant::now();
+                    polling = true;
+                    queue_all(targets, &mut queues);
+                    continue;
+                }
+                Err(e) => {
+                    log_event("watcher_error", serde_json::json!({ "error": e.to_string() }));
+                    continue;
+                }
+            };
+            if !dirs.is_empty() && matches!(event.kind, EventKind::Create(_)) {
+                for dir in event.paths.iter().filter(|path| path.is_dir()) {
+                    let dir = paths::normalize(dir);
+                    if !dir.parent().is_some_and(|parent| dirs.iter().any(|watched| watched == parent)) {
+                        continue;
+                    }
+                    for new_dir in paths::directories(&dir, paths::is_ignored_dir) {
+                        if paths::is_ignored_dir(&new_dir) {
+                            continue;
+                        }
+                        if watcher.as_mut().is_some_and(|w| w.watch(&new_dir, RecursiveMode::NonRecursive).is_ok()) {
+                            dirs.push(new_dir);
+                            METRICS.watches.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    // Files may have landed in it before its watch was in place.
+                    for path in target_files(targets, Some(&dir)) {
+                        let path = paths::normalize(&path);
+                        if let Some(target) = target_of(targets, &path) {
+                            queues[target].insert(path, Instant::now());
+                        }
+                    }
+                }
+            }
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                for path in event.paths {
+                    let canonical_path = paths::normalize(&path);
+                    match target_of(targets, &canonical_path) {
+                        Some(target) => {
+                            let requeued = queues[target].insert(canonical_path.clone(), Instant::now()).is_some();
+                            log_event(
+                                "queued",
+                                serde_json::json!({ "path": canonical_path, "target": target, "requeued": requeued }),
+                            );
+                        }
+                        None => log_event("ignored", serde_json::json!({ "path": canonical_path, "reason": "no matching target" })),
+                    }
+                }
+            } else {
+                log_event(
+                    "ignored",
+                    serde_json::json!({ "paths": event.paths, "reason": format!("{:?} events are not processed", event.kind) }),
+                );
+            }
+        }
+
+        for (target, queue) in queues.iter_mut().enumerate() {
+            // The whole queue is handed over once it went quiet, so files changed together are
+            // processed together, or after the coalescing window while events keep coming.
+            if queue.is_empty() {
+                first_events[target] = None;
+            } else {
+                let first_event = *first_events[target].get_or_insert_with(Instant::now);
+                let quiet = queue.values().all(|last_event_time| last_event_time.elapsed() > debounce_duration);
+                if quiet || first_event.elapsed() >= COALESCE_WINDOW {
+                    first_events[target] = None;
+                }
+            }
+            let mut paths_to_process = Vec::new();
+            if !queue.is_empty() && first_events[target].is_none() {
+                paths_to_process = queue.drain().map(|(path, _)| path).collect();
+                paths_to_process.sort();
+            }
+
+            if !paths_to_process.is_empty() || last_batches[target].elapsed() >= RETRY_INTERVAL {
+                last_batches[target] = Instant::now();
+                if !paths_to_process.is_empty() {
+                    log_event("batch", serde_json::json!({ "target": target, "paths": paths_to_process }));
+                }
+                on_batch(target, paths_to_process);
+            }
+        }
+
+        METRICS
+            .debounce_queue_depth
+            .store(queues.iter().map(HashMap::len).sum::<usize>() as u64, Ordering::Relaxed);
+
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_root_falls_back_to_polling() {
+        let dir = std::env::temp_dir().join(format!("dx-watch-fallback-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let root = paths::canonicalize(&dir).unwrap();
+        // The native backend refuses the missing root; the polling one watches the other.
+        let targets = [(root.clone(), "tsx"), (root.join("missing"), "tsx")];
+        let stop = AtomicBool::new(false);
+        let file = root.join("a.tsx");
+        let mut batches = Vec::new();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(500));
+                std::fs::write(&file, "export {};\n").unwrap();
+                for _ in 0..100 {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                stop.store(true, Ordering::Relaxed);
+            });
+            watch_targets(&targets, &[], &stop, |_, batch| {
+                if !batch.is_empty() {
+                    batches.extend(batch);
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(batches, [file]);
+    }
+}
@@ -0,0 +1,121 @@
+use glob::{glob, Pattern};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::config::CONFIG_FILE;
+use crate::paths;
+
+/// A workspace package with its own `dx.config.toml`.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    pub root: PathBuf,
+}
+
+/// Package globs from `pnpm-workspace.yaml`. Only the `packages:` list is read.
+fn pnpm_patterns(source: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') && !line.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                patterns.push(item.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+    }
+    patterns
+}
+
+/// Package globs from the `workspaces` field of `package.json` (npm, yarn and bun).
+fn package_json_patterns(source: &str) -> Vec<String> {
+    let Ok(manifest) = serde_json::from_str::<Value>(source) else {
+        return Vec::new();
+    };
+    let workspaces = match manifest.get("workspaces") {
+        Some(Value::Object(fields)) => fields.get("packages"),
+        other => other,
+    };
+    workspaces
+        .and_then(Value::as_array)
+        .map(|patterns| patterns.iter().filter_map(Value::as_str).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// The package globs declared at `root`, or `None` if it is not a workspace root.
+pub fn workspace_patterns(root: &Path) -> Option<Vec<String>> {
+    if let Ok(source) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        return Some(pnpm_patterns(&source));
+    }
+    let source = std::fs::read_to_string(root.join("package.json")).ok()?;
+    let patterns = package_json_patterns(&source);
+    (!patterns.is_empty()).then_some(patterns)
+}
+
+fn package_name(root: &Path) -> String {
+    std::fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|source| serde_json::from_str::<Value>(&source).ok())
+        .and_then(|manifest| manifest.get("name").and_then(Value::as_str).map(String::from))
+        .unwrap_or_else(|| {
+            root.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+/// Every package under the workspace at `root` that has its own `dx.config.toml`, sorted by name.
+pub fn packages(root: &Path) -> Vec<Package> {
+    let Some(patterns) = workspace_patterns(root) else {
+        return Vec::new();
+    };
+    let escaped_root = Pattern::escape(&root.to_string_lossy());
+    let excluded: Vec<_> = patterns
+        .iter()
+        .filter_map(|pattern| pattern.strip_prefix('!'))
+        .filter_map(|pattern| Pattern::new(&format!("{}/{}", escaped_root, pattern.trim_start_matches("./"))).ok())
+        .collect();
+
+    let mut packages: Vec<_> = patterns
+        .iter()
+        .filter(|pattern| !pattern.starts_with('!'))
+        .filter_map(|pattern| glob(&format!("{}/{}", escaped_root, pattern.trim_start_matches("./"))).ok())
+        .flat_map(|paths| paths.filter_map(Result::ok))
+        .filter(|dir| dir.join(CONFIG_FILE).is_file())
+        .filter(|dir| !excluded.iter().any(|pattern| pattern.matches_path(dir)))
+        .map(|dir| Package {
+            name: package_name(&dir),
+            root: dir,
+        })
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages.dedup_by(|a, b| a.root == b.root);
+    packages
+}
+
+/// Keeps packages matching any filter: a name, a name glob such as `@app/*`, or a `./path`.
+pub fn filter_packages(packages: Vec<Package>, filters: &[String]) -> Vec<Package> {
+    if filters.is_empty() {
+        return packages;
+    }
+    packages
+        .into_iter()
+        .filter(|package| {
+            filters.iter().any(|filter| {
+                if filter.starts_with("./") || filter.starts_with("../") {
+                    let wanted = PathBuf::from(filter);
+                    paths::canonicalize(wanted).ok() == paths::canonicalize(&package.root).ok()
+                } else {
+                    Pattern::new(filter).is_ok_and(|pattern| pattern.matches(&package.name))
+                }
+            })
+        })
+        .collect()
+}